@@ -0,0 +1,826 @@
+//! Conversions between Sarus types and Apache Arrow schemas.
+//!
+//! Optional types become nullable fields. What Arrow cannot represent (bounds, possible values,
+//! formats, enum names, Ids, properties...) is kept in the field metadata under `sarus:` keys,
+//! so that converting back to Sarus gives the original type.
+//! Arrow types without metadata get the natural range of their width.
+
+use super::{
+    ddl::{decimal_type, float_type, integer_type},
+    Error, Result, SARUS_DATA,
+};
+use crate::protobuf::{parse_from_str, path, print_to_string, schema, type_};
+use arrow_schema::{
+    DataType as ArrowDataType, Field, FieldRef, Fields, Schema as ArrowSchema, TimeUnit,
+    UnionFields, UnionMode,
+};
+use qrlew::data_type::DataType;
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+};
+
+/// The Sarus type of an Arrow type shared by several Sarus types (Id, Unit or string temporal types)
+pub const ARROW_TYPE: &str = "sarus:type";
+const ARROW_MIN: &str = "sarus:min";
+const ARROW_MAX: &str = "sarus:max";
+/// Possible values as a JSON list
+const ARROW_POSSIBLE_VALUES: &str = "sarus:possible_values";
+const ARROW_FORMAT: &str = "sarus:format";
+const ARROW_ENCODING: &str = "sarus:encoding";
+/// Enum names and values as a JSON list of pairs
+const ARROW_NAME_VALUES: &str = "sarus:name_values";
+const ARROW_ORDERED: &str = "sarus:ordered";
+const ARROW_UNIQUE: &str = "sarus:unique";
+/// The referred path of an Id, as JSON
+const ARROW_REFERENCE: &str = "sarus:reference";
+const ARROW_MAX_SIZE: &str = "sarus:max_size";
+/// The shape of an Array, as a JSON list
+const ARROW_SHAPE: &str = "sarus:shape";
+/// Properties of the type as a JSON object
+const ARROW_PROPERTIES: &str = "sarus:properties";
+/// Properties of the Optional wrapping a nullable field, as a JSON object
+const ARROW_OPTIONAL_PROPERTIES: &str = "sarus:optional_properties";
+/// Schema metadata
+const ARROW_SCHEMA_NAME: &str = "sarus:name";
+const ARROW_PROTECTED: &str = "sarus:protected";
+
+type Metadata = HashMap<String, String>;
+
+impl type_::Type {
+    /// The Arrow field named `name` with this type: nullable if Optional,
+    /// with the information Arrow has no equivalent for in its metadata
+    pub fn to_arrow_field(&self, name: &str) -> Result<Field> {
+        match self.type_.as_ref() {
+            Some(type_::type_::Type::Optional(optional)) => {
+                let field = optional.type_().to_arrow_field(name)?;
+                let mut metadata = field.metadata().clone();
+                if !self.properties.is_empty() {
+                    metadata.insert(
+                        ARROW_OPTIONAL_PROPERTIES.to_string(),
+                        serde_json::to_string(&self.properties).unwrap(),
+                    );
+                }
+                Ok(field.with_nullable(true).with_metadata(metadata))
+            }
+            _ => {
+                let (data_type, metadata) = arrow_type(self)?;
+                Ok(Field::new(name, data_type, false).with_metadata(metadata))
+            }
+        }
+    }
+}
+
+/// The Arrow type of a type, without its metadata
+impl<'a> TryFrom<&'a type_::Type> for ArrowDataType {
+    type Error = Error;
+
+    fn try_from(value: &'a type_::Type) -> Result<Self> {
+        Ok(value.to_arrow_field("")?.data_type().clone())
+    }
+}
+
+/// Nullable Arrow fields become Optional types
+impl<'a> TryFrom<&'a Field> for type_::Type {
+    type Error = Error;
+
+    fn try_from(value: &'a Field) -> Result<Self> {
+        let metadata = value.metadata();
+        let type_ = sarus_type(value.data_type(), metadata).map_err(|err| {
+            Error::other(format!(
+                "Cannot convert Arrow field `{}`: {}",
+                value.name(),
+                err
+            ))
+        })?;
+        if value.is_nullable() && !matches!(value.data_type(), ArrowDataType::Null) {
+            let mut optional = type_::type_::Optional::new();
+            optional.set_type(type_);
+            let mut type_ = type_::Type::new();
+            type_.set_name("Optional".to_string());
+            type_.set_optional(optional);
+            if let Some(properties) = metadata.get(ARROW_OPTIONAL_PROPERTIES) {
+                type_.properties = parse_json(ARROW_OPTIONAL_PROPERTIES, properties)?;
+            }
+            Ok(type_)
+        } else {
+            Ok(type_)
+        }
+    }
+}
+
+/// The type of the values of an Arrow type, with the natural range of its width
+impl<'a> TryFrom<&'a ArrowDataType> for type_::Type {
+    type Error = Error;
+
+    fn try_from(value: &'a ArrowDataType) -> Result<Self> {
+        sarus_type(value, &Metadata::new())
+    }
+}
+
+/// An Arrow schema with a field per field of the schema Struct, or a single `sarus_data` field
+impl<'a> TryFrom<&'a schema::Schema> for ArrowSchema {
+    type Error = Error;
+
+    fn try_from(value: &'a schema::Schema) -> Result<Self> {
+        let fields = match value.type_().type_.as_ref() {
+            Some(type_::type_::Type::Struct(s)) => s
+                .fields
+                .iter()
+                .map(|f| f.type_().to_arrow_field(f.name()))
+                .collect::<Result<Vec<_>>>()?,
+            _ => vec![value.type_().to_arrow_field(SARUS_DATA)?],
+        };
+        let mut metadata = Metadata::new();
+        metadata.insert(ARROW_SCHEMA_NAME.to_string(), value.name().to_string());
+        if !value.properties.is_empty() {
+            metadata.insert(
+                ARROW_PROPERTIES.to_string(),
+                serde_json::to_string(&value.properties).unwrap(),
+            );
+        }
+        if value.protected.is_some() {
+            metadata.insert(
+                ARROW_PROTECTED.to_string(),
+                print_to_string(value.protected()).map_err(Error::other)?,
+            );
+        }
+        Ok(ArrowSchema::new_with_metadata(fields, metadata))
+    }
+}
+
+/// A schema whose type is a Struct with a field per Arrow field
+impl<'a> TryFrom<&'a ArrowSchema> for schema::Schema {
+    type Error = Error;
+
+    fn try_from(value: &'a ArrowSchema) -> Result<Self> {
+        let metadata = value.metadata();
+        let mut struct_ = type_::type_::Struct::new();
+        for field in value.fields().iter() {
+            let mut struct_field = type_::type_::struct_::Field::new();
+            struct_field.set_name(field.name().to_string());
+            struct_field.set_type(field.as_ref().try_into()?);
+            struct_.fields.push(struct_field);
+        }
+        let mut type_ = type_::Type::new();
+        type_.set_name("Struct".to_string());
+        type_.set_struct(struct_);
+        let mut schema = schema::Schema::new();
+        schema.set_name(metadata.get(ARROW_SCHEMA_NAME).cloned().unwrap_or_default());
+        schema.set_type(type_);
+        if let Some(properties) = metadata.get(ARROW_PROPERTIES) {
+            schema.properties = parse_json(ARROW_PROPERTIES, properties)?;
+        }
+        if let Some(protected) = metadata.get(ARROW_PROTECTED) {
+            schema.set_protected(parse_from_str::<path::Path>(protected)?);
+        }
+        Ok(schema)
+    }
+}
+
+fn parse_json<T: serde::de::DeserializeOwned>(key: &str, value: &str) -> Result<T> {
+    serde_json::from_str(value)
+        .map_err(|err| Error::parsing_error(format!("Arrow metadata {} ({})", key, err)))
+}
+
+/// The `sarus:` metadata of a type, also used by the Avro and Parquet schemas
+pub(super) fn sarus_metadata(type_: &type_::Type) -> Result<BTreeMap<String, String>> {
+    Ok(arrow_type(type_)?.1.into_iter().collect())
+}
+
+/// The Arrow type of a type and the metadata keeping what Arrow cannot represent
+fn arrow_type(type_: &type_::Type) -> Result<(ArrowDataType, Metadata)> {
+    use type_::type_::Type as T;
+    let mut metadata = Metadata::new();
+    let mut insert = |key: &str, value: String| {
+        metadata.insert(key.to_string(), value);
+    };
+    if !type_.properties.is_empty() {
+        insert(
+            ARROW_PROPERTIES,
+            serde_json::to_string(&type_.properties).unwrap(),
+        );
+    }
+    macro_rules! insert_possible_values {
+        ($t:expr) => {
+            if !$t.possible_values.is_empty() {
+                insert(
+                    ARROW_POSSIBLE_VALUES,
+                    serde_json::to_string(&$t.possible_values).unwrap(),
+                );
+            }
+        };
+    }
+    macro_rules! insert_temporal {
+        ($t:expr) => {
+            insert(ARROW_FORMAT, $t.format.clone());
+            insert(ARROW_MIN, $t.min.clone());
+            insert(ARROW_MAX, $t.max.clone());
+            insert_possible_values!($t);
+        };
+    }
+    let data_type = match type_.type_.as_ref() {
+        None | Some(T::Null(_)) => ArrowDataType::Null,
+        Some(T::Unit(_)) => {
+            insert(ARROW_TYPE, "Unit".to_string());
+            ArrowDataType::Null
+        }
+        Some(T::Boolean(_)) => ArrowDataType::Boolean,
+        Some(T::Integer(i)) => {
+            use type_::type_::integer::Base;
+            insert(ARROW_MIN, i.min.to_string());
+            insert(ARROW_MAX, i.max.to_string());
+            insert_possible_values!(i);
+            match i.base() {
+                Base::INT8 => ArrowDataType::Int8,
+                Base::INT16 => ArrowDataType::Int16,
+                Base::INT32 => ArrowDataType::Int32,
+                Base::INT64 => ArrowDataType::Int64,
+                Base::UINT8 => ArrowDataType::UInt8,
+                Base::UINT16 => ArrowDataType::UInt16,
+                Base::UINT32 => ArrowDataType::UInt32,
+                Base::UINT64 => ArrowDataType::UInt64,
+            }
+        }
+        Some(T::Enum(e)) => {
+            use type_::type_::enum_::Base;
+            let name_values: Vec<(&str, i64)> = e
+                .name_values
+                .iter()
+                .map(|nv| (nv.name(), nv.value))
+                .collect();
+            insert(
+                ARROW_NAME_VALUES,
+                serde_json::to_string(&name_values).unwrap(),
+            );
+            insert(ARROW_ORDERED, e.ordered.to_string());
+            let key = match e.base() {
+                Base::INT8 => ArrowDataType::Int8,
+                Base::INT16 => ArrowDataType::Int16,
+                Base::INT32 => ArrowDataType::Int32,
+                Base::INT64 => ArrowDataType::Int64,
+            };
+            ArrowDataType::Dictionary(Box::new(key), Box::new(ArrowDataType::Utf8))
+        }
+        Some(T::Float(f)) => {
+            use type_::type_::float::Base;
+            insert(ARROW_MIN, f.min.to_string());
+            insert(ARROW_MAX, f.max.to_string());
+            insert_possible_values!(f);
+            match f.base() {
+                Base::FLOAT16 => ArrowDataType::Float16,
+                Base::FLOAT32 => ArrowDataType::Float32,
+                Base::FLOAT64 => ArrowDataType::Float64,
+            }
+        }
+        Some(T::Text(t)) => {
+            if !t.encoding.is_empty() {
+                insert(ARROW_ENCODING, t.encoding.clone());
+            }
+            insert_possible_values!(t);
+            ArrowDataType::Utf8
+        }
+        Some(T::Bytes(_)) => ArrowDataType::Binary,
+        Some(T::Struct(s)) => ArrowDataType::Struct(
+            s.fields
+                .iter()
+                .map(|f| f.type_().to_arrow_field(f.name()))
+                .collect::<Result<Fields>>()?,
+        ),
+        Some(T::Union(u)) => ArrowDataType::Union(
+            UnionFields::new(
+                0..i8::try_from(u.fields.len()).map_err(|_| {
+                    Error::other(format!(
+                        "Cannot convert a Union of {} fields to Arrow",
+                        u.fields.len()
+                    ))
+                })?,
+                u.fields
+                    .iter()
+                    .map(|f| f.type_().to_arrow_field(f.name()))
+                    .collect::<Result<Vec<_>>>()?,
+            ),
+            UnionMode::Sparse,
+        ),
+        Some(T::Optional(o)) => return arrow_type(o.type_()),
+        Some(T::List(l)) => {
+            insert(ARROW_MAX_SIZE, l.max_size.to_string());
+            ArrowDataType::List(Arc::new(l.type_().to_arrow_field("item")?))
+        }
+        Some(T::Array(a)) => {
+            insert(ARROW_SHAPE, serde_json::to_string(&a.shape).unwrap());
+            let item = a.type_().to_arrow_field("item")?;
+            let data_type = a.shape.iter().rev().try_fold(
+                item.data_type().clone(),
+                |data_type, size| -> Result<ArrowDataType> {
+                    let item: FieldRef = if data_type == *item.data_type() {
+                        Arc::new(item.clone())
+                    } else {
+                        Arc::new(Field::new("item", data_type, false))
+                    };
+                    Ok(ArrowDataType::FixedSizeList(
+                        item,
+                        (*size).try_into().map_err(Error::other)?,
+                    ))
+                },
+            )?;
+            if a.shape.is_empty() {
+                return Err(Error::other(
+                    "Cannot convert an Array without shape to Arrow",
+                ));
+            }
+            data_type
+        }
+        Some(T::Datetime(d)) => {
+            use type_::type_::datetime::Base;
+            insert_temporal!(d);
+            match d.base() {
+                Base::INT64_NS => ArrowDataType::Timestamp(TimeUnit::Nanosecond, None),
+                Base::INT64_MS => ArrowDataType::Timestamp(TimeUnit::Millisecond, None),
+                Base::STRING => {
+                    insert(ARROW_TYPE, "Datetime".to_string());
+                    ArrowDataType::Utf8
+                }
+            }
+        }
+        Some(T::Date(d)) => {
+            use type_::type_::date::Base;
+            insert_temporal!(d);
+            match d.base() {
+                Base::INT32 => ArrowDataType::Date32,
+                Base::STRING => {
+                    insert(ARROW_TYPE, "Date".to_string());
+                    ArrowDataType::Utf8
+                }
+            }
+        }
+        Some(T::Time(t)) => {
+            use type_::type_::time::Base;
+            insert_temporal!(t);
+            match t.base() {
+                Base::INT64_NS => ArrowDataType::Time64(TimeUnit::Nanosecond),
+                Base::INT64_US => ArrowDataType::Time64(TimeUnit::Microsecond),
+                Base::INT32_MS => ArrowDataType::Time32(TimeUnit::Millisecond),
+                Base::STRING => {
+                    insert(ARROW_TYPE, "Time".to_string());
+                    ArrowDataType::Utf8
+                }
+            }
+        }
+        Some(T::Duration(d)) => {
+            insert(ARROW_MIN, d.min.to_string());
+            insert(ARROW_MAX, d.max.to_string());
+            insert_possible_values!(d);
+            ArrowDataType::Duration(time_unit(&d.unit)?)
+        }
+        Some(T::Id(i)) => {
+            use type_::type_::id::Base;
+            insert(ARROW_TYPE, "Id".to_string());
+            insert(ARROW_UNIQUE, i.unique.to_string());
+            if let Some(reference) = i.reference.as_ref() {
+                insert(
+                    ARROW_REFERENCE,
+                    print_to_string(reference).map_err(Error::other)?,
+                );
+            }
+            match i.base() {
+                Base::INT8 => ArrowDataType::Int8,
+                Base::INT16 => ArrowDataType::Int16,
+                Base::INT32 => ArrowDataType::Int32,
+                Base::INT64 => ArrowDataType::Int64,
+                Base::STRING => ArrowDataType::Utf8,
+                Base::BYTES => ArrowDataType::Binary,
+            }
+        }
+        Some(T::Constrained(_)) | Some(T::Hypothesis(_)) => {
+            return Err(Error::other(format!(
+                "Cannot convert a {} type to Arrow",
+                type_.name()
+            )))
+        }
+    };
+    Ok((data_type, metadata))
+}
+
+fn time_unit(unit: &str) -> Result<TimeUnit> {
+    match unit {
+        "s" => Ok(TimeUnit::Second),
+        "ms" => Ok(TimeUnit::Millisecond),
+        "us" => Ok(TimeUnit::Microsecond),
+        "ns" => Ok(TimeUnit::Nanosecond),
+        _ => Err(Error::other(format!("Unknown duration unit {}", unit))),
+    }
+}
+
+/// The Sarus type of an Arrow type and its metadata
+fn sarus_type(data_type: &ArrowDataType, metadata: &Metadata) -> Result<type_::Type> {
+    use type_::type_::{float, integer};
+    let marker = metadata.get(ARROW_TYPE).map(String::as_str);
+    let from_data_type = |data_type: DataType| type_::Type::try_from(&data_type);
+    let mut type_ = match (data_type, marker) {
+        (ArrowDataType::Null, Some("Unit")) => from_data_type(DataType::unit())?,
+        (ArrowDataType::Null, _) => from_data_type(DataType::Null)?,
+        (ArrowDataType::Boolean, _) => from_data_type(DataType::boolean())?,
+        (_, Some("Id")) => {
+            use type_::type_::id::Base;
+            let base = match data_type {
+                ArrowDataType::Int8 => Base::INT8,
+                ArrowDataType::Int16 => Base::INT16,
+                ArrowDataType::Int32 => Base::INT32,
+                ArrowDataType::Int64 => Base::INT64,
+                ArrowDataType::Utf8 | ArrowDataType::LargeUtf8 => Base::STRING,
+                ArrowDataType::Binary | ArrowDataType::LargeBinary => Base::BYTES,
+                _ => return Err(Error::other(format!("{} cannot be an Id", data_type))),
+            };
+            let mut id = type_::type_::Id::new();
+            id.set_base(base);
+            id.unique = metadata.get(ARROW_UNIQUE).is_some_and(|u| u == "true");
+            if let Some(reference) = metadata.get(ARROW_REFERENCE) {
+                id.set_reference(parse_from_str(reference)?);
+            }
+            let mut type_ = type_::Type::new();
+            type_.set_name("Id".to_string());
+            type_.set_id(id);
+            type_
+        }
+        (ArrowDataType::Utf8 | ArrowDataType::LargeUtf8, Some("Date")) => {
+            let mut type_ = from_data_type(DataType::date())?;
+            type_.mut_date().set_base(type_::type_::date::Base::STRING);
+            type_
+        }
+        (ArrowDataType::Utf8 | ArrowDataType::LargeUtf8, Some("Time")) => {
+            let mut type_ = from_data_type(DataType::time())?;
+            type_.mut_time().set_base(type_::type_::time::Base::STRING);
+            type_
+        }
+        (ArrowDataType::Utf8 | ArrowDataType::LargeUtf8, Some("Datetime")) => {
+            let mut type_ = from_data_type(DataType::date_time())?;
+            type_
+                .mut_datetime()
+                .set_base(type_::type_::datetime::Base::STRING);
+            type_
+        }
+        (ArrowDataType::Int8, _) => {
+            integer_type(integer::Base::INT8, i8::MIN.into(), i8::MAX.into())
+        }
+        (ArrowDataType::Int16, _) => {
+            integer_type(integer::Base::INT16, i16::MIN.into(), i16::MAX.into())
+        }
+        (ArrowDataType::Int32, _) => {
+            integer_type(integer::Base::INT32, i32::MIN.into(), i32::MAX.into())
+        }
+        (ArrowDataType::Int64, _) => integer_type(integer::Base::INT64, i64::MIN, i64::MAX),
+        (ArrowDataType::UInt8, _) => integer_type(integer::Base::UINT8, 0, u8::MAX.into()),
+        (ArrowDataType::UInt16, _) => integer_type(integer::Base::UINT16, 0, u16::MAX.into()),
+        (ArrowDataType::UInt32, _) => integer_type(integer::Base::UINT32, 0, u32::MAX.into()),
+        (ArrowDataType::UInt64, _) => integer_type(integer::Base::UINT64, 0, i64::MAX),
+        (ArrowDataType::Float16, _) => float_type(float::Base::FLOAT16, -65504., 65504.),
+        (ArrowDataType::Float32, _) => {
+            float_type(float::Base::FLOAT32, f32::MIN.into(), f32::MAX.into())
+        }
+        (ArrowDataType::Float64, _) => float_type(float::Base::FLOAT64, f64::MIN, f64::MAX),
+        (ArrowDataType::Decimal128(precision, scale), _)
+        | (ArrowDataType::Decimal256(precision, scale), _) => {
+            decimal_type(Some((*precision).into()), (*scale).max(0) as u64)
+        }
+        (ArrowDataType::Utf8 | ArrowDataType::LargeUtf8 | ArrowDataType::Utf8View, _) => {
+            from_data_type(DataType::text())?
+        }
+        (
+            ArrowDataType::Binary
+            | ArrowDataType::LargeBinary
+            | ArrowDataType::BinaryView
+            | ArrowDataType::FixedSizeBinary(_),
+            _,
+        ) => from_data_type(DataType::bytes())?,
+        (ArrowDataType::Dictionary(key, value), _)
+            if matches!(
+                value.as_ref(),
+                ArrowDataType::Utf8 | ArrowDataType::LargeUtf8
+            ) =>
+        {
+            use type_::type_::enum_::Base;
+            let mut enum_ = type_::type_::Enum::new();
+            enum_.set_base(match key.as_ref() {
+                ArrowDataType::Int8 => Base::INT8,
+                ArrowDataType::Int16 | ArrowDataType::UInt8 => Base::INT16,
+                ArrowDataType::Int32 | ArrowDataType::UInt16 => Base::INT32,
+                _ => Base::INT64,
+            });
+            enum_.ordered = metadata.get(ARROW_ORDERED).is_some_and(|o| o == "true");
+            if let Some(name_values) = metadata.get(ARROW_NAME_VALUES) {
+                let name_values: Vec<(String, i64)> = parse_json(ARROW_NAME_VALUES, name_values)?;
+                enum_.name_values = name_values
+                    .into_iter()
+                    .map(|(name, value)| {
+                        let mut name_value = type_::type_::enum_::NameValue::new();
+                        name_value.set_name(name);
+                        name_value.set_value(value);
+                        name_value
+                    })
+                    .collect();
+            }
+            let mut type_ = type_::Type::new();
+            type_.set_name("Enum".to_string());
+            type_.set_enum(enum_);
+            type_
+        }
+        // Other dictionaries only encode the values
+        (ArrowDataType::Dictionary(_, value), _) => return sarus_type(value, metadata),
+        (ArrowDataType::Date32 | ArrowDataType::Date64, _) => from_data_type(DataType::date())?,
+        (ArrowDataType::Timestamp(unit, _), _) => {
+            use type_::type_::datetime::Base;
+            let mut type_ = from_data_type(DataType::date_time())?;
+            type_.mut_datetime().set_base(match unit {
+                // Datetimes have no microsecond base
+                TimeUnit::Nanosecond | TimeUnit::Microsecond => Base::INT64_NS,
+                TimeUnit::Millisecond | TimeUnit::Second => Base::INT64_MS,
+            });
+            type_
+        }
+        (ArrowDataType::Time32(_) | ArrowDataType::Time64(_), _) => {
+            use type_::type_::time::Base;
+            let mut type_ = from_data_type(DataType::time())?;
+            type_.mut_time().set_base(match data_type {
+                ArrowDataType::Time64(TimeUnit::Nanosecond) => Base::INT64_NS,
+                ArrowDataType::Time64(_) => Base::INT64_US,
+                _ => Base::INT32_MS,
+            });
+            type_
+        }
+        (ArrowDataType::Duration(unit), _) => {
+            let mut duration = type_::type_::Duration::new();
+            duration.set_unit(
+                match unit {
+                    TimeUnit::Second => "s",
+                    TimeUnit::Millisecond => "ms",
+                    TimeUnit::Microsecond => "us",
+                    TimeUnit::Nanosecond => "ns",
+                }
+                .to_string(),
+            );
+            duration.set_min(i64::MIN);
+            duration.set_max(i64::MAX);
+            let mut type_ = type_::Type::new();
+            type_.set_name("Duration".to_string());
+            type_.set_duration(duration);
+            type_
+        }
+        (ArrowDataType::Struct(fields), _) => {
+            let mut struct_ = type_::type_::Struct::new();
+            for field in fields.iter() {
+                let mut struct_field = type_::type_::struct_::Field::new();
+                struct_field.set_name(field.name().to_string());
+                struct_field.set_type(field.as_ref().try_into()?);
+                struct_.fields.push(struct_field);
+            }
+            let mut type_ = type_::Type::new();
+            type_.set_name("Struct".to_string());
+            type_.set_struct(struct_);
+            type_
+        }
+        (ArrowDataType::Union(fields, _), _) => {
+            let mut union = type_::type_::Union::new();
+            for (_, field) in fields.iter() {
+                let mut union_field = type_::type_::union::Field::new();
+                union_field.set_name(field.name().to_string());
+                union_field.set_type(field.as_ref().try_into()?);
+                union.fields.push(union_field);
+            }
+            let mut type_ = type_::Type::new();
+            type_.set_name("Union".to_string());
+            type_.set_union(union);
+            type_
+        }
+        (ArrowDataType::List(item) | ArrowDataType::LargeList(item), _) => {
+            let mut list = type_::type_::List::new();
+            list.set_type(item.as_ref().try_into()?);
+            list.set_max_size(match data_type {
+                ArrowDataType::List(_) => i32::MAX.into(),
+                _ => i64::MAX,
+            });
+            let mut type_ = type_::Type::new();
+            type_.set_name("List".to_string());
+            type_.set_list(list);
+            type_
+        }
+        (ArrowDataType::FixedSizeList(item, size), _) => {
+            // The dimensions of an Array are nested fixed size lists
+            let shape: Vec<i64> = match metadata.get(ARROW_SHAPE) {
+                Some(shape) => parse_json(ARROW_SHAPE, shape)?,
+                None => vec![(*size).into()],
+            };
+            let mut item = item;
+            for _ in 1..shape.len() {
+                item = match item.data_type() {
+                    ArrowDataType::FixedSizeList(inner, _) => inner,
+                    _ => return Err(Error::other("The Array shape does not match its type")),
+                }
+            }
+            let mut array = type_::type_::Array::new();
+            array.set_type(item.as_ref().try_into()?);
+            array.shape = shape;
+            let mut type_ = type_::Type::new();
+            type_.set_name("Array".to_string());
+            type_.set_array(array);
+            type_
+        }
+        _ => {
+            return Err(Error::other(format!(
+                "Cannot convert Arrow type {} to a Sarus type",
+                data_type
+            )))
+        }
+    };
+    apply_metadata(&mut type_, metadata)?;
+    Ok(type_)
+}
+
+/// Restore the information kept in the metadata of an Arrow field
+fn apply_metadata(type_: &mut type_::Type, metadata: &Metadata) -> Result<()> {
+    use type_::type_::Type as T;
+    if let Some(properties) = metadata.get(ARROW_PROPERTIES) {
+        type_.properties = parse_json(ARROW_PROPERTIES, properties)?;
+    }
+    let get = |key: &str| metadata.get(key);
+    let parse = |key: &str| -> Result<Option<i64>> {
+        get(key)
+            .map(|value| value.parse().map_err(|_| Error::parsing_error(value)))
+            .transpose()
+    };
+    let parse_float = |key: &str| -> Result<Option<f64>> {
+        get(key)
+            .map(|value| value.parse().map_err(|_| Error::parsing_error(value)))
+            .transpose()
+    };
+    macro_rules! apply_possible_values {
+        ($t:expr) => {
+            if let Some(possible_values) = get(ARROW_POSSIBLE_VALUES) {
+                $t.possible_values = parse_json(ARROW_POSSIBLE_VALUES, possible_values)?;
+            }
+        };
+    }
+    macro_rules! apply_temporal {
+        ($t:expr) => {
+            if let Some(format) = get(ARROW_FORMAT) {
+                $t.format = format.clone();
+            }
+            if let Some(min) = get(ARROW_MIN) {
+                $t.min = min.clone();
+            }
+            if let Some(max) = get(ARROW_MAX) {
+                $t.max = max.clone();
+            }
+            apply_possible_values!($t);
+        };
+    }
+    match type_.type_.as_mut() {
+        Some(T::Integer(i)) => {
+            i.min = parse(ARROW_MIN)?.unwrap_or(i.min);
+            i.max = parse(ARROW_MAX)?.unwrap_or(i.max);
+            apply_possible_values!(i);
+        }
+        Some(T::Float(f)) => {
+            f.min = parse_float(ARROW_MIN)?.unwrap_or(f.min);
+            f.max = parse_float(ARROW_MAX)?.unwrap_or(f.max);
+            apply_possible_values!(f);
+        }
+        Some(T::Duration(d)) => {
+            d.min = parse(ARROW_MIN)?.unwrap_or(d.min);
+            d.max = parse(ARROW_MAX)?.unwrap_or(d.max);
+            apply_possible_values!(d);
+        }
+        Some(T::Text(t)) => {
+            if let Some(encoding) = get(ARROW_ENCODING) {
+                t.encoding = encoding.clone();
+            }
+            apply_possible_values!(t);
+        }
+        Some(T::Date(d)) => {
+            apply_temporal!(d);
+        }
+        Some(T::Time(t)) => {
+            apply_temporal!(t);
+        }
+        Some(T::Datetime(d)) => {
+            apply_temporal!(d);
+        }
+        Some(T::List(l)) => {
+            l.max_size = parse(ARROW_MAX_SIZE)?.unwrap_or(l.max_size);
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    const SCHEMA: &str = r#"
+    {
+        "name": "a",
+        "type": {"name": "Struct", "struct": {"fields": [
+            {"name": "sarus_data", "type": {"name": "Union", "union": {"fields": [
+                {"name": "users", "type": {"name": "Struct", "struct": {"fields": [
+                    {"name": "id", "type": {"name": "Integer", "properties": {"_CONSTRAINT_": "_PRIMARY_KEY_"},
+                        "integer": {"base": "UINT16", "min": "0", "max": "1000"}}},
+                    {"name": "name", "type": {"name": "Text", "text": {"encoding": "UTF-8", "possible_values": ["Alice", "Bob"]}}},
+                    {"name": "age", "type": {"name": "Optional", "optional": {"type": {"name": "Float", "float": {"base": "FLOAT32", "min": 0, "max": 200.5}}}}},
+                    {"name": "status", "type": {"name": "Enum", "enum": {"base": "INT8", "ordered": true,
+                        "name_values": [{"name": "active", "value": "0"}, {"name": "closed", "value": "1"}]}}},
+                    {"name": "tags", "type": {"name": "List", "list": {"type": {"name": "Text", "text": {}}, "max_size": "3"}}},
+                    {"name": "embedding", "type": {"name": "Array", "array": {"type": {"name": "Float", "float": {"min": -1, "max": 1}}, "shape": ["2", "3"]}}}
+                ]}}},
+                {"name": "visits", "type": {"name": "Struct", "struct": {"fields": [
+                    {"name": "day", "type": {"name": "Date", "date": {"format": "%Y-%m-%d", "min": "2020-01-01", "max": "2030-01-01"}}},
+                    {"name": "at", "type": {"name": "Datetime", "datetime": {"format": "%Y-%m-%d %H:%M:%S", "min": "2020-01-01 00:00:00", "max": "2030-01-01 00:00:00", "base": "INT64_MS"}}},
+                    {"name": "length", "type": {"name": "Duration", "duration": {"unit": "ms", "min": "0", "max": "3600000"}}}
+                ]}}}
+            ]}}},
+            {"name": "sarus_is_public", "type": {"name": "Boolean", "boolean": {}}},
+            {"name": "sarus_privacy_unit", "type": {"name": "Optional", "optional": {"type": {"name": "Id", "id": {"base": "STRING", "unique": true}}}}},
+            {"name": "sarus_weights", "type": {"name": "Float", "float": {"min": 0, "max": 1}}}
+        ]}}
+    }
+    "#;
+
+    #[test]
+    fn test_arrow_schema() -> Result<()> {
+        let schema: schema::Schema = parse_from_str(SCHEMA)?;
+        let arrow_schema = ArrowSchema::try_from(&schema)?;
+        println!("{:#?}", arrow_schema);
+        assert_eq!(arrow_schema.fields().len(), 4);
+        let privacy_unit = arrow_schema.field_with_name("sarus_privacy_unit")?;
+        assert!(privacy_unit.is_nullable());
+        assert_eq!(privacy_unit.data_type(), &ArrowDataType::Utf8);
+        let users = match arrow_schema.field_with_name(SARUS_DATA)?.data_type() {
+            ArrowDataType::Union(fields, _) => fields.iter().next().unwrap().1.clone(),
+            _ => panic!("Expected a Union"),
+        };
+        let ArrowDataType::Struct(fields) = users.data_type() else {
+            panic!("Expected a Struct")
+        };
+        assert_eq!(fields[0].data_type(), &ArrowDataType::UInt16);
+        assert_eq!(fields[0].metadata()[ARROW_MAX], "1000");
+        assert_eq!(fields[2].data_type(), &ArrowDataType::Float32);
+        assert!(fields[2].is_nullable());
+        assert_eq!(
+            fields[3].data_type(),
+            &ArrowDataType::Dictionary(
+                Box::new(ArrowDataType::Int8),
+                Box::new(ArrowDataType::Utf8)
+            )
+        );
+        // The round trip keeps the whole schema
+        assert_eq!(schema::Schema::try_from(&arrow_schema)?, schema);
+        // Arrow type ids only fit Unions of up to 127 fields
+        let union = |size: usize| -> Result<type_::Type> {
+            let fields: Vec<String> = (0..size)
+                .map(|i| {
+                    format!(
+                        r#"{{"name": "f{}", "type": {{"name": "Null", "null": {{}}}}}}"#,
+                        i
+                    )
+                })
+                .collect();
+            Ok(parse_from_str(&format!(
+                r#"{{"name": "Union", "union": {{"fields": [{}]}}}}"#,
+                fields.join(", ")
+            ))?)
+        };
+        assert!(union(127)?.to_arrow_field("u").is_ok());
+        assert!(union(128)?.to_arrow_field("u").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_arrow() -> Result<()> {
+        let type_: type_::Type = (&ArrowDataType::Int16).try_into()?;
+        assert_eq!((type_.integer().min, type_.integer().max), (-32768, 32767));
+        let field = Field::new(
+            "at",
+            ArrowDataType::Timestamp(TimeUnit::Nanosecond, None),
+            true,
+        );
+        let type_: type_::Type = (&field).try_into()?;
+        assert_eq!(
+            type_.optional().type_().datetime().base(),
+            type_::type_::datetime::Base::INT64_NS
+        );
+        let type_: type_::Type = (&ArrowDataType::Duration(TimeUnit::Second)).try_into()?;
+        assert_eq!(type_.duration().unit, "s");
+        let type_: type_::Type = (&ArrowDataType::Dictionary(
+            Box::new(ArrowDataType::Int32),
+            Box::new(ArrowDataType::Utf8),
+        ))
+            .try_into()?;
+        assert_eq!(type_.enum_().base(), type_::type_::enum_::Base::INT32);
+        assert!(type_::Type::try_from(&ArrowDataType::Interval(
+            arrow_schema::IntervalUnit::DayTime
+        ))
+        .is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,287 @@
+//! Avro record schemas of the tables of a dataset.
+//!
+//! Optional types are unions with `null`, temporal types use Avro logical types, durations are
+//! `long` with their `sarus:unit` and Enums are Avro enums. Bounds and possible values are kept as custom attributes
+//! with the `sarus:` keys of the Arrow metadata.
+
+use super::{arrow::sarus_metadata, Dataset, Error, Result};
+use crate::protobuf::type_;
+use itertools::Itertools;
+use qrlew::expr::identifier::Identifier;
+use serde_json::{json, Map, Value};
+
+impl Dataset {
+    /// An Avro record schema per table, with the admin columns
+    pub fn to_avro_schemas(&self) -> Result<Vec<(Identifier, Value)>> {
+        self.table_columns()
+            .into_iter()
+            .map(|(identifier, columns)| {
+                let (name, namespace) = identifier.split_last()?;
+                let name = avro_name(&name);
+                let fields = columns
+                    .into_iter()
+                    .map(|(column, type_)| avro_field(&name, column, type_))
+                    .collect::<Result<Vec<_>>>()?;
+                let mut schema = json!({"type": "record", "name": name, "fields": fields});
+                if !namespace.is_empty() {
+                    schema["namespace"] = json!(namespace
+                        .iter()
+                        .map(|label| avro_name(label))
+                        .collect::<Vec<_>>()
+                        .join("."));
+                }
+                Ok((identifier, schema))
+            })
+            .collect()
+    }
+}
+
+/// A valid Avro name: letters, digits and underscores, not starting with a digit
+fn avro_name(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    match name.chars().next() {
+        Some(c) if !c.is_ascii_digit() => name,
+        _ => format!("_{}", name),
+    }
+}
+
+/// The Avro field of a column, named types being prefixed with the name of the enclosing record
+fn avro_field(record: &str, name: &str, type_: &type_::Type) -> Result<Value> {
+    let mut field = json!({
+        "name": avro_name(name),
+        "type": avro_type(&format!("{}_{}", record, avro_name(name)), type_)?,
+    });
+    if avro_name(name) != name {
+        field["aliases"] = json!([name]);
+    }
+    if matches!(type_.type_, Some(type_::type_::Type::Optional(_))) {
+        field["default"] = Value::Null;
+    }
+    Ok(field)
+}
+
+/// The Avro schema of the values of a type
+fn avro_type(name: &str, type_: &type_::Type) -> Result<Value> {
+    use type_::type_::Type as T;
+    let mut schema = match type_.type_.as_ref() {
+        None | Some(T::Null(_)) | Some(T::Unit(_)) => json!({"type": "null"}),
+        Some(T::Boolean(_)) => json!({"type": "boolean"}),
+        Some(T::Integer(i)) => {
+            use type_::type_::integer::Base;
+            match i.base() {
+                Base::INT8 | Base::INT16 | Base::INT32 | Base::UINT8 | Base::UINT16 => {
+                    json!({"type": "int"})
+                }
+                Base::INT64 | Base::UINT32 | Base::UINT64 => json!({"type": "long"}),
+            }
+        }
+        Some(T::Enum(e)) => {
+            let symbols: Vec<String> = e
+                .name_values
+                .iter()
+                .map(|nv| avro_name(nv.name()))
+                .collect();
+            if let Some(symbol) = symbols.iter().duplicates().next() {
+                return Err(Error::other(format!(
+                    "The Enum {} has several values named {} in Avro",
+                    name, symbol
+                )));
+            }
+            json!({"type": "enum", "name": name, "symbols": symbols})
+        }
+        Some(T::Float(f)) => match f.base() {
+            type_::type_::float::Base::FLOAT64 => json!({"type": "double"}),
+            _ => json!({"type": "float"}),
+        },
+        Some(T::Text(_)) => json!({"type": "string"}),
+        Some(T::Bytes(_)) => json!({"type": "bytes"}),
+        Some(T::Struct(s)) => json!({
+            "type": "record",
+            "name": name,
+            "fields": s
+                .fields
+                .iter()
+                .map(|f| avro_field(name, f.name(), f.type_()))
+                .collect::<Result<Vec<_>>>()?,
+        }),
+        // A union of records with a single field named after the variant
+        Some(T::Union(u)) => {
+            return u
+                .fields
+                .iter()
+                .map(|f| {
+                    let variant = format!("{}_{}", name, avro_name(f.name()));
+                    Ok(json!({
+                        "type": "record",
+                        "name": variant,
+                        "fields": [avro_field(&variant, f.name(), f.type_())?],
+                    }))
+                })
+                .collect::<Result<Vec<_>>>()
+                .map(Value::Array)
+        }
+        // Avro unions cannot be nested, null is added to the branches of an optional union
+        Some(T::Optional(o)) => {
+            return Ok(match avro_type(name, o.type_())? {
+                Value::Array(branches) if branches.contains(&json!("null")) => {
+                    Value::Array(branches)
+                }
+                Value::Array(branches) => {
+                    Value::Array([json!("null")].into_iter().chain(branches).collect())
+                }
+                schema => json!(["null", schema]),
+            })
+        }
+        Some(T::List(l)) => json!({"type": "array", "items": avro_type(name, l.type_())?}),
+        Some(T::Array(a)) => a
+            .shape
+            .iter()
+            .try_fold(avro_type(name, a.type_())?, |items, _| -> Result<Value> {
+                Ok(json!({"type": "array", "items": items}))
+            })?,
+        Some(T::Date(d)) => match d.base() {
+            type_::type_::date::Base::INT32 => json!({"type": "int", "logicalType": "date"}),
+            type_::type_::date::Base::STRING => json!({"type": "string"}),
+        },
+        Some(T::Time(t)) => {
+            use type_::type_::time::Base;
+            match t.base() {
+                Base::INT32_MS => json!({"type": "int", "logicalType": "time-millis"}),
+                Base::INT64_US => json!({"type": "long", "logicalType": "time-micros"}),
+                // Avro has no nanosecond time
+                Base::INT64_NS => json!({"type": "long"}),
+                Base::STRING => json!({"type": "string"}),
+            }
+        }
+        Some(T::Datetime(d)) => {
+            use type_::type_::datetime::Base;
+            match d.base() {
+                Base::INT64_MS => json!({"type": "long", "logicalType": "timestamp-millis"}),
+                Base::INT64_NS => json!({"type": "long", "logicalType": "timestamp-nanos"}),
+                Base::STRING => json!({"type": "string"}),
+            }
+        }
+        // The Avro duration is a months, days and milliseconds triple, the unit is kept instead
+        Some(T::Duration(d)) => json!({"type": "long", "sarus:unit": d.unit}),
+        Some(T::Id(i)) => {
+            use type_::type_::id::Base;
+            match i.base() {
+                Base::INT8 | Base::INT16 | Base::INT32 => json!({"type": "int"}),
+                Base::INT64 => json!({"type": "long"}),
+                Base::STRING => json!({"type": "string"}),
+                Base::BYTES => json!({"type": "bytes"}),
+            }
+        }
+        Some(T::Constrained(c)) => return avro_type(name, c.type_()),
+        Some(T::Hypothesis(_)) => {
+            return Err(Error::other(format!(
+                "Cannot convert the Hypothesis type of {} to Avro",
+                name
+            )))
+        }
+    };
+    if let Value::Object(schema) = &mut schema {
+        schema.extend(
+            sarus_metadata(type_)?
+                .into_iter()
+                .map(|(key, value)| (key, Value::String(value)))
+                .collect::<Map<_, _>>(),
+        );
+    }
+    Ok(schema)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protobuf::parse_from_str;
+    use anyhow::Result;
+
+    const SCHEMA: &str = r#"
+    {
+        "name": "shop",
+        "type": {"name": "Struct", "struct": {"fields": [
+            {"name": "sarus_data", "type": {"name": "Union", "union": {"fields": [
+                {"name": "users", "type": {"name": "Struct", "struct": {"fields": [
+                    {"name": "id", "type": {"name": "Integer", "integer": {"base": "INT32", "min": "0", "max": "1000"}}},
+                    {"name": "name", "type": {"name": "Text", "text": {"encoding": "UTF-8", "possible_values": ["Alice", "Bob"]}}},
+                    {"name": "age", "type": {"name": "Optional", "optional": {"type": {"name": "Float", "float": {"min": 0, "max": 120}}}}},
+                    {"name": "status", "type": {"name": "Enum", "enum": {"base": "INT8",
+                        "name_values": [{"name": "active", "value": "0"}, {"name": "on hold", "value": "1"}]}}}
+                ]}}},
+                {"name": "visits", "type": {"name": "Struct", "struct": {"fields": [
+                    {"name": "day", "type": {"name": "Date", "date": {"format": "%Y-%m-%d", "min": "2020-01-01", "max": "2030-01-01", "base": "INT32"}}},
+                    {"name": "at", "type": {"name": "Datetime", "datetime": {"format": "%Y-%m-%d %H:%M:%S", "min": "2020-01-01 00:00:00", "max": "2030-01-01 00:00:00", "base": "INT64_MS"}}},
+                    {"name": "length", "type": {"name": "Duration", "duration": {"unit": "ms", "min": "0", "max": "3600000"}}}
+                ]}}}
+            ]}}},
+            {"name": "sarus_is_public", "type": {"name": "Boolean", "boolean": {}}},
+            {"name": "sarus_privacy_unit", "type": {"name": "Optional", "optional": {"type": {"name": "Id", "id": {"base": "STRING"}}}}},
+            {"name": "sarus_weights", "type": {"name": "Float", "float": {"min": 0, "max": 1}}}
+        ]}}
+    }
+    "#;
+
+    #[test]
+    fn test_to_avro_schemas() -> Result<()> {
+        let dataset = Dataset::new(parse_from_str("{}")?, parse_from_str(SCHEMA)?, None);
+        let schemas = dataset.to_avro_schemas()?;
+        for (identifier, schema) in &schemas {
+            println!("{}\n{}", identifier, serde_json::to_string_pretty(schema)?);
+        }
+        assert_eq!(schemas.len(), 2);
+        let (identifier, users) = &schemas[0];
+        assert_eq!(identifier, &Identifier::from(["shop", "users"]));
+        assert_eq!(users["name"], "users");
+        assert_eq!(users["namespace"], "shop");
+        assert_eq!(
+            users["fields"][0]["type"],
+            json!({"type": "int", "sarus:min": "0", "sarus:max": "1000"})
+        );
+        assert_eq!(
+            users["fields"][1]["type"]["sarus:possible_values"],
+            r#"["Alice","Bob"]"#
+        );
+        assert_eq!(users["fields"][2]["type"][0], "null");
+        assert_eq!(users["fields"][2]["default"], Value::Null);
+        assert_eq!(
+            users["fields"][3]["type"]["symbols"],
+            json!(["active", "on_hold"])
+        );
+        assert_eq!(users["fields"][4]["name"], "sarus_is_public");
+        let visits = &schemas[1].1;
+        assert_eq!(visits["fields"][0]["type"]["logicalType"], "date");
+        assert_eq!(visits["fields"][0]["type"]["sarus:min"], "2020-01-01");
+        assert_eq!(
+            visits["fields"][1]["type"]["logicalType"],
+            "timestamp-millis"
+        );
+        assert_eq!(visits["fields"][2]["type"]["type"], "long");
+        assert_eq!(visits["fields"][2]["type"]["sarus:unit"], "ms");
+        assert!(visits["fields"][2]["type"].get("logicalType").is_none());
+        // Optional unions are flattened, Avro unions cannot be nested
+        let optional: type_::Type = parse_from_str(
+            r#"{"name": "Optional", "optional": {"type": {"name": "Optional", "optional": {"type":
+                {"name": "Union", "union": {"fields": [
+                    {"name": "a", "type": {"name": "Boolean", "boolean": {}}},
+                    {"name": "b", "type": {"name": "Text", "text": {"encoding": "UTF-8"}}}
+                ]}}
+            }}}}"#,
+        )?;
+        let schema = avro_type("t", &optional)?;
+        assert_eq!(schema.as_array().map(Vec::len), Some(3));
+        assert_eq!(schema[0], "null");
+        assert_eq!(schema[2]["name"], "t_b");
+        // Enum values colliding once made valid Avro names are an error
+        let enum_: type_::Type = parse_from_str(
+            r#"{"name": "Enum", "enum": {"base": "INT8", "name_values": [
+                {"name": "on hold", "value": "0"}, {"name": "on_hold", "value": "1"}
+            ]}}"#,
+        )?;
+        assert!(avro_type("t", &enum_).is_err());
+        Ok(())
+    }
+}
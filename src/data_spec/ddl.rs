@@ -0,0 +1,1182 @@
+//! SQL DDL generation: `CREATE SCHEMA` and `CREATE TABLE` statements creating empty tables
+//! with the structure of the relations of a dataset, e.g. to test compiled queries on a mock database.
+//! And its counterpart: datasets built from `CREATE TABLE` scripts.
+//!
+//! Tables are named after the path of their relation, as rendered in the compiled queries.
+//! Foreign keys are only emitted when the referred column is unique, as most engines require it.
+
+use super::{
+    table_structs, Dataset, Error, ForeignKey, Result, CONSTRAINT, CONSTRAINT_PRIMARY_KEY,
+    CONSTRAINT_UNIQUE, FOREIGN_KEYS, SARUS_DATA,
+};
+use crate::protobuf::{dataset, schema, transform::transform::SQLDialect, type_};
+use qrlew::{
+    ast::{self, AlterTableOperation, ColumnOption, Ident, ObjectName, Statement, TableConstraint},
+    data_type::DataType,
+    dialect as sql_dialect,
+    expr::identifier::Identifier,
+    parser::Parser,
+    relation::{Constraint, Relation, Variant as _},
+};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// The SQL type of a column, before its translation into a dialect
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ColumnType {
+    Boolean,
+    SmallInt,
+    Int,
+    BigInt,
+    Real,
+    Double,
+    Text,
+    Bytes,
+    Date,
+    Time,
+    Datetime,
+    Duration,
+    Json,
+}
+
+impl ColumnType {
+    /// The column type of a `type_::Type`, and whether it is nullable
+    fn new(type_: &type_::Type) -> (ColumnType, bool) {
+        use type_::type_::Type as T;
+        match type_.type_.as_ref() {
+            Some(T::Optional(o)) => (ColumnType::new(o.type_()).0, true),
+            Some(T::Constrained(c)) => ColumnType::new(c.type_()),
+            Some(T::Hypothesis(h)) => h
+                .types
+                .first()
+                .map(|t| ColumnType::new(t.type_()))
+                .unwrap_or((ColumnType::Json, true)),
+            Some(T::Null(_)) | None => (ColumnType::Boolean, true),
+            Some(T::Unit(_)) | Some(T::Boolean(_)) => (ColumnType::Boolean, false),
+            Some(T::Integer(i)) => {
+                use type_::type_::integer::Base;
+                let column_type = match i.base() {
+                    Base::INT8 | Base::INT16 | Base::UINT8 => ColumnType::SmallInt,
+                    Base::INT32 | Base::UINT16 => ColumnType::Int,
+                    Base::INT64 | Base::UINT32 | Base::UINT64 => ColumnType::BigInt,
+                };
+                (column_type, false)
+            }
+            Some(T::Float(f)) => {
+                use type_::type_::float::Base;
+                let column_type = match f.base() {
+                    Base::FLOAT16 | Base::FLOAT32 => ColumnType::Real,
+                    Base::FLOAT64 => ColumnType::Double,
+                };
+                (column_type, false)
+            }
+            Some(T::Id(i)) => {
+                use type_::type_::id::Base;
+                let column_type = match i.base() {
+                    Base::INT8 | Base::INT16 => ColumnType::SmallInt,
+                    Base::INT32 => ColumnType::Int,
+                    Base::INT64 => ColumnType::BigInt,
+                    Base::STRING => ColumnType::Text,
+                    Base::BYTES => ColumnType::Bytes,
+                };
+                (column_type, false)
+            }
+            Some(T::Enum(_)) | Some(T::Text(_)) => (ColumnType::Text, false),
+            Some(T::Bytes(_)) => (ColumnType::Bytes, false),
+            Some(T::Date(_)) => (ColumnType::Date, false),
+            Some(T::Time(_)) => (ColumnType::Time, false),
+            Some(T::Datetime(_)) => (ColumnType::Datetime, false),
+            Some(T::Duration(_)) => (ColumnType::Duration, false),
+            Some(T::Struct(_)) | Some(T::Union(_)) | Some(T::List(_)) | Some(T::Array(_)) => {
+                (ColumnType::Json, false)
+            }
+        }
+    }
+
+    /// The native type in `dialect`, `key` columns must be indexable
+    fn sql(&self, dialect: SQLDialect, key: bool) -> &'static str {
+        use SQLDialect as D;
+        match (self, dialect) {
+            (ColumnType::Boolean, D::SQL_SERVER) => "BIT",
+            (ColumnType::Boolean, D::BIG_QUERY) => "BOOL",
+            (ColumnType::Boolean, D::ORACLE) => "NUMBER(1)",
+            (ColumnType::Boolean, _) => "BOOLEAN",
+            (ColumnType::SmallInt, D::BIG_QUERY) => "INT64",
+            (ColumnType::SmallInt, D::ORACLE) => "NUMBER(5)",
+            (ColumnType::SmallInt, D::SQLLITE) => "INTEGER",
+            (ColumnType::SmallInt, _) => "SMALLINT",
+            (ColumnType::Int, D::BIG_QUERY) => "INT64",
+            (ColumnType::Int, D::ORACLE) => "NUMBER(10)",
+            (ColumnType::Int, D::HIVE) => "INT",
+            (ColumnType::Int, _) => "INTEGER",
+            (ColumnType::BigInt, D::BIG_QUERY) => "INT64",
+            (ColumnType::BigInt, D::ORACLE) => "NUMBER(19)",
+            (ColumnType::BigInt, D::SQLLITE) => "INTEGER",
+            (ColumnType::BigInt, _) => "BIGINT",
+            (ColumnType::Real, D::BIG_QUERY) => "FLOAT64",
+            (ColumnType::Real, D::ORACLE) => "BINARY_FLOAT",
+            (ColumnType::Real, D::MY_SQL) | (ColumnType::Real, D::HIVE) => "FLOAT",
+            (ColumnType::Real, _) => "REAL",
+            (ColumnType::Double, D::BIG_QUERY) => "FLOAT64",
+            (ColumnType::Double, D::ORACLE) => "BINARY_DOUBLE",
+            (ColumnType::Double, D::MY_SQL) | (ColumnType::Double, D::HIVE) => "DOUBLE",
+            (ColumnType::Double, D::SQL_SERVER) => "FLOAT",
+            (ColumnType::Double, D::SQLLITE) => "REAL",
+            (ColumnType::Double, _) => "DOUBLE PRECISION",
+            (ColumnType::Text, D::MY_SQL) if key => "VARCHAR(255)",
+            (ColumnType::Text, D::MY_SQL) => "TEXT",
+            (ColumnType::Text, D::SQL_SERVER) if key => "NVARCHAR(450)",
+            (ColumnType::Text, D::SQL_SERVER) => "NVARCHAR(MAX)",
+            (ColumnType::Text, D::BIG_QUERY) | (ColumnType::Text, D::HIVE) => "STRING",
+            (ColumnType::Text, D::ORACLE) => "VARCHAR2(4000)",
+            (ColumnType::Text, D::REDSHIFT) => "VARCHAR(MAX)",
+            (ColumnType::Text, _) => "TEXT",
+            (ColumnType::Bytes, D::MY_SQL) if key => "VARBINARY(255)",
+            (ColumnType::Bytes, D::SQL_SERVER) if key => "VARBINARY(900)",
+            (ColumnType::Bytes, D::MY_SQL) => "BLOB",
+            (ColumnType::Bytes, D::SQL_SERVER) => "VARBINARY(MAX)",
+            (ColumnType::Bytes, D::BIG_QUERY) => "BYTES",
+            (ColumnType::Bytes, D::SQLLITE) | (ColumnType::Bytes, D::ORACLE) => "BLOB",
+            (ColumnType::Bytes, D::REDSHIFT) => "VARBYTE",
+            (ColumnType::Bytes, D::HIVE) => "BINARY",
+            (ColumnType::Bytes, _) => "BYTEA",
+            (ColumnType::Date, D::SQLLITE) => "TEXT",
+            (ColumnType::Date, _) => "DATE",
+            (ColumnType::Time, D::SQLLITE) | (ColumnType::Time, D::HIVE) => "STRING",
+            (ColumnType::Time, D::ORACLE) => "INTERVAL DAY TO SECOND",
+            (ColumnType::Time, _) => "TIME",
+            (ColumnType::Datetime, D::MY_SQL) | (ColumnType::Datetime, D::BIG_QUERY) => "DATETIME",
+            (ColumnType::Datetime, D::SQL_SERVER) => "DATETIME2",
+            (ColumnType::Datetime, D::SQLLITE) => "TEXT",
+            (ColumnType::Datetime, _) => "TIMESTAMP",
+            (ColumnType::Duration, D::POSTGRES) | (ColumnType::Duration, D::BIG_QUERY) => {
+                "INTERVAL"
+            }
+            (ColumnType::Duration, D::ORACLE) => "INTERVAL DAY TO SECOND",
+            (ColumnType::Duration, D::SQLLITE) => "INTEGER",
+            (ColumnType::Duration, _) => "BIGINT",
+            (ColumnType::Json, D::POSTGRES) => "JSONB",
+            (ColumnType::Json, D::MY_SQL) | (ColumnType::Json, D::BIG_QUERY) => "JSON",
+            (ColumnType::Json, D::SQL_SERVER) => "NVARCHAR(MAX)",
+            (ColumnType::Json, D::ORACLE) => "CLOB",
+            (ColumnType::Json, D::REDSHIFT) => "SUPER",
+            (ColumnType::Json, D::HIVE) => "STRING",
+            (ColumnType::Json, _) => "TEXT",
+        }
+    }
+}
+
+/// Quote an identifier the way `dialect` does
+fn quote(dialect: SQLDialect, name: &str) -> String {
+    match dialect {
+        SQLDialect::MY_SQL | SQLDialect::BIG_QUERY | SQLDialect::HIVE => {
+            format!("`{}`", name.replace('`', "``"))
+        }
+        SQLDialect::SQL_SERVER => format!("[{}]", name.replace(']', "]]")),
+        _ => format!("\"{}\"", name.replace('"', "\"\"")),
+    }
+}
+
+/// Quote and join the components of a qualified name
+fn qualified_name(dialect: SQLDialect, path: &[String]) -> String {
+    path.iter()
+        .map(|name| quote(dialect, name))
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// The statement creating the schema containing the tables with `prefix`
+fn create_schema(dialect: SQLDialect, prefix: &[String]) -> String {
+    let name = qualified_name(dialect, prefix);
+    match dialect {
+        // SQLite has no schemas but attached databases
+        SQLDialect::SQLLITE => format!("ATTACH DATABASE ':memory:' AS {}", name),
+        SQLDialect::SQL_SERVER => format!("CREATE SCHEMA {}", name),
+        SQLDialect::ORACLE => format!("CREATE USER {} NO AUTHENTICATION", name),
+        _ => format!("CREATE SCHEMA IF NOT EXISTS {}", name),
+    }
+}
+
+/// The name of a dataset built from tables without a common schema
+const DDL_SCHEMA_NAME: &str = "ddl";
+
+/// A column of a table to create
+struct Column {
+    name: String,
+    column_type: ColumnType,
+    nullable: bool,
+    constraint: Option<Constraint>,
+}
+
+/// A table to create, named after the path of its relation
+struct TableDefinition {
+    path: Vec<String>,
+    columns: Vec<Column>,
+}
+
+impl Dataset {
+    /// The `CREATE SCHEMA` and `CREATE TABLE` statements creating the relations of the dataset in `dialect`.
+    /// Foreign keys are added once all the tables are created, except in SQLite where they are inlined.
+    /// Hive tables are created without foreign keys.
+    pub fn to_ddl(&self, dialect: SQLDialect) -> String {
+        let mut tables = self.table_definitions();
+        let foreign_keys = self.sql_foreign_keys(&tables);
+        // Foreign key columns take the type of the column they refer to
+        for (table, column, referred_table, referred_column) in &foreign_keys {
+            let referred_type = tables
+                .values()
+                .find(|t| &t.path == referred_table)
+                .and_then(|t| t.columns.iter().find(|c| &c.name == referred_column))
+                .map(|c| c.column_type);
+            let column = tables
+                .values_mut()
+                .find(|t| &t.path == table)
+                .and_then(|t| t.columns.iter_mut().find(|c| &c.name == column));
+            if let (Some(referred_type), Some(column)) = (referred_type, column) {
+                column.column_type = referred_type;
+            }
+        }
+        let keys: BTreeSet<(&[String], &str)> = foreign_keys
+            .iter()
+            .flat_map(|(table, column, referred_table, referred_column)| {
+                [
+                    (table.as_slice(), column.as_str()),
+                    (referred_table.as_slice(), referred_column.as_str()),
+                ]
+            })
+            .collect();
+        let mut statements: Vec<String> = tables
+            .values()
+            .filter_map(|table| table.path.split_last().map(|(_, prefix)| prefix))
+            .filter(|prefix| !prefix.is_empty())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .map(|prefix| create_schema(dialect, prefix))
+            .collect();
+        for table in tables.values() {
+            let name = qualified_name(dialect, &table.path);
+            let mut definitions: Vec<String> = table
+                .columns
+                .iter()
+                .map(|column| {
+                    let key = column.constraint.is_some()
+                        || keys.contains(&(table.path.as_slice(), column.name.as_str()));
+                    let mut definition = format!(
+                        "{} {}",
+                        quote(dialect, &column.name),
+                        column.column_type.sql(dialect, key)
+                    );
+                    if !column.nullable {
+                        definition.push_str(" NOT NULL");
+                    }
+                    // BigQuery and Hive do not support unique constraints
+                    if column.constraint == Some(Constraint::Unique)
+                        && !matches!(dialect, SQLDialect::BIG_QUERY | SQLDialect::HIVE)
+                    {
+                        definition.push_str(" UNIQUE");
+                    }
+                    definition
+                })
+                .collect();
+            let primary_key: Vec<String> = table
+                .columns
+                .iter()
+                .filter(|column| column.constraint == Some(Constraint::PrimaryKey))
+                .map(|column| quote(dialect, &column.name))
+                .collect();
+            if !primary_key.is_empty() {
+                definitions.push(format!(
+                    "PRIMARY KEY ({}){}",
+                    primary_key.join(", "),
+                    not_enforced(dialect)
+                ));
+            }
+            if dialect == SQLDialect::SQLLITE {
+                definitions.extend(
+                    foreign_keys
+                        .iter()
+                        .filter(|(pointing, _, _, _)| pointing == &table.path)
+                        .map(|(_, column, referred_table, referred_column)| {
+                            foreign_key(dialect, column, referred_table, referred_column)
+                        }),
+                );
+            }
+            statements.push(format!(
+                "CREATE TABLE {} (\n  {}\n)",
+                name,
+                definitions.join(",\n  ")
+            ));
+        }
+        // Hive only accepts named foreign keys, they are left out
+        if !matches!(dialect, SQLDialect::SQLLITE | SQLDialect::HIVE) {
+            statements.extend(foreign_keys.iter().map(
+                |(table, column, referred_table, referred_column)| {
+                    format!(
+                        "ALTER TABLE {} ADD {}",
+                        qualified_name(dialect, table),
+                        foreign_key(dialect, column, referred_table, referred_column)
+                    )
+                },
+            ));
+        }
+        statements
+            .into_iter()
+            .map(|statement| format!("{};\n", statement))
+            .collect()
+    }
+
+    /// The tables to create indexed by the identifier of their relation in `relations()`
+    fn table_definitions(&self) -> BTreeMap<Vec<String>, TableDefinition> {
+        let relations = self.relations();
+        let admin_types: BTreeMap<&str, &type_::Type> =
+            self.admin_names_and_types().into_iter().collect();
+        table_structs(self.schema_type_data(), None)
+            .into_iter()
+            .filter_map(|(identifier, schema_struct, _)| {
+                let identifier = self.full_path(&identifier);
+                let table = match relations.get(&identifier)?.as_ref() {
+                    Relation::Table(table) => table,
+                    _ => return None,
+                };
+                let columns = table
+                    .schema()
+                    .iter()
+                    .filter_map(|field| {
+                        let type_ = schema_struct
+                            .fields
+                            .iter()
+                            .find(|f| f.name() == field.name())
+                            .map(|f| f.type_())
+                            .or_else(|| admin_types.get(field.name()).copied())?;
+                        let (column_type, nullable) = ColumnType::new(type_);
+                        Some(Column {
+                            name: field.name().to_string(),
+                            column_type,
+                            nullable,
+                            constraint: field.constraint(),
+                        })
+                    })
+                    .collect();
+                Some((
+                    identifier.to_vec(),
+                    TableDefinition {
+                        path: table.path().to_vec(),
+                        columns,
+                    },
+                ))
+            })
+            .collect()
+    }
+
+    /// The foreign keys of the schema property and of the Id references
+    /// as (table path, column, referred table path, referred column).
+    /// Foreign keys referring to a column without key constraint are dropped.
+    fn sql_foreign_keys(
+        &self,
+        tables: &BTreeMap<Vec<String>, TableDefinition>,
+    ) -> Vec<(Vec<String>, String, Vec<String>, String)> {
+        let is_key = |table: &TableDefinition, name: &str| {
+            table.columns.iter().any(|column| {
+                column.name == name
+                    && matches!(
+                        column.constraint,
+                        Some(Constraint::Unique) | Some(Constraint::PrimaryKey)
+                    )
+            })
+        };
+        let mut foreign_keys: Vec<(Vec<String>, String, Vec<String>, String)> = Vec::new();
+        for foreign_key in self.foreign_keys().into_iter().chain(self.id_references()) {
+            if let (Some(table), Some(referred_table)) = (
+                tables.get(&foreign_key.relation.to_vec()),
+                tables.get(&foreign_key.referred_relation.to_vec()),
+            ) {
+                let sql_foreign_key = (
+                    table.path.clone(),
+                    foreign_key.column,
+                    referred_table.path.clone(),
+                    foreign_key.referred_column,
+                );
+                if is_key(referred_table, &sql_foreign_key.3)
+                    && !foreign_keys.contains(&sql_foreign_key)
+                {
+                    foreign_keys.push(sql_foreign_key);
+                }
+            }
+        }
+        foreign_keys
+    }
+
+    /// The foreign keys defined by the references of the Id columns
+    pub(super) fn id_references(&self) -> Vec<ForeignKey> {
+        table_structs(self.schema_type_data(), None)
+            .into_iter()
+            .flat_map(|(identifier, schema_struct, _)| {
+                let relation = self.full_path(&identifier);
+                schema_struct.fields.iter().filter_map(move |field| {
+                    let (referred_relation, referred_column) =
+                        self.path_column(id_reference(field.type_())?)?;
+                    Some(ForeignKey {
+                        relation: relation.clone(),
+                        column: field.name().to_string(),
+                        referred_relation,
+                        referred_column,
+                    })
+                })
+            })
+            .collect()
+    }
+
+    /// The relation, as in `relations`, and the column designated by a path
+    /// from the root of the schema or of its data part
+    pub(super) fn path_column(
+        &self,
+        path: &crate::protobuf::path::Path,
+    ) -> Option<(Identifier, String)> {
+        let labels: Vec<String> = Identifier::try_from(path)
+            .ok()?
+            .iter()
+            .filter(|label| !label.is_empty())
+            .cloned()
+            .collect();
+        let labels = labels
+            .strip_prefix(&[SARUS_DATA.to_string()])
+            .unwrap_or(&labels);
+        let (column, table) = labels.split_last()?;
+        Some((self.full_path(table), column.clone()))
+    }
+}
+
+/// The reference of an Id column, if any
+fn id_reference(type_: &type_::Type) -> Option<&crate::protobuf::path::Path> {
+    use type_::type_::Type as T;
+    match type_.type_.as_ref()? {
+        T::Optional(o) => id_reference(o.type_()),
+        T::Constrained(c) => id_reference(c.type_()),
+        T::Id(i) => i.reference.as_ref(),
+        _ => None,
+    }
+}
+
+/// A table constraint creating a foreign key.
+/// SQLite only refers to tables of the same database, by their bare name.
+fn foreign_key(
+    dialect: SQLDialect,
+    column: &str,
+    referred_table: &[String],
+    referred_column: &str,
+) -> String {
+    let referred_table = match (dialect, referred_table.split_last()) {
+        (SQLDialect::SQLLITE, Some((table, _))) => quote(dialect, table),
+        _ => qualified_name(dialect, referred_table),
+    };
+    format!(
+        "FOREIGN KEY ({}) REFERENCES {} ({}){}",
+        quote(dialect, column),
+        referred_table,
+        quote(dialect, referred_column),
+        not_enforced(dialect)
+    )
+}
+
+/// Key constraints are informational only in BigQuery and Hive
+fn not_enforced(dialect: SQLDialect) -> &'static str {
+    match dialect {
+        SQLDialect::BIG_QUERY => " NOT ENFORCED",
+        SQLDialect::HIVE => " DISABLE NOVALIDATE",
+        _ => "",
+    }
+}
+
+/// A key declared in a SQL DDL script, on the columns of a table
+enum Key {
+    Primary(Vec<String>),
+    Unique(Vec<String>),
+    Foreign {
+        columns: Vec<String>,
+        referred_table: Vec<String>,
+        referred_columns: Vec<String>,
+    },
+}
+
+impl Key {
+    fn new(constraint: &TableConstraint) -> Option<Key> {
+        let names = |idents: &[Ident]| idents.iter().map(|i| i.value.clone()).collect();
+        match constraint {
+            TableConstraint::PrimaryKey { columns, .. } => Some(Key::Primary(names(columns))),
+            TableConstraint::Unique { columns, .. } => Some(Key::Unique(names(columns))),
+            TableConstraint::ForeignKey {
+                columns,
+                foreign_table,
+                referred_columns,
+                ..
+            } => Some(Key::Foreign {
+                columns: names(columns),
+                referred_table: object_path(foreign_table),
+                referred_columns: names(referred_columns),
+            }),
+            _ => None,
+        }
+    }
+
+    /// The key declared by a column option
+    fn from_column(column: &str, option: &ColumnOption) -> Option<Key> {
+        match option {
+            ColumnOption::Unique {
+                is_primary: true, ..
+            } => Some(Key::Primary(vec![column.to_string()])),
+            ColumnOption::Unique { .. } => Some(Key::Unique(vec![column.to_string()])),
+            ColumnOption::ForeignKey {
+                foreign_table,
+                referred_columns,
+                ..
+            } => Some(Key::Foreign {
+                columns: vec![column.to_string()],
+                referred_table: object_path(foreign_table),
+                referred_columns: referred_columns.iter().map(|i| i.value.clone()).collect(),
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// A column parsed from a SQL DDL script
+struct ParsedColumn {
+    name: String,
+    type_: type_::Type,
+    not_null: bool,
+    constraint: Option<&'static str>,
+}
+
+/// A table parsed from a SQL DDL script
+struct ParsedTable {
+    path: Vec<String>,
+    columns: Vec<ParsedColumn>,
+}
+
+impl ParsedTable {
+    fn column(&mut self, name: &str) -> Result<&mut ParsedColumn> {
+        let path = self.path.join(".");
+        self.columns
+            .iter_mut()
+            .find(|column| column.name == name)
+            .ok_or_else(|| Error::other(format!("Table `{}` has no column `{}`", path, name)))
+    }
+
+    /// The path of a column relative to the data type
+    fn column_path(&self, name: &str) -> Vec<String> {
+        self.path
+            .iter()
+            .cloned()
+            .chain([name.to_string()])
+            .collect()
+    }
+
+    /// The Struct type of the table, nullable columns are wrapped in an Optional
+    fn struct_type(self) -> type_::Type {
+        let mut struct_ = type_::type_::Struct::new();
+        for column in self.columns {
+            let mut type_ = column.type_;
+            if !column.not_null {
+                let mut optional = type_::type_::Optional::new();
+                optional.set_type(type_);
+                type_ = type_::Type::new();
+                type_.set_name("Optional".to_string());
+                type_.set_optional(optional);
+            }
+            if let Some(constraint) = column.constraint {
+                type_
+                    .properties
+                    .insert(CONSTRAINT.to_string(), constraint.to_string());
+            }
+            let mut field = type_::type_::struct_::Field::new();
+            field.set_name(column.name);
+            field.set_type(type_);
+            struct_.fields.push(field);
+        }
+        let mut type_ = type_::Type::new();
+        type_.set_name("Struct".to_string());
+        type_.set_struct(struct_);
+        type_
+    }
+}
+
+impl Dataset {
+    /// Build a dataset from the `CREATE TABLE` statements of a SQL script, with one Union branch per table.
+    /// Columns get the natural range of their SQL type and are Optional unless declared NOT NULL or primary key.
+    /// Single column keys, declared in `CREATE TABLE` or `ALTER TABLE` statements,
+    /// become column constraints and foreign key properties; composite keys only make their columns not null.
+    pub fn from_ddl(sql: &str, dialect: SQLDialect) -> Result<Self> {
+        let statements = Parser::parse_sql(parser_dialect(dialect).as_ref(), sql)
+            .map_err(|err| Error::parsing_error(format!("SQL DDL ({})", err)))?;
+        let mut tables: Vec<ParsedTable> = Vec::new();
+        let mut keys: Vec<(Vec<String>, Key)> = Vec::new();
+        for statement in statements.iter() {
+            match statement {
+                Statement::CreateTable {
+                    name,
+                    columns,
+                    constraints,
+                    ..
+                } => {
+                    let path = object_path(name);
+                    let mut parsed_columns = Vec::new();
+                    for column in columns {
+                        let type_ = column_type(&column.data_type, dialect).map_err(|err| {
+                            Error::other(format!(
+                                "Cannot import column `{}.{}`: {}",
+                                path.join("."),
+                                column.name.value,
+                                err
+                            ))
+                        })?;
+                        let mut not_null = false;
+                        for option in column.options.iter() {
+                            if option.option == ColumnOption::NotNull {
+                                not_null = true;
+                            }
+                            if let Some(key) = Key::from_column(&column.name.value, &option.option)
+                            {
+                                keys.push((path.clone(), key));
+                            }
+                        }
+                        parsed_columns.push(ParsedColumn {
+                            name: column.name.value.clone(),
+                            type_,
+                            not_null,
+                            constraint: None,
+                        });
+                    }
+                    keys.extend(
+                        constraints
+                            .iter()
+                            .filter_map(|constraint| Some((path.clone(), Key::new(constraint)?))),
+                    );
+                    tables.push(ParsedTable {
+                        path,
+                        columns: parsed_columns,
+                    });
+                }
+                Statement::AlterTable {
+                    name, operations, ..
+                } => keys.extend(operations.iter().filter_map(|operation| match operation {
+                    AlterTableOperation::AddConstraint(constraint) => {
+                        Some((object_path(name), Key::new(constraint)?))
+                    }
+                    _ => None,
+                })),
+                _ => {}
+            }
+        }
+        if tables.is_empty() {
+            return Err(Error::other("The SQL DDL creates no table"));
+        }
+        // Primary keys and unique columns first, as foreign keys may refer to primary keys implicitly
+        for (path, key) in keys.iter() {
+            let table = resolve_table(&mut tables, path)?;
+            match key {
+                Key::Primary(columns) => {
+                    for column in columns {
+                        table.column(column)?.not_null = true;
+                    }
+                    if let [column] = columns.as_slice() {
+                        table.column(column)?.constraint = Some(CONSTRAINT_PRIMARY_KEY);
+                    }
+                }
+                Key::Unique(columns) => {
+                    if let [column] = columns.as_slice() {
+                        let column = table.column(column)?;
+                        column.constraint = column.constraint.or(Some(CONSTRAINT_UNIQUE));
+                    }
+                }
+                Key::Foreign { .. } => {}
+            }
+        }
+        let mut foreign_keys: Vec<(Vec<String>, Vec<String>)> = Vec::new();
+        for (path, key) in keys.iter() {
+            if let Key::Foreign {
+                columns,
+                referred_table,
+                referred_columns,
+            } = key
+            {
+                let [column] = columns.as_slice() else {
+                    continue;
+                };
+                let table = resolve_table(&mut tables, path)?;
+                table.column(column)?;
+                let pointing = table.column_path(column);
+                let referred_table = resolve_table(&mut tables, referred_table)?;
+                let referred_column = match referred_columns.as_slice() {
+                    [column] => column.clone(),
+                    [] => match referred_table
+                        .columns
+                        .iter()
+                        .find(|column| column.constraint == Some(CONSTRAINT_PRIMARY_KEY))
+                    {
+                        Some(column) => column.name.clone(),
+                        None => continue,
+                    },
+                    _ => continue,
+                };
+                referred_table.column(&referred_column)?;
+                let foreign_key = (pointing, referred_table.column_path(&referred_column));
+                if !foreign_keys.contains(&foreign_key) {
+                    foreign_keys.push(foreign_key);
+                }
+            }
+        }
+        // The dataset is named after the schema of its tables if they share one,
+        // the paths of the tables and of the keys are then relative to this schema
+        let (name, shared_schema) = match tables[0].path.as_slice() {
+            [name, _, ..]
+                if tables
+                    .iter()
+                    .all(|table| table.path.len() > 1 && &table.path[0] == name) =>
+            {
+                (name.clone(), 1)
+            }
+            _ => (DDL_SCHEMA_NAME.to_string(), 0),
+        };
+        let foreign_keys: Vec<(&[String], &[String])> = foreign_keys
+            .iter()
+            .map(|(pointing, pointed)| (&pointing[shared_schema..], &pointed[shared_schema..]))
+            .collect();
+        let mut data_type = type_::Type::new();
+        data_type.set_name("Union".to_string());
+        data_type.set_union(type_::type_::Union::new());
+        for table in tables {
+            let path = table.path[shared_schema..].to_vec();
+            insert_table(&mut data_type, &path, table.struct_type());
+        }
+        let mut schema = schema::Schema::new();
+        schema.set_name(name.clone());
+        schema.set_type(data_type);
+        schema.properties.insert(
+            FOREIGN_KEYS.to_string(),
+            serde_json::to_string(&foreign_keys).unwrap(),
+        );
+        schema.check_foreign_keys()?;
+        let mut dataset = dataset::Dataset::new();
+        dataset.set_name(name);
+        Ok(Dataset::new(dataset, schema, None))
+    }
+}
+
+/// The parser of a SQL dialect
+fn parser_dialect(dialect: SQLDialect) -> Box<dyn sql_dialect::Dialect> {
+    match dialect {
+        SQLDialect::POSTGRES => Box::new(sql_dialect::PostgreSqlDialect {}),
+        SQLDialect::SQL_SERVER => Box::new(sql_dialect::MsSqlDialect {}),
+        SQLDialect::MY_SQL => Box::new(sql_dialect::MySqlDialect {}),
+        SQLDialect::SQLLITE => Box::new(sql_dialect::SQLiteDialect {}),
+        SQLDialect::BIG_QUERY => Box::new(sql_dialect::BigQueryDialect {}),
+        SQLDialect::REDSHIFT => Box::new(sql_dialect::RedshiftSqlDialect {}),
+        SQLDialect::HIVE => Box::new(sql_dialect::HiveDialect {}),
+        SQLDialect::NONE | SQLDialect::ORACLE => Box::new(sql_dialect::GenericDialect {}),
+    }
+}
+
+/// The path of a table name
+fn object_path(name: &ObjectName) -> Vec<String> {
+    name.0.iter().map(|ident| ident.value.clone()).collect()
+}
+
+/// The table with `path`, or the only one whose path ends with `path`
+fn resolve_table<'a>(
+    tables: &'a mut [ParsedTable],
+    path: &[String],
+) -> Result<&'a mut ParsedTable> {
+    let position = tables
+        .iter()
+        .position(|table| table.path == path)
+        .or_else(|| {
+            let mut matches = tables
+                .iter()
+                .enumerate()
+                .filter(|(_, table)| table.path.ends_with(path));
+            match (matches.next(), matches.next()) {
+                (Some((position, _)), None) => Some(position),
+                _ => None,
+            }
+        })
+        .ok_or_else(|| {
+            Error::other(format!(
+                "Cannot find table `{}` in the SQL DDL",
+                path.join(".")
+            ))
+        })?;
+    Ok(&mut tables[position])
+}
+
+/// Insert the type of the table at `path` in a tree of Unions
+pub(super) fn insert_table(union: &mut type_::Type, path: &[String], table: type_::Type) {
+    let (name, tail) = match path.split_first() {
+        Some(split) => split,
+        None => return,
+    };
+    let fields = &mut union.mut_union().fields;
+    if tail.is_empty() {
+        let mut field = type_::type_::union::Field::new();
+        field.set_name(name.clone());
+        field.set_type(table);
+        fields.push(field);
+        return;
+    }
+    let position = match fields.iter().position(|field| &field.name == name) {
+        Some(position) => position,
+        None => {
+            let mut type_ = type_::Type::new();
+            type_.set_name("Union".to_string());
+            type_.set_union(type_::type_::Union::new());
+            let mut field = type_::type_::union::Field::new();
+            field.set_name(name.clone());
+            field.set_type(type_);
+            fields.push(field);
+            fields.len() - 1
+        }
+    };
+    insert_table(fields[position].mut_type(), tail, table);
+}
+
+/// An Integer type with a base and its range
+pub(super) fn integer_type(base: type_::type_::integer::Base, min: i64, max: i64) -> type_::Type {
+    let mut integer = type_::type_::Integer::new();
+    integer.set_base(base);
+    integer.set_min(min);
+    integer.set_max(max);
+    let mut type_ = type_::Type::new();
+    type_.set_name("Integer".to_string());
+    type_.set_integer(integer);
+    type_
+}
+
+/// A Float type with a base and its range
+pub(super) fn float_type(base: type_::type_::float::Base, min: f64, max: f64) -> type_::Type {
+    let mut float = type_::type_::Float::new();
+    float.set_base(base);
+    float.set_min(min);
+    float.set_max(max);
+    let mut type_ = type_::Type::new();
+    type_.set_name("Float".to_string());
+    type_.set_float(float);
+    type_
+}
+
+/// The type of a DECIMAL(precision, scale): an integer if the scale is 0 or a float
+pub(super) fn decimal_type(precision: Option<u64>, scale: u64) -> type_::Type {
+    use type_::type_::{float, integer};
+    match precision {
+        Some(precision) if scale == 0 && precision <= 18 => {
+            let max = 10_i64.pow(precision as u32) - 1;
+            integer_type(integer::Base::INT64, -max, max)
+        }
+        Some(_) if scale == 0 => integer_type(integer::Base::INT64, i64::MIN, i64::MAX),
+        Some(precision) => {
+            let max = 10_f64.powi(precision as i32 - scale as i32) - 10_f64.powi(-(scale as i32));
+            float_type(float::Base::FLOAT64, -max, max)
+        }
+        None => float_type(float::Base::FLOAT64, f64::MIN, f64::MAX),
+    }
+}
+
+/// The type of a column of SQL type `data_type`, with the natural range of the type
+fn column_type(data_type: &ast::DataType, dialect: SQLDialect) -> Result<type_::Type> {
+    use ast::{DataType as D, ExactNumberInfo};
+    use type_::type_::{float, integer};
+    let int8 = || integer_type(integer::Base::INT8, i8::MIN.into(), i8::MAX.into());
+    let int16 = || integer_type(integer::Base::INT16, i16::MIN.into(), i16::MAX.into());
+    let int32 = || integer_type(integer::Base::INT32, i32::MIN.into(), i32::MAX.into());
+    let int64 = || integer_type(integer::Base::INT64, i64::MIN, i64::MAX);
+    let uint8 = || integer_type(integer::Base::UINT8, 0, u8::MAX.into());
+    let uint16 = || integer_type(integer::Base::UINT16, 0, u16::MAX.into());
+    let uint32 = || integer_type(integer::Base::UINT32, 0, u32::MAX.into());
+    let uint64 = || integer_type(integer::Base::UINT64, 0, i64::MAX);
+    let float32 = || float_type(float::Base::FLOAT32, f32::MIN.into(), f32::MAX.into());
+    let float64 = || float_type(float::Base::FLOAT64, f64::MIN, f64::MAX);
+    let from_data_type = |data_type: DataType| type_::Type::try_from(&data_type);
+    // INTEGER is 64 bits wide in SQLite, BigQuery and Oracle, REAL and FLOAT are 64 bits in MySQL and SQLite
+    let wide_integer = matches!(
+        dialect,
+        SQLDialect::SQLLITE | SQLDialect::BIG_QUERY | SQLDialect::ORACLE
+    );
+    let wide_real = matches!(dialect, SQLDialect::MY_SQL | SQLDialect::SQLLITE);
+    let wide_float = !matches!(dialect, SQLDialect::MY_SQL | SQLDialect::HIVE);
+    Ok(match data_type {
+        D::Boolean | D::Bool => from_data_type(DataType::boolean())?,
+        D::TinyInt(_) => int8(),
+        D::UnsignedTinyInt(_) => uint8(),
+        D::SmallInt(_) | D::Int2(_) => int16(),
+        D::UnsignedSmallInt(_) | D::UnsignedInt2(_) => uint16(),
+        D::MediumInt(_) => integer_type(integer::Base::INT32, -(1 << 23), (1 << 23) - 1),
+        D::UnsignedMediumInt(_) => integer_type(integer::Base::UINT32, 0, (1 << 24) - 1),
+        D::Int(_) | D::Integer(_) | D::Int4(_) if wide_integer => int64(),
+        D::Int(_) | D::Integer(_) | D::Int4(_) => int32(),
+        D::UnsignedInt(_) | D::UnsignedInteger(_) | D::UnsignedInt4(_) => uint32(),
+        D::BigInt(_) | D::Int8(_) | D::Int64 => int64(),
+        D::UnsignedBigInt(_) | D::UnsignedInt8(_) => uint64(),
+        D::Real | D::Float4 if wide_real => float64(),
+        D::Real | D::Float4 => float32(),
+        D::Float(Some(precision)) if *precision <= 24 => float32(),
+        D::Float(None) if !wide_float => float32(),
+        D::Float(_) | D::Float8 | D::Float64 | D::Double | D::DoublePrecision => float64(),
+        D::Numeric(info)
+        | D::Decimal(info)
+        | D::Dec(info)
+        | D::BigNumeric(info)
+        | D::BigDecimal(info) => match info {
+            ExactNumberInfo::None => decimal_type(None, 0),
+            ExactNumberInfo::Precision(precision) => decimal_type(Some(*precision), 0),
+            ExactNumberInfo::PrecisionAndScale(precision, scale) => {
+                decimal_type(Some(*precision), *scale)
+            }
+        },
+        D::Character(_)
+        | D::Char(_)
+        | D::CharacterVarying(_)
+        | D::CharVarying(_)
+        | D::Varchar(_)
+        | D::Nvarchar(_)
+        | D::CharacterLargeObject(_)
+        | D::CharLargeObject(_)
+        | D::Clob(_)
+        | D::Text
+        | D::String(_)
+        | D::Uuid
+        | D::JSON
+        | D::JSONB
+        | D::Set(_) => from_data_type(DataType::text())?,
+        D::Enum(values) => from_data_type(DataType::text_values(values.clone()))?,
+        D::Binary(_) | D::Varbinary(_) | D::Blob(_) | D::Bytes(_) | D::Bytea => {
+            from_data_type(DataType::bytes())?
+        }
+        D::Date => from_data_type(DataType::date())?,
+        D::Time(_, _) => from_data_type(DataType::time())?,
+        D::Datetime(_) | D::Timestamp(_, _) => from_data_type(DataType::date_time())?,
+        D::Interval => from_data_type(DataType::duration())?,
+        D::Custom(name, arguments) => {
+            let name = name
+                .0
+                .last()
+                .map(|i| i.value.to_uppercase())
+                .unwrap_or_default();
+            let argument = |i: usize| arguments.get(i).and_then(|a| a.trim().parse::<u64>().ok());
+            match name.as_str() {
+                "BIT" => from_data_type(DataType::boolean())?,
+                "INT1" => int8(),
+                "SMALLSERIAL" | "SERIAL2" => integer_type(integer::Base::INT16, 1, i16::MAX.into()),
+                "SERIAL" | "SERIAL4" => integer_type(integer::Base::INT32, 1, i32::MAX.into()),
+                "BIGSERIAL" | "SERIAL8" => integer_type(integer::Base::INT64, 1, i64::MAX),
+                "NUMBER" => decimal_type(argument(0), argument(1).unwrap_or(0)),
+                "BINARY_FLOAT" => float32(),
+                "BINARY_DOUBLE" | "MONEY" | "SMALLMONEY" => float64(),
+                "VARCHAR2" | "NVARCHAR2" | "NCHAR" | "NTEXT" | "CITEXT" | "TINYTEXT"
+                | "MEDIUMTEXT" | "LONGTEXT" | "SUPER" | "UNIQUEIDENTIFIER" => {
+                    from_data_type(DataType::text())?
+                }
+                "VARBYTE" | "RAW" | "IMAGE" | "TINYBLOB" | "MEDIUMBLOB" | "LONGBLOB" => {
+                    from_data_type(DataType::bytes())?
+                }
+                "DATETIME2" | "SMALLDATETIME" | "DATETIMEOFFSET" | "TIMESTAMPTZ" => {
+                    from_data_type(DataType::date_time())?
+                }
+                _ => return Err(Error::other(format!("unsupported type {}", data_type))),
+            }
+        }
+        _ => return Err(Error::other(format!("unsupported type {}", data_type))),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        data_spec::{CONSTRAINT_PRIMARY_KEY, CONSTRAINT_UNIQUE},
+        protobuf::parse_from_str,
+    };
+    use anyhow::Result;
+    use qrlew::data_type::DataTyped;
+
+    const SCHEMA: &str = r#"
+    {
+        "name": "a",
+        "type": {"name": "Struct", "struct": {"fields": [
+            {"name": "sarus_data", "type": {"name": "Union", "union": {"fields": [
+                {"name": "shop", "type": {"name": "Union", "union": {"fields": [
+                    {"name": "users", "type": {"name": "Struct", "struct": {"fields": [
+                        {"name": "id", "type": {"name": "Integer", "integer": {"base": "INT32", "min": "0", "max": "1000"}}},
+                        {"name": "name", "type": {"name": "Text", "text": {"encoding": "UTF-8"}}},
+                        {"name": "age", "type": {"name": "Optional", "optional": {"type": {"name": "Float", "float": {"min": 0, "max": 200}}}}}
+                    ]}}},
+                    {"name": "orders", "type": {"name": "Struct", "struct": {"fields": [
+                        {"name": "user_id", "type": {"name": "Id", "id": {"base": "INT64",
+                            "reference": {"label": "sarus_data", "paths": [{"label": "shop", "paths": [{"label": "users", "paths": [{"label": "id"}]}]}]}}}},
+                        {"name": "code", "type": {"name": "Text", "text": {"encoding": "UTF-8"}}},
+                        {"name": "date", "type": {"name": "Datetime", "datetime": {"format": "%Y-%m-%d %H:%M:%S", "min": "2020-01-01 00:00:00", "max": "2030-01-01 00:00:00"}}}
+                    ]}}}
+                ]}}}
+            ]}}},
+            {"name": "sarus_is_public", "type": {"name": "Boolean", "boolean": {}}},
+            {"name": "sarus_privacy_unit", "type": {"name": "Optional", "optional": {"type": {"name": "Id", "id": {"base": "STRING"}}}}},
+            {"name": "sarus_weights", "type": {"name": "Float", "float": {"min": 0, "max": 1}}}
+        ]}}
+    }
+    "#;
+
+    fn dataset() -> Result<Dataset> {
+        Ok(
+            Dataset::new(parse_from_str("{}")?, parse_from_str(SCHEMA)?, None)
+                .with_constraint(["shop", "users", "id"], Some(CONSTRAINT_PRIMARY_KEY))?
+                .with_constraint(["shop", "orders", "code"], Some(CONSTRAINT_UNIQUE))?,
+        )
+    }
+
+    #[test]
+    fn test_to_ddl() -> Result<()> {
+        let dataset = dataset()?;
+        let ddl = dataset.to_ddl(SQLDialect::POSTGRES);
+        println!("{}", ddl);
+        assert!(ddl.starts_with("CREATE SCHEMA IF NOT EXISTS \"shop\";\n"));
+        assert!(ddl.contains("CREATE TABLE \"shop\".\"users\" (\n  \"id\" INTEGER NOT NULL,"));
+        assert!(ddl.contains("\"age\" DOUBLE PRECISION,"));
+        assert!(ddl.contains("PRIMARY KEY (\"id\")"));
+        assert!(ddl.contains("\"code\" TEXT NOT NULL UNIQUE"));
+        assert!(ddl.contains("\"date\" TIMESTAMP NOT NULL"));
+        assert!(ddl.contains("\"sarus_privacy_unit\" TEXT,"));
+        assert!(ddl.ends_with(
+            "ALTER TABLE \"shop\".\"orders\" ADD FOREIGN KEY (\"user_id\") REFERENCES \"shop\".\"users\" (\"id\");\n"
+        ));
+        // The same foreign key from the schema properties is not repeated
+        let ddl = dataset
+            .with_foreign_key(["shop", "orders", "user_id"], ["shop", "users", "id"])?
+            .to_ddl(SQLDialect::POSTGRES);
+        assert_eq!(ddl.matches("FOREIGN KEY").count(), 1);
+        // A foreign key to a column without key constraint is left out
+        let ddl = dataset
+            .with_foreign_key(["shop", "users", "name"], ["shop", "orders", "code"])?
+            .with_constraint(["shop", "orders", "code"], None)?
+            .to_ddl(SQLDialect::POSTGRES);
+        assert_eq!(ddl.matches("FOREIGN KEY").count(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_to_ddl_dialects() -> Result<()> {
+        let dataset = dataset()?;
+        let ddl = dataset.to_ddl(SQLDialect::MY_SQL);
+        println!("{}", ddl);
+        assert!(ddl.contains("CREATE TABLE `shop`.`orders` (\n  `user_id` INTEGER NOT NULL,"));
+        assert!(ddl.contains("`code` VARCHAR(255) NOT NULL UNIQUE"));
+        assert!(ddl.contains("`name` TEXT NOT NULL"));
+        assert!(ddl.contains("`date` DATETIME NOT NULL"));
+        let ddl = dataset.to_ddl(SQLDialect::SQL_SERVER);
+        println!("{}", ddl);
+        assert!(ddl.starts_with("CREATE SCHEMA [shop];\n"));
+        assert!(ddl.contains("[sarus_is_public] BIT NOT NULL"));
+        let ddl = dataset.to_ddl(SQLDialect::BIG_QUERY);
+        println!("{}", ddl);
+        assert!(ddl.contains("`code` STRING NOT NULL,"));
+        assert!(ddl.contains("PRIMARY KEY (`id`) NOT ENFORCED"));
+        assert!(ddl.contains("REFERENCES `shop`.`users` (`id`) NOT ENFORCED;\n"));
+        let ddl = dataset.to_ddl(SQLDialect::SQLLITE);
+        println!("{}", ddl);
+        assert!(ddl.starts_with("ATTACH DATABASE ':memory:' AS \"shop\";\n"));
+        assert!(ddl.contains("FOREIGN KEY (\"user_id\") REFERENCES \"users\" (\"id\")\n);\n"));
+        assert!(!ddl.contains("ALTER TABLE"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_ddl() -> Result<()> {
+        let ddl = dataset()?.to_ddl(SQLDialect::POSTGRES);
+        let dataset = Dataset::from_ddl(&ddl, SQLDialect::POSTGRES)?;
+        println!("{}", dataset);
+        assert_eq!(dataset.schema().name(), "shop");
+        let relations = dataset.relations();
+        let users = &relations[["shop", "users"]];
+        let id = users.schema().field("id")?;
+        assert_eq!(
+            id.data_type(),
+            DataType::integer_interval(i32::MIN.into(), i32::MAX.into())
+        );
+        assert_eq!(id.constraint(), Some(Constraint::PrimaryKey));
+        assert!(matches!(
+            users.schema().field("age")?.data_type(),
+            DataType::Optional(_)
+        ));
+        let orders = &relations[["shop", "orders"]];
+        assert_eq!(
+            orders.schema().field("code")?.constraint(),
+            Some(Constraint::Unique)
+        );
+        assert_eq!(
+            orders.schema().field("user_id")?.constraint(),
+            Some(Constraint::ForeignKey)
+        );
+        assert_eq!(
+            dataset.foreign_keys(),
+            vec![ForeignKey {
+                relation: ["shop", "orders"].into(),
+                column: "user_id".to_string(),
+                referred_relation: ["shop", "users"].into(),
+                referred_column: "id".to_string(),
+            }]
+        );
+        // The generated DDL creates the same tables, relative to the shared schema
+        assert_eq!(
+            dataset.to_ddl(SQLDialect::POSTGRES),
+            ddl.replace("CREATE SCHEMA IF NOT EXISTS \"shop\";\n", "")
+                .replace("\"shop\".", "")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_ddl_mysql() -> Result<()> {
+        const DDL: &str = r#"
+        CREATE TABLE users (
+            id INT UNSIGNED PRIMARY KEY,
+            status ENUM('active', 'closed') NOT NULL,
+            score FLOAT,
+            amount DECIMAL(6, 2)
+        );
+        CREATE TABLE visits (
+            user_id INT UNSIGNED NOT NULL REFERENCES users,
+            day DATE NOT NULL,
+            at DATETIME,
+            PRIMARY KEY (user_id, day)
+        );
+        "#;
+        let dataset = Dataset::from_ddl(DDL, SQLDialect::MY_SQL)?;
+        println!("{}", dataset);
+        assert_eq!(dataset.schema().name(), DDL_SCHEMA_NAME);
+        let relations = dataset.relations();
+        let users = &relations[["ddl", "users"]];
+        assert_eq!(
+            users.schema().field("status")?.data_type(),
+            DataType::text_values(["active".to_string(), "closed".to_string()])
+        );
+        assert_eq!(
+            users.schema().field("score")?.data_type(),
+            DataType::optional(DataType::float_interval(f32::MIN.into(), f32::MAX.into()))
+        );
+        assert_eq!(
+            users.schema().field("amount")?.data_type(),
+            DataType::optional(DataType::float_interval(-9999.99, 9999.99))
+        );
+        let visits = &relations[["ddl", "visits"]];
+        // Composite keys make their columns not null
+        assert_eq!(visits.schema().field("day")?.data_type(), DataType::date());
+        assert_eq!(
+            visits.schema().field("user_id")?.constraint(),
+            Some(Constraint::ForeignKey)
+        );
+        assert_eq!(dataset.foreign_keys().len(), 1);
+        assert!(Dataset::from_ddl("CREATE TABLE t (a GEOMETRY)", SQLDialect::MY_SQL).is_err());
+        assert!(Dataset::from_ddl(
+            "CREATE TABLE t (a INT REFERENCES u (a))",
+            SQLDialect::MY_SQL
+        )
+        .is_err());
+        assert!(Dataset::from_ddl("SELECT 1", SQLDialect::MY_SQL).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,546 @@
+//! Data dictionaries of datasets, in Markdown or standalone HTML.
+//!
+//! The dictionary describes each table and column with the types, bounds and constraints of the schema.
+//! Marginal summaries are given for the columns whose statistics hold a distribution.
+
+use super::{
+    display::{
+        bounds, constraint_property, constraint_string, dotted, escape, possible_values, type_name,
+    },
+    size_range,
+    structure::size_of,
+    table_structs,
+    validate::distribution,
+    Dataset,
+};
+use crate::protobuf::{marginals, statistics, type_};
+use qrlew::relation::Variant as _;
+use std::fmt::Write as _;
+
+/// The format of a data dictionary
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DictionaryFormat {
+    Markdown,
+    /// A standalone HTML page
+    Html,
+}
+
+/// The number of most frequent values in the summary of a categorical column
+const TOP_VALUES: usize = 3;
+
+struct Column {
+    name: String,
+    type_: String,
+    nullable: bool,
+    bounds: String,
+    possible_values: String,
+    constraint: String,
+    summary: String,
+}
+
+struct Table {
+    name: String,
+    size: String,
+    columns: Vec<Column>,
+}
+
+/// The content of a dictionary, before its formatting
+struct Dictionary {
+    name: String,
+    doc: String,
+    schema: String,
+    protected_tables: Vec<String>,
+    tables: Vec<Table>,
+    admin_columns: Vec<Column>,
+    /// The referring and referred columns
+    foreign_keys: Vec<(String, String)>,
+}
+
+impl Dataset {
+    /// The data dictionary of the dataset, with the summaries of the distributions
+    /// in the Size statistics if any
+    pub fn data_dictionary(&self, format: DictionaryFormat) -> String {
+        self.dictionary(self.size_statistics()).format(format)
+    }
+
+    /// The data dictionary of the dataset, with the summaries of the marginal distributions
+    pub fn data_dictionary_with_marginals(
+        &self,
+        format: DictionaryFormat,
+        marginals: &marginals::Marginals,
+    ) -> String {
+        self.dictionary(marginals.statistics.as_ref())
+            .format(format)
+    }
+
+    fn dictionary(&self, statistics: Option<&statistics::Statistics>) -> Dictionary {
+        let schema_name = self.schema().name().to_string();
+        let relations = self.relations();
+        let foreign_keys = self.foreign_keys();
+        let tables = table_structs(self.schema_type_data(), statistics)
+            .into_iter()
+            .map(|(identifier, schema_struct, statistics)| {
+                let identifier = self.full_path(&identifier);
+                let relation = relations.get(identifier.as_ref());
+                let size = match statistics.map(size_range) {
+                    Some((min, max)) if min == max => min.to_string(),
+                    Some((min, max)) => format!("{} to {}", min, max),
+                    None => String::new(),
+                };
+                let columns = schema_struct
+                    .fields
+                    .iter()
+                    .map(|field| {
+                        let constraint = relation
+                            .and_then(|relation| relation.schema().field(field.name()).ok())
+                            .and_then(|field| field.constraint())
+                            .or_else(|| constraint_property(field.type_()));
+                        let field_statistics = statistics.and_then(|statistics| {
+                            statistics
+                                .struct_()
+                                .fields
+                                .iter()
+                                .find(|f| f.name() == field.name())
+                                .map(|f| f.statistics())
+                        });
+                        let mut column = column(field.name(), field.type_(), field_statistics);
+                        column.constraint =
+                            constraint_string(constraint, &identifier, field.name(), &foreign_keys)
+                                .unwrap_or_default();
+                        column
+                    })
+                    .collect();
+                Table {
+                    name: dotted(&identifier),
+                    size,
+                    columns,
+                }
+            })
+            .collect();
+        Dictionary {
+            name: self.dataset().name().to_string(),
+            doc: self.dataset().doc.clone(),
+            schema: schema_name,
+            protected_tables: self.protected_tables().iter().map(dotted).collect(),
+            tables,
+            admin_columns: self
+                .admin_names_and_types()
+                .into_iter()
+                .map(|(name, type_)| column(name, type_, None))
+                .collect(),
+            foreign_keys: foreign_keys
+                .iter()
+                .map(|fk| {
+                    (
+                        format!("{}.{}", dotted(&fk.relation), fk.column),
+                        format!("{}.{}", dotted(&fk.referred_relation), fk.referred_column),
+                    )
+                })
+                .collect(),
+        }
+    }
+}
+
+fn column(name: &str, type_: &type_::Type, statistics: Option<&statistics::Statistics>) -> Column {
+    let bounds = bounds(type_).map_or(String::new(), |(min, max)| format!("[{}, {}]", min, max));
+    Column {
+        name: name.to_string(),
+        type_: column_type(type_),
+        nullable: matches!(type_.type_, Some(type_::type_::Type::Optional(_))),
+        bounds,
+        possible_values: possible_values(type_).join(", "),
+        constraint: String::new(),
+        summary: statistics.map_or(String::new(), |statistics| summary(type_, statistics)),
+    }
+}
+
+/// The name of a scalar type, or the compact notation of a composite type
+fn column_type(type_: &type_::Type) -> String {
+    use type_::type_::Type as T;
+    match value_type(type_).type_.as_ref() {
+        Some(T::Struct(_)) | Some(T::Union(_)) | Some(T::List(_)) | Some(T::Array(_)) => {
+            value_type(type_).to_compact_string()
+        }
+        _ => type_name(type_),
+    }
+}
+
+/// The type of the values of an Optional type
+fn value_type(type_: &type_::Type) -> &type_::Type {
+    match type_.type_.as_ref() {
+        Some(type_::type_::Type::Optional(o)) => o.type_(),
+        _ => type_,
+    }
+}
+
+/// A short description of the marginal distribution of a column
+fn summary(type_: &type_::Type, statistics: &statistics::Statistics) -> String {
+    use statistics::distribution::Distribution as D;
+    use statistics::statistics::Statistics as S;
+    let mut parts = vec![];
+    let statistics = match statistics.statistics.as_ref() {
+        Some(S::Optional(o)) => {
+            let nulls = o.size - size_of(o.statistics.get_or_default());
+            if o.size > 0 && nulls > 0 {
+                parts.push(format!(
+                    "{}% null",
+                    number(100. * nulls as f64 / o.size as f64)
+                ));
+            }
+            o.statistics.get_or_default()
+        }
+        _ => statistics,
+    };
+    let temporal = matches!(
+        value_type(type_).type_,
+        Some(type_::type_::Type::Date(_))
+            | Some(type_::type_::Type::Time(_))
+            | Some(type_::type_::Type::Datetime(_))
+    );
+    if let Some(distribution) = statistics.statistics.as_ref().and_then(distribution) {
+        match distribution.distribution.as_ref() {
+            Some(D::Enum(e)) => {
+                let total: f64 = e.points.iter().map(|p| p.probability).sum();
+                let mut points: Vec<_> = e.points.iter().collect();
+                points.sort_by(|a, b| b.probability.total_cmp(&a.probability));
+                if total > 0. {
+                    parts.push(format!(
+                        "top: {}",
+                        points
+                            .iter()
+                            .take(TOP_VALUES)
+                            .map(|p| format!(
+                                "{} ({}%)",
+                                p.name,
+                                number(100. * p.probability / total)
+                            ))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ));
+                }
+            }
+            Some(D::Boolean(_)) => {
+                let total = distribution.total_probability();
+                if total > 0. {
+                    parts.push(format!(
+                        "{}% true",
+                        number(100. * (1. - distribution.cdf(0.) / total))
+                    ));
+                }
+            }
+            // Temporal values are encoded as numbers by the profiler
+            Some(_) if !temporal => {
+                if let (Some(min), Some(max)) = (distribution.min(), distribution.max()) {
+                    parts.push(format!("range [{}, {}]", number(min), number(max)));
+                }
+                if let Some(mean) = distribution.mean() {
+                    parts.push(format!("mean {}", number(mean)));
+                }
+                if let Some(median) = distribution.quantile(0.5) {
+                    parts.push(format!("median {}", number(median)));
+                }
+            }
+            _ => {}
+        }
+    }
+    parts.join(", ")
+}
+
+/// A number with at most 3 decimals
+fn number(x: f64) -> String {
+    let x = format!("{:.3}", x);
+    x.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+impl Column {
+    fn cells(&self, with_summary: bool) -> Vec<String> {
+        let mut cells = vec![
+            self.name.clone(),
+            self.type_.clone(),
+            if self.nullable { "yes" } else { "no" }.to_string(),
+            self.bounds.clone(),
+            self.possible_values.clone(),
+            self.constraint.clone(),
+        ];
+        if with_summary {
+            cells.push(self.summary.clone());
+        }
+        cells
+    }
+}
+
+const HEADERS: [&str; 7] = [
+    "Column",
+    "Type",
+    "Nullable",
+    "Bounds",
+    "Possible values",
+    "Constraint",
+    "Summary",
+];
+
+impl Dictionary {
+    fn format(&self, format: DictionaryFormat) -> String {
+        match format {
+            DictionaryFormat::Markdown => self.markdown(),
+            DictionaryFormat::Html => self.html(),
+        }
+    }
+
+    fn with_summary(&self) -> bool {
+        self.tables.iter().any(|table| {
+            table
+                .columns
+                .iter()
+                .any(|column| !column.summary.is_empty())
+        })
+    }
+
+    fn markdown(&self) -> String {
+        let with_summary = self.with_summary();
+        let headers = &HEADERS[..if with_summary { 7 } else { 6 }];
+        let row = |cells: Vec<String>| {
+            format!(
+                "| {} |\n",
+                cells
+                    .iter()
+                    .map(|cell| cell.replace('|', "\\|"))
+                    .collect::<Vec<_>>()
+                    .join(" | ")
+            )
+        };
+        let table = |columns: &[Column], with_summary: bool| {
+            let headers = &headers[..if with_summary { headers.len() } else { 6 }];
+            let mut table = row(headers.iter().map(|h| h.to_string()).collect());
+            table.push_str(&row(headers.iter().map(|_| "---".to_string()).collect()));
+            for column in columns {
+                let mut cells = column.cells(with_summary);
+                cells[0] = format!("`{}`", cells[0]);
+                cells[1] = format!("`{}`", cells[1]);
+                table.push_str(&row(cells));
+            }
+            table
+        };
+        let mut markdown = format!("# {}\n\n", self.name);
+        if !self.doc.is_empty() {
+            let _ = write!(markdown, "{}\n\n", self.doc);
+        }
+        let _ = writeln!(markdown, "- **Schema**: `{}`", self.schema);
+        if !self.protected_tables.is_empty() {
+            let _ = writeln!(
+                markdown,
+                "- **Protected entity**: {}",
+                self.protected_tables
+                    .iter()
+                    .map(|table| format!("`{}`", table))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+        }
+        markdown.push_str("\n## Tables\n");
+        for t in &self.tables {
+            let _ = write!(markdown, "\n### `{}`\n\n", t.name);
+            if !t.size.is_empty() {
+                let _ = write!(markdown, "Size: {} rows\n\n", t.size);
+            }
+            markdown.push_str(&table(&t.columns, with_summary));
+        }
+        if !self.admin_columns.is_empty() {
+            markdown.push_str("\n## Admin columns\n\n");
+            markdown.push_str(&table(&self.admin_columns, false));
+        }
+        if !self.foreign_keys.is_empty() {
+            markdown.push_str("\n## Key relationships\n\n| Column | References |\n| --- | --- |\n");
+            for (column, referred) in &self.foreign_keys {
+                let _ = writeln!(markdown, "| `{}` | `{}` |", column, referred);
+            }
+        }
+        markdown
+    }
+
+    fn html(&self) -> String {
+        let with_summary = self.with_summary();
+        let table = |columns: &[Column], with_summary: bool| {
+            let headers = &HEADERS[..if with_summary { 7 } else { 6 }];
+            let mut table = format!(
+                "<table>\n<tr>{}</tr>\n",
+                headers
+                    .iter()
+                    .map(|h| format!("<th>{}</th>", h))
+                    .collect::<String>()
+            );
+            for column in columns {
+                let cells = column.cells(with_summary);
+                let _ = writeln!(
+                    table,
+                    "<tr>{}</tr>",
+                    cells
+                        .iter()
+                        .enumerate()
+                        .map(|(i, cell)| if i < 2 {
+                            format!("<td><code>{}</code></td>", escape(cell))
+                        } else {
+                            format!("<td>{}</td>", escape(cell))
+                        })
+                        .collect::<String>()
+                );
+            }
+            table.push_str("</table>\n");
+            table
+        };
+        let mut html = format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>\n{}\n</style>\n</head>\n<body>\n<h1>{}</h1>\n",
+            escape(&self.name),
+            "body { font-family: sans-serif; margin: 2em; }\n\
+             table { border-collapse: collapse; margin-bottom: 1em; }\n\
+             th, td { border: 1px solid #ccc; padding: 0.3em 0.6em; text-align: left; }\n\
+             th { background: #f0f0f0; }",
+            escape(&self.name)
+        );
+        if !self.doc.is_empty() {
+            let _ = writeln!(html, "<p>{}</p>", escape(&self.doc));
+        }
+        let _ = writeln!(
+            html,
+            "<ul>\n<li><strong>Schema</strong>: <code>{}</code></li>",
+            escape(&self.schema)
+        );
+        if !self.protected_tables.is_empty() {
+            let _ = writeln!(
+                html,
+                "<li><strong>Protected entity</strong>: {}</li>",
+                self.protected_tables
+                    .iter()
+                    .map(|table| format!("<code>{}</code>", escape(table)))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+        }
+        html.push_str("</ul>\n<h2>Tables</h2>\n");
+        for t in &self.tables {
+            let _ = writeln!(html, "<h3><code>{}</code></h3>", escape(&t.name));
+            if !t.size.is_empty() {
+                let _ = writeln!(html, "<p>Size: {} rows</p>", escape(&t.size));
+            }
+            html.push_str(&table(&t.columns, with_summary));
+        }
+        if !self.admin_columns.is_empty() {
+            html.push_str("<h2>Admin columns</h2>\n");
+            html.push_str(&table(&self.admin_columns, false));
+        }
+        if !self.foreign_keys.is_empty() {
+            html.push_str(
+                "<h2>Key relationships</h2>\n<table>\n<tr><th>Column</th><th>References</th></tr>\n",
+            );
+            for (column, referred) in &self.foreign_keys {
+                let _ = writeln!(
+                    html,
+                    "<tr><td><code>{}</code></td><td><code>{}</code></td></tr>",
+                    escape(column),
+                    escape(referred)
+                );
+            }
+            html.push_str("</table>\n");
+        }
+        html.push_str("</body>\n</html>\n");
+        html
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protobuf::parse_from_str;
+    use anyhow::Result;
+
+    const SCHEMA: &str = r#"
+    {
+        "name": "shop",
+        "properties": {"foreign_keys": "[[[\"visits\",\"user_id\"],[\"users\",\"id\"]]]"},
+        "protected": {"label": "sarus_data", "paths": [{"label": "users"}]},
+        "type": {"name": "Struct", "struct": {"fields": [
+            {"name": "sarus_data", "type": {"name": "Union", "union": {"fields": [
+                {"name": "users", "type": {"name": "Struct", "struct": {"fields": [
+                    {"name": "id", "type": {"name": "Integer", "properties": {"_CONSTRAINT_": "_PRIMARY_KEY_"},
+                        "integer": {"base": "INT64", "min": "0", "max": "120"}}},
+                    {"name": "city", "type": {"name": "Text", "text": {"encoding": "UTF-8", "possible_values": ["Paris", "Lyon"]}}},
+                    {"name": "age", "type": {"name": "Optional", "optional": {"type": {"name": "Integer", "integer": {"min": "0", "max": "100"}}}}}
+                ]}}},
+                {"name": "visits", "type": {"name": "Struct", "struct": {"fields": [
+                    {"name": "user_id", "type": {"name": "Integer", "integer": {"base": "INT64", "min": "0", "max": "120"}}}
+                ]}}}
+            ]}}},
+            {"name": "sarus_is_public", "type": {"name": "Boolean", "boolean": {}}},
+            {"name": "sarus_privacy_unit", "type": {"name": "Optional", "optional": {"type": {"name": "Id", "id": {"base": "STRING"}}}}},
+            {"name": "sarus_weights", "type": {"name": "Float", "float": {"min": 0, "max": 1}}}
+        ]}}
+    }
+    "#;
+
+    const MARGINALS: &str = r#"
+    {
+        "name": "shop_marginals",
+        "statistics": {"name": "Union", "union": {"fields": [
+            {"name": "users", "statistics": {"name": "Struct", "struct": {"size": "4", "fields": [
+                {"name": "city", "statistics": {"name": "Text", "text": {"size": "4", "distribution": {"enum": {"points": [
+                    {"name": "Paris", "value": 0, "probability": 0.75},
+                    {"name": "Lyon", "value": 1, "probability": 0.25}
+                ]}}}}},
+                {"name": "age", "statistics": {"name": "Optional", "optional": {"size": "4", "statistics": {"name": "Integer",
+                    "integer": {"size": "2", "min": "0", "max": "100", "distribution": {"integer": {"points": [
+                        {"value": "20", "probability": 0.5},
+                        {"value": "40", "probability": 0.5}
+                    ]}}}}}}}
+            ]}}},
+            {"name": "visits", "statistics": {"name": "Struct", "struct": {"size": "10", "fields": []}}}
+        ]}}
+    }
+    "#;
+
+    #[test]
+    fn test_data_dictionary() -> Result<()> {
+        let dataset = Dataset::new(
+            parse_from_str(r#"{"name": "shop", "doc": "Customers | visits"}"#)?,
+            parse_from_str(SCHEMA)?,
+            None,
+        );
+        let markdown = dataset.data_dictionary(DictionaryFormat::Markdown);
+        println!("{}", markdown);
+        assert!(markdown.starts_with("# shop\n\nCustomers | visits\n"));
+        assert!(markdown.contains("- **Protected entity**: `shop.users`"));
+        assert!(markdown.contains("| `id` | `int` | no | [0, 120] |  | primary key |\n"));
+        assert!(markdown.contains(r#"| `city` | `text` | no |  | "Paris", "Lyon" |  |"#));
+        assert!(markdown.contains("| `age` | `int` | yes | [0, 100] |  |  |"));
+        assert!(markdown.contains("| `shop.visits.user_id` | `shop.users.id` |"));
+        assert!(!markdown.contains("Summary"));
+        let marginals: marginals::Marginals = parse_from_str(MARGINALS)?;
+        let markdown =
+            dataset.data_dictionary_with_marginals(DictionaryFormat::Markdown, &marginals);
+        println!("{}", markdown);
+        assert!(markdown.contains("Size: 4 rows"));
+        assert!(markdown.contains("top: Paris (75%), Lyon (25%)"));
+        assert!(markdown.contains("50% null, range [20, 40], mean 30, median 20"));
+        let html = dataset.data_dictionary_with_marginals(DictionaryFormat::Html, &marginals);
+        println!("{}", html);
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<h3><code>shop.users</code></h3>"));
+        assert!(html.contains("<td>&quot;Paris&quot;, &quot;Lyon&quot;</td>"));
+        assert!(html.ends_with("</html>\n"));
+        // No protected entity is listed when the schema has none
+        let dataset = Dataset::new(
+            parse_from_str("{}")?,
+            parse_from_str(&SCHEMA.replace(
+                r#""protected": {"label": "sarus_data", "paths": [{"label": "users"}]},"#,
+                "",
+            ))?,
+            None,
+        );
+        assert!(!dataset
+            .data_dictionary(DictionaryFormat::Markdown)
+            .contains("Protected entity"));
+        assert!(!dataset
+            .data_dictionary(DictionaryFormat::Html)
+            .contains("Protected entity"));
+        Ok(())
+    }
+}
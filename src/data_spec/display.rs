@@ -0,0 +1,417 @@
+//! Human-readable display of datasets as trees of tables and columns.
+//!
+//! Types are printed in a compact notation such as `int[0, 120]`, `text{"a","b"}` or
+//! `optional<datetime>`. Datasets are displayed without colours, `Dataset::to_colored_string`
+//! gives the same tree coloured for terminals.
+
+use super::{
+    size_range, table_structs, Dataset, ForeignKey, CONSTRAINT, CONSTRAINT_PRIMARY_KEY,
+    CONSTRAINT_UNIQUE, POSSIBLE_VALUES,
+};
+use crate::protobuf::type_;
+use colored::{ColoredString, Colorize};
+use qrlew::{
+    expr::identifier::Identifier,
+    relation::{Constraint, Variant as _},
+};
+use std::fmt;
+
+impl type_::Type {
+    /// A compact notation of the type, as `int[0, 120]`, `text{"a","b"}` or `optional<datetime>`
+    pub fn to_compact_string(&self) -> String {
+        use type_::type_::Type as T;
+        let fields = |fields: Vec<(&str, &type_::Type)>| {
+            fields
+                .into_iter()
+                .map(|(name, type_)| format!("{}: {}", name, type_.to_compact_string()))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        match self.type_.as_ref() {
+            Some(T::Struct(s)) => format!(
+                "struct{{{}}}",
+                fields(s.fields.iter().map(|f| (f.name(), f.type_())).collect())
+            ),
+            Some(T::Union(u)) => format!(
+                "union{{{}}}",
+                fields(u.fields.iter().map(|f| (f.name(), f.type_())).collect())
+            ),
+            Some(T::Optional(o)) => format!("optional<{}>", o.type_().to_compact_string()),
+            Some(T::List(l)) => format!("list<{}, {}>", l.type_().to_compact_string(), l.max_size),
+            Some(T::Array(a)) => format!(
+                "array<{}, {}>",
+                a.type_().to_compact_string(),
+                a.shape
+                    .iter()
+                    .map(|size| size.to_string())
+                    .collect::<Vec<_>>()
+                    .join("x")
+            ),
+            Some(T::Constrained(c)) => format!("constrained<{}>", c.type_().to_compact_string()),
+            Some(T::Hypothesis(h)) => format!(
+                "hypothesis<{}>",
+                h.types
+                    .iter()
+                    .map(|scored| scored.type_().to_compact_string())
+                    .collect::<Vec<_>>()
+                    .join(" | ")
+            ),
+            _ => {
+                let possible_values = possible_values(self);
+                match bounds(self) {
+                    _ if !possible_values.is_empty() => {
+                        format!("{}{{{}}}", type_name(self), possible_values.join(","))
+                    }
+                    Some((min, max)) => format!("{}[{}, {}]", type_name(self), min, max),
+                    None => type_name(self),
+                }
+            }
+        }
+    }
+}
+
+/// The name of the type without its bounds nor possible values, as `int` or `duration<ms>`
+pub(super) fn type_name(type_: &type_::Type) -> String {
+    use type_::type_::Type as T;
+    match type_.type_.as_ref() {
+        None | Some(T::Null(_)) => "null".to_string(),
+        Some(T::Unit(_)) => "unit".to_string(),
+        Some(T::Boolean(_)) => "bool".to_string(),
+        Some(T::Integer(i)) => {
+            use type_::type_::integer::Base;
+            match i.base() {
+                Base::INT64 => "int",
+                Base::INT32 => "int32",
+                Base::INT16 => "int16",
+                Base::INT8 => "int8",
+                Base::UINT64 => "uint64",
+                Base::UINT32 => "uint32",
+                Base::UINT16 => "uint16",
+                Base::UINT8 => "uint8",
+            }
+            .to_string()
+        }
+        Some(T::Enum(_)) => "enum".to_string(),
+        Some(T::Float(f)) => {
+            use type_::type_::float::Base;
+            match f.base() {
+                Base::FLOAT64 => "float",
+                Base::FLOAT32 => "float32",
+                Base::FLOAT16 => "float16",
+            }
+            .to_string()
+        }
+        Some(T::Text(_)) => "text".to_string(),
+        Some(T::Bytes(_)) => "bytes".to_string(),
+        Some(T::Date(_)) => "date".to_string(),
+        Some(T::Time(_)) => "time".to_string(),
+        Some(T::Datetime(_)) => "datetime".to_string(),
+        Some(T::Duration(d)) => format!("duration<{}>", d.unit),
+        Some(T::Id(i)) if i.unique => "id(unique)".to_string(),
+        Some(T::Id(_)) => "id".to_string(),
+        Some(T::Struct(_)) => "struct".to_string(),
+        Some(T::Union(_)) => "union".to_string(),
+        Some(T::Optional(o)) => type_name(o.type_()),
+        Some(T::List(_)) => "list".to_string(),
+        Some(T::Array(_)) => "array".to_string(),
+        Some(T::Constrained(c)) => type_name(c.type_()),
+        Some(T::Hypothesis(_)) => "hypothesis".to_string(),
+    }
+}
+
+/// The bounds of a scalar type, unless they are the natural ones
+pub(super) fn bounds(type_: &type_::Type) -> Option<(String, String)> {
+    use type_::type_::Type as T;
+    let temporal = |min: &str, max: &str| {
+        (!min.is_empty() || !max.is_empty()).then(|| (min.to_string(), max.to_string()))
+    };
+    match type_.type_.as_ref() {
+        Some(T::Integer(i)) if i.min != i64::MIN || i.max != i64::MAX => {
+            Some((i.min.to_string(), i.max.to_string()))
+        }
+        Some(T::Float(f)) if f.min > f64::MIN || f.max < f64::MAX => {
+            Some((f.min.to_string(), f.max.to_string()))
+        }
+        Some(T::Duration(d)) if d.min != i64::MIN || d.max != i64::MAX => {
+            Some((d.min.to_string(), d.max.to_string()))
+        }
+        Some(T::Date(d)) => temporal(&d.min, &d.max),
+        Some(T::Time(t)) => temporal(&t.min, &t.max),
+        Some(T::Datetime(d)) => temporal(&d.min, &d.max),
+        Some(T::Optional(o)) => bounds(o.type_()),
+        Some(T::Constrained(c)) => bounds(c.type_()),
+        _ => None,
+    }
+}
+
+/// The possible values of a scalar type, texts being quoted
+pub(super) fn possible_values(type_: &type_::Type) -> Vec<String> {
+    use type_::type_::Type as T;
+    match type_.type_.as_ref() {
+        Some(T::Boolean(_)) => type_
+            .properties
+            .get(POSSIBLE_VALUES)
+            .and_then(|values| serde_json::from_str::<Vec<bool>>(values).ok())
+            .map_or_else(Vec::new, |values| strings(&values)),
+        Some(T::Integer(i)) => strings(&i.possible_values),
+        Some(T::Float(f)) => strings(&f.possible_values),
+        Some(T::Duration(d)) => strings(&d.possible_values),
+        Some(T::Enum(e)) => e
+            .name_values
+            .iter()
+            .map(|nv| format!("{:?}", nv.name()))
+            .collect(),
+        Some(T::Text(t)) => t
+            .possible_values
+            .iter()
+            .map(|value| format!("{:?}", value))
+            .collect(),
+        Some(T::Date(d)) => d.possible_values.clone(),
+        Some(T::Time(t)) => t.possible_values.clone(),
+        Some(T::Datetime(d)) => d.possible_values.clone(),
+        Some(T::Optional(o)) => possible_values(o.type_()),
+        Some(T::Constrained(c)) => possible_values(c.type_()),
+        _ => vec![],
+    }
+}
+
+fn strings<T: ToString>(values: &[T]) -> Vec<String> {
+    values.iter().map(ToString::to_string).collect()
+}
+
+/// The labels of an identifier joined with dots, as `schema.table`
+pub(super) fn dotted(identifier: &Identifier) -> String {
+    identifier.iter().cloned().collect::<Vec<_>>().join(".")
+}
+
+/// Escape the HTML special characters of a text
+pub(super) fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Apply a style if `colored`
+fn paint(text: &str, colored: bool, style: fn(ColoredString) -> ColoredString) -> String {
+    if colored {
+        style(text.into()).to_string()
+    } else {
+        text.to_string()
+    }
+}
+
+impl Dataset {
+    /// The tree of the tables and columns of the dataset, coloured unless colours are disabled
+    /// by the `NO_COLOR` or `CLICOLOR` environment variables or the output is not a terminal
+    pub fn to_colored_string(&self) -> String {
+        self.to_tree_string(true)
+    }
+
+    fn to_tree_string(&self, colored: bool) -> String {
+        let relations = self.relations();
+        let foreign_keys = self.foreign_keys();
+        let mut lines = vec![format!(
+            "{} {}",
+            paint("Dataset", colored, |s| s.bold()),
+            paint(self.dataset().name(), colored, |s| s.bold().blue())
+        )];
+        if !self.dataset().doc.is_empty() {
+            lines.push(paint(&self.dataset().doc, colored, |s| s.italic()));
+        }
+        let tables = table_structs(self.schema_type_data(), self.size_statistics());
+        let admin_columns = self.admin_names_and_types();
+        let mut groups: Vec<(String, Vec<String>)> = tables
+            .into_iter()
+            .map(|(identifier, schema_struct, statistics)| {
+                let identifier = self.full_path(&identifier);
+                let mut header = paint(&dotted(&identifier), colored, |s| s.bold().blue());
+                if let Some(statistics) = statistics {
+                    let size = match size_range(statistics) {
+                        (min, max) if min == max => format!("size {}", min),
+                        (min, max) => format!("size [{}, {}]", min, max),
+                    };
+                    header = format!("{} {}", header, paint(&size, colored, |s| s.dimmed()));
+                }
+                let relation = relations.get(identifier.as_ref());
+                let columns = schema_struct
+                    .fields
+                    .iter()
+                    .map(|field| {
+                        let constraint = relation
+                            .and_then(|relation| relation.schema().field(field.name()).ok())
+                            .and_then(|field| field.constraint())
+                            .or_else(|| constraint_property(field.type_()));
+                        let mut line = format!(
+                            "{}: {}",
+                            field.name(),
+                            paint(&field.type_().to_compact_string(), colored, |s| s.green())
+                        );
+                        if let Some(constraint) =
+                            constraint_string(constraint, &identifier, field.name(), &foreign_keys)
+                        {
+                            line =
+                                format!("{} {}", line, paint(&constraint, colored, |s| s.yellow()));
+                        }
+                        line
+                    })
+                    .collect();
+                (header, columns)
+            })
+            .collect();
+        if !admin_columns.is_empty() {
+            groups.push((
+                paint("admin columns", colored, |s| s.dimmed().italic()),
+                admin_columns
+                    .into_iter()
+                    .map(|(name, type_)| {
+                        format!(
+                            "{}: {}",
+                            paint(name, colored, |s| s.dimmed()),
+                            paint(&type_.to_compact_string(), colored, |s| s.green())
+                        )
+                    })
+                    .collect(),
+            ));
+        }
+        let group_count = groups.len();
+        for (i, (header, columns)) in groups.into_iter().enumerate() {
+            let last_group = i + 1 == group_count;
+            lines.push(format!(
+                "{}{}",
+                if last_group {
+                    "└── "
+                } else {
+                    "├── "
+                },
+                header
+            ));
+            let column_count = columns.len();
+            for (j, column) in columns.into_iter().enumerate() {
+                lines.push(format!(
+                    "{}{}{}",
+                    if last_group { "    " } else { "│   " },
+                    if j + 1 == column_count {
+                        "└── "
+                    } else {
+                        "├── "
+                    },
+                    column
+                ));
+            }
+        }
+        lines.join("\n")
+    }
+}
+
+/// The constraint set in the type properties, for tables missing from the relations
+pub(super) fn constraint_property(type_: &type_::Type) -> Option<Constraint> {
+    match type_.properties.get(CONSTRAINT).map(String::as_str) {
+        Some(CONSTRAINT_PRIMARY_KEY) => Some(Constraint::PrimaryKey),
+        Some(CONSTRAINT_UNIQUE) => Some(Constraint::Unique),
+        _ => None,
+    }
+}
+
+pub(super) fn constraint_string(
+    constraint: Option<Constraint>,
+    relation: &Identifier,
+    column: &str,
+    foreign_keys: &[ForeignKey],
+) -> Option<String> {
+    match constraint? {
+        Constraint::PrimaryKey => Some("primary key".to_string()),
+        Constraint::Unique => Some("unique".to_string()),
+        Constraint::ForeignKey => Some(
+            foreign_keys
+                .iter()
+                .find(|fk| &fk.relation == relation && fk.column == column)
+                .map_or("foreign key".to_string(), |fk| {
+                    format!("→ {}.{}", dotted(&fk.referred_relation), fk.referred_column)
+                }),
+        ),
+    }
+}
+
+/// A tree of the tables and columns, without colours
+impl fmt::Display for Dataset {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_tree_string(false))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protobuf::parse_from_str;
+    use anyhow::Result;
+
+    const SCHEMA: &str = r#"
+    {
+        "name": "shop",
+        "properties": {"foreign_keys": "[[[\"visits\",\"user_id\"],[\"users\",\"id\"]]]"},
+        "type": {"name": "Struct", "struct": {"fields": [
+            {"name": "sarus_data", "type": {"name": "Union", "union": {"fields": [
+                {"name": "users", "type": {"name": "Struct", "struct": {"fields": [
+                    {"name": "id", "type": {"name": "Integer", "properties": {"_CONSTRAINT_": "_PRIMARY_KEY_"},
+                        "integer": {"base": "INT64", "min": "0", "max": "120"}}},
+                    {"name": "name", "type": {"name": "Text", "text": {"encoding": "UTF-8", "possible_values": ["a", "b"]}}},
+                    {"name": "born", "type": {"name": "Optional", "optional": {"type": {"name": "Datetime", "datetime": {"format": "%Y-%m-%d %H:%M:%S"}}}}}
+                ]}}},
+                {"name": "visits", "type": {"name": "Struct", "struct": {"fields": [
+                    {"name": "user_id", "type": {"name": "Integer", "integer": {"base": "INT64", "min": "0", "max": "120"}}},
+                    {"name": "score", "type": {"name": "Float", "float": {"base": "FLOAT32", "min": 0, "max": 1.5}}}
+                ]}}}
+            ]}}},
+            {"name": "sarus_is_public", "type": {"name": "Boolean", "boolean": {}}},
+            {"name": "sarus_privacy_unit", "type": {"name": "Optional", "optional": {"type": {"name": "Id", "id": {"base": "STRING"}}}}},
+            {"name": "sarus_weights", "type": {"name": "Float", "float": {"min": 0, "max": 1}}}
+        ]}}
+    }
+    "#;
+
+    const SIZE: &str = r#"
+    {
+        "name": "shop_sizes",
+        "statistics": {"name": "Union", "union": {"fields": [
+            {"name": "users", "statistics": {"name": "Struct", "struct": {"fields": [], "size": "100"}}},
+            {"name": "visits", "statistics": {"name": "Struct", "struct": {"fields": [], "size": "1000"},
+                "properties": {"_SIZE_MAX_": "2000"}}}
+        ]}}
+    }
+    "#;
+
+    #[test]
+    fn test_display() -> Result<()> {
+        let dataset = Dataset::new(
+            parse_from_str(r#"{"name": "shop", "doc": "A shop"}"#)?,
+            parse_from_str(SCHEMA)?,
+            Some(parse_from_str(SIZE)?),
+        );
+        println!("{}", dataset.to_colored_string());
+        let tree = dataset.to_string();
+        println!("{}", tree);
+        assert_eq!(
+            tree,
+            r#"Dataset shop
+A shop
+├── shop.users size 100
+│   ├── id: int[0, 120] primary key
+│   ├── name: text{"a","b"}
+│   └── born: optional<datetime>
+├── shop.visits size [1000, 2000]
+│   ├── user_id: int[0, 120] → shop.users.id
+│   └── score: float32[0, 1.5]
+└── admin columns
+    ├── sarus_is_public: bool
+    ├── sarus_privacy_unit: optional<id>
+    └── sarus_weights: float[0, 1]"#
+        );
+        colored::control::set_override(true);
+        let colored_tree = dataset.to_colored_string();
+        colored::control::unset_override();
+        assert!(colored_tree.contains("\u{1b}["));
+        assert!(!tree.contains("\u{1b}["));
+        Ok(())
+    }
+}
@@ -0,0 +1,561 @@
+//! Analytics on the Distribution message.
+//!
+//! The points of a Distribution are atoms with a probability mass.
+//! Booleans are valued 0 and 1, Enum points use their `value`.
+
+use super::{Error, Result};
+use crate::protobuf::statistics::{distribution, Distribution};
+use qrlew::data_type::{value::Value, DataType};
+use std::cmp::Ordering;
+
+impl Distribution {
+    /// The (value, probability) points, sorted by value
+    pub fn numeric_points(&self) -> Vec<(f64, f64)> {
+        let mut points: Vec<(f64, f64)> = match self.distribution.as_ref() {
+            Some(distribution::Distribution::Double(d)) => {
+                d.points.iter().map(|p| (p.value, p.probability)).collect()
+            }
+            Some(distribution::Distribution::Integer(d)) => d
+                .points
+                .iter()
+                .map(|p| (p.value as f64, p.probability))
+                .collect(),
+            Some(distribution::Distribution::Boolean(d)) => d
+                .points
+                .iter()
+                .map(|p| (if p.name { 1. } else { 0. }, p.probability))
+                .collect(),
+            Some(distribution::Distribution::Enum(d)) => {
+                d.points.iter().map(|p| (p.value, p.probability)).collect()
+            }
+            None => vec![],
+        };
+        points.sort_by(|(a, _), (b, _)| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+        points
+    }
+
+    /// The sum of the probabilities of the points
+    pub fn total_probability(&self) -> f64 {
+        self.numeric_points().iter().map(|(_, p)| p).sum()
+    }
+
+    /// The distribution with probabilities summing to 1
+    pub fn normalized(&self) -> Self {
+        let total = self.total_probability();
+        let mut result = self.clone();
+        if total > 0. {
+            result.map_probabilities(|p| p / total);
+        }
+        result
+    }
+
+    /// The probability of a value lower or equal to `x`
+    pub fn cdf(&self, x: f64) -> f64 {
+        let total = self.total_probability();
+        if total <= 0. {
+            return 0.;
+        }
+        self.numeric_points()
+            .iter()
+            .take_while(|(value, _)| *value <= x)
+            .map(|(_, p)| p)
+            .sum::<f64>()
+            / total
+    }
+
+    /// The smallest value whose cdf reaches `q`
+    pub fn quantile(&self, q: f64) -> Option<f64> {
+        let total = self.total_probability();
+        if !(0. ..=1.).contains(&q) || total <= 0. {
+            return None;
+        }
+        let mut cumulated = 0.;
+        for (value, p) in self.numeric_points() {
+            cumulated += p / total;
+            if cumulated >= q - f64::EPSILON {
+                return Some(value);
+            }
+        }
+        self.numeric_points().last().map(|(value, _)| *value)
+    }
+
+    pub fn mean(&self) -> Option<f64> {
+        let total = self.total_probability();
+        (total > 0.).then(|| {
+            self.numeric_points()
+                .iter()
+                .map(|(value, p)| value * p)
+                .sum::<f64>()
+                / total
+        })
+    }
+
+    pub fn variance(&self) -> Option<f64> {
+        let mean = self.mean()?;
+        let total = self.total_probability();
+        Some(
+            self.numeric_points()
+                .iter()
+                .map(|(value, p)| (value - mean).powi(2) * p)
+                .sum::<f64>()
+                / total,
+        )
+    }
+
+    /// The values with a positive probability
+    pub fn support(&self) -> Vec<Value> {
+        match self.distribution.as_ref() {
+            Some(distribution::Distribution::Double(d)) => d
+                .points
+                .iter()
+                .filter(|p| p.probability > 0.)
+                .map(|p| Value::float(p.value))
+                .collect(),
+            Some(distribution::Distribution::Integer(d)) => d
+                .points
+                .iter()
+                .filter(|p| p.probability > 0.)
+                .map(|p| Value::integer(p.value))
+                .collect(),
+            Some(distribution::Distribution::Boolean(d)) => d
+                .points
+                .iter()
+                .filter(|p| p.probability > 0.)
+                .map(|p| Value::boolean(p.name))
+                .collect(),
+            Some(distribution::Distribution::Enum(d)) => d
+                .points
+                .iter()
+                .filter(|p| p.probability > 0.)
+                .map(|p| Value::text(p.name.clone()))
+                .collect(),
+            None => vec![],
+        }
+    }
+
+    /// The smallest value of the support, or the `min` bound without points
+    pub fn min(&self) -> Option<f64> {
+        let support = self.numeric_support();
+        match self.distribution.as_ref() {
+            Some(distribution::Distribution::Double(d)) if support.is_empty() => Some(d.min),
+            Some(distribution::Distribution::Integer(d)) if support.is_empty() => {
+                Some(d.min as f64)
+            }
+            _ => support.first().cloned(),
+        }
+    }
+
+    /// The largest value of the support, or the `max` bound without points
+    pub fn max(&self) -> Option<f64> {
+        let support = self.numeric_support();
+        match self.distribution.as_ref() {
+            Some(distribution::Distribution::Double(d)) if support.is_empty() => Some(d.max),
+            Some(distribution::Distribution::Integer(d)) if support.is_empty() => {
+                Some(d.max as f64)
+            }
+            _ => support.last().cloned(),
+        }
+    }
+
+    /// The mixture of two distributions of the same kind, weighted by `weight` and `other_weight`
+    /// (e.g. the sizes of the datasets they describe).
+    /// Points with the same value are combined and bounds are widened. Probabilities are not
+    /// renormalized: the mass missing from a truncated distribution is missing from the mixture.
+    pub fn merge(&self, other: &Self, weight: f64, other_weight: f64) -> Result<Self> {
+        let (left, right) = (self, other);
+        let (wl, wr) = match weight + other_weight {
+            total if total > 0. => (weight / total, other_weight / total),
+            _ => (0.5, 0.5),
+        };
+        let mut result = Distribution::new();
+        result.properties = self.properties.clone();
+        match (left.distribution.as_ref(), right.distribution.as_ref()) {
+            (
+                Some(distribution::Distribution::Double(l)),
+                Some(distribution::Distribution::Double(r)),
+            ) => {
+                let mut merged = distribution::Double::new();
+                merged.min = l.min.min(r.min);
+                merged.max = l.max.max(r.max);
+                merged.points = merge_points(
+                    l.points.iter().map(|p| (p.value, p.probability * wl)),
+                    r.points.iter().map(|p| (p.value, p.probability * wr)),
+                    |a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal),
+                )
+                .into_iter()
+                .map(|(value, probability)| distribution::double::Point {
+                    value,
+                    probability,
+                    ..Default::default()
+                })
+                .collect();
+                result.set_double(merged);
+            }
+            (
+                Some(distribution::Distribution::Integer(l)),
+                Some(distribution::Distribution::Integer(r)),
+            ) => {
+                let mut merged = distribution::Integer::new();
+                merged.min = l.min.min(r.min);
+                merged.max = l.max.max(r.max);
+                merged.points = merge_points(
+                    l.points.iter().map(|p| (p.value, p.probability * wl)),
+                    r.points.iter().map(|p| (p.value, p.probability * wr)),
+                    Ord::cmp,
+                )
+                .into_iter()
+                .map(|(value, probability)| distribution::integer::Point {
+                    value,
+                    probability,
+                    ..Default::default()
+                })
+                .collect();
+                result.set_integer(merged);
+            }
+            (
+                Some(distribution::Distribution::Boolean(l)),
+                Some(distribution::Distribution::Boolean(r)),
+            ) => {
+                let mut merged = distribution::Boolean::new();
+                merged.points = merge_points(
+                    l.points
+                        .iter()
+                        .map(|p| ((p.name, p.value), p.probability * wl)),
+                    r.points
+                        .iter()
+                        .map(|p| ((p.name, p.value), p.probability * wr)),
+                    |a, b| a.0.cmp(&b.0),
+                )
+                .into_iter()
+                .map(
+                    |((name, value), probability)| distribution::boolean::Point {
+                        name,
+                        value,
+                        probability,
+                        ..Default::default()
+                    },
+                )
+                .collect();
+                result.set_boolean(merged);
+            }
+            (
+                Some(distribution::Distribution::Enum(l)),
+                Some(distribution::Distribution::Enum(r)),
+            ) => {
+                let mut merged = distribution::Enum::new();
+                merged.points = merge_points(
+                    l.points
+                        .iter()
+                        .map(|p| ((p.name.clone(), p.value), p.probability * wl)),
+                    r.points
+                        .iter()
+                        .map(|p| ((p.name.clone(), p.value), p.probability * wr)),
+                    |a, b| a.0.cmp(&b.0),
+                )
+                .into_iter()
+                .map(|((name, value), probability)| distribution::enum_::Point {
+                    name,
+                    value,
+                    probability,
+                    ..Default::default()
+                })
+                .collect();
+                result.set_enum(merged);
+            }
+            (None, _) => return Ok(other.clone()),
+            (_, None) => return Ok(self.clone()),
+            _ => {
+                return Err(Error::other(
+                    "Cannot merge distributions of different kinds",
+                ))
+            }
+        }
+        Ok(result)
+    }
+
+    /// Regroup the points of a Double or Integer distribution into the buckets delimited
+    /// by `bounds`, sorted and deduplicated. Each bucket is a point at its upper bound holding
+    /// the mass of the values in (previous bound, bound], so that the cdf is unchanged at the
+    /// bounds. The mass above the last bound is put on a point at the `max` of the distribution,
+    /// or at the largest value if it exceeds `max`. Integer buckets are rounded down, buckets
+    /// falling on the same integer are combined.
+    pub fn rebucket(&self, bounds: &[f64]) -> Result<Self> {
+        if bounds.iter().any(|b| b.is_nan()) {
+            return Err(Error::other("Bucket bounds must be numbers"));
+        }
+        let mut bounds = bounds.to_vec();
+        bounds.sort_by(f64::total_cmp);
+        bounds.dedup();
+        let buckets = |points: Vec<(f64, f64)>, max: f64| -> Vec<(f64, f64)> {
+            let mut buckets: Vec<(f64, f64)> = bounds.iter().map(|b| (*b, 0.)).collect();
+            let mut overflow: Option<(f64, f64)> = None;
+            for (value, p) in points {
+                match bounds.iter().position(|b| value <= *b) {
+                    Some(index) => buckets[index].1 += p,
+                    None => {
+                        let (top, mass) = overflow.get_or_insert((max, 0.));
+                        *top = top.max(value);
+                        *mass += p;
+                    }
+                }
+            }
+            buckets.extend(overflow.filter(|(_, mass)| *mass > 0.));
+            buckets
+        };
+        let mut result = self.clone();
+        match result.distribution.as_mut() {
+            Some(distribution::Distribution::Double(d)) => {
+                let points = self.numeric_points();
+                d.points = buckets(points, d.max)
+                    .into_iter()
+                    .map(|(value, probability)| distribution::double::Point {
+                        value,
+                        probability,
+                        ..Default::default()
+                    })
+                    .collect();
+            }
+            Some(distribution::Distribution::Integer(d)) => {
+                let points = self.numeric_points();
+                let buckets = buckets(points, d.max as f64)
+                    .into_iter()
+                    .map(|(value, probability)| (value.floor() as i64, probability));
+                d.points = merge_points(buckets, std::iter::empty(), Ord::cmp)
+                    .into_iter()
+                    .map(|(value, probability)| distribution::integer::Point {
+                        value,
+                        probability,
+                        ..Default::default()
+                    })
+                    .collect();
+            }
+            _ => {
+                return Err(Error::other(
+                    "Only Double and Integer distributions can be rebucketed",
+                ))
+            }
+        }
+        Ok(result)
+    }
+
+    /// The qrlew DataType of the values of the distribution:
+    /// an interval for Doubles, a set of values for the other kinds
+    /// (an interval for Integers without points).
+    /// Unset bounds (both 0) are ignored and inverted bounds give `DataType::Any`.
+    pub fn data_type(&self) -> DataType {
+        match self.distribution.as_ref() {
+            Some(distribution::Distribution::Double(d)) => {
+                let bounds = (d.min != 0. || d.max != 0.).then_some((d.min, d.max));
+                let support = self.numeric_support();
+                match (bounds, support.first().zip(support.last())) {
+                    (Some((min, max)), _) if min > max || min.is_nan() || max.is_nan() => {
+                        DataType::Any
+                    }
+                    (None, None) => DataType::float(),
+                    (Some((min, max)), None) | (None, Some((&min, &max))) => {
+                        DataType::float_interval(min, max)
+                    }
+                    (Some((lower, upper)), Some((&min, &max))) => {
+                        DataType::float_interval(min.min(lower), max.max(upper))
+                    }
+                }
+            }
+            Some(distribution::Distribution::Integer(d)) if d.points.is_empty() => {
+                match (d.min, d.max) {
+                    (0, 0) => DataType::integer(),
+                    (min, max) if min > max => DataType::Any,
+                    (min, max) => DataType::integer_interval(min, max),
+                }
+            }
+            Some(distribution::Distribution::Integer(d)) => DataType::integer_values(
+                d.points
+                    .iter()
+                    .filter(|p| p.probability > 0.)
+                    .map(|p| p.value)
+                    .collect::<Vec<_>>(),
+            ),
+            Some(distribution::Distribution::Boolean(d)) if d.points.is_empty() => {
+                DataType::boolean()
+            }
+            Some(distribution::Distribution::Boolean(d)) => DataType::boolean_values(
+                d.points
+                    .iter()
+                    .filter(|p| p.probability > 0.)
+                    .map(|p| p.name)
+                    .collect::<Vec<_>>(),
+            ),
+            Some(distribution::Distribution::Enum(d)) => DataType::Enum(
+                d.points
+                    .iter()
+                    .map(|p| (p.name.as_str(), p.value as i64))
+                    .collect(),
+            ),
+            None => DataType::Any,
+        }
+    }
+
+    fn numeric_support(&self) -> Vec<f64> {
+        self.numeric_points()
+            .into_iter()
+            .filter(|(_, p)| *p > 0.)
+            .map(|(value, _)| value)
+            .collect()
+    }
+
+    fn map_probabilities(&mut self, f: impl Fn(f64) -> f64) {
+        match self.distribution.as_mut() {
+            Some(distribution::Distribution::Double(d)) => d
+                .points
+                .iter_mut()
+                .for_each(|p| p.probability = f(p.probability)),
+            Some(distribution::Distribution::Integer(d)) => d
+                .points
+                .iter_mut()
+                .for_each(|p| p.probability = f(p.probability)),
+            Some(distribution::Distribution::Boolean(d)) => d
+                .points
+                .iter_mut()
+                .for_each(|p| p.probability = f(p.probability)),
+            Some(distribution::Distribution::Enum(d)) => d
+                .points
+                .iter_mut()
+                .for_each(|p| p.probability = f(p.probability)),
+            None => {}
+        }
+    }
+}
+
+/// Merge weighted points, summing the probabilities of equal keys
+fn merge_points<K: Clone>(
+    left: impl Iterator<Item = (K, f64)>,
+    right: impl Iterator<Item = (K, f64)>,
+    cmp: impl Fn(&K, &K) -> Ordering,
+) -> Vec<(K, f64)> {
+    let mut points: Vec<(K, f64)> = left.chain(right).collect();
+    points.sort_by(|(a, _), (b, _)| cmp(a, b));
+    let mut merged: Vec<(K, f64)> = vec![];
+    for (key, p) in points {
+        match merged.last_mut() {
+            Some((last, q)) if cmp(last, &key) == Ordering::Equal => *q += p,
+            _ => merged.push((key, p)),
+        }
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protobuf::parse_from_str;
+    use anyhow::Result;
+
+    fn integer() -> Distribution {
+        parse_from_str(
+            r#"{"integer": {"min": "0", "max": "10", "points": [
+                {"value": "1", "probability": 2},
+                {"value": "2", "probability": 1},
+                {"value": "5", "probability": 1}
+            ]}}"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_moments() {
+        let distribution = integer();
+        assert_eq!(distribution.total_probability(), 4.);
+        assert_eq!(distribution.normalized().total_probability(), 1.);
+        assert_eq!(distribution.mean(), Some(2.25));
+        assert_eq!(distribution.variance(), Some(2.6875));
+        assert_eq!(distribution.cdf(1.5), 0.5);
+        assert_eq!(distribution.cdf(10.), 1.);
+        assert_eq!(distribution.quantile(0.5), Some(1.));
+        assert_eq!(distribution.quantile(0.6), Some(2.));
+        assert_eq!(distribution.quantile(1.), Some(5.));
+        assert_eq!(distribution.quantile(1.5), None);
+        assert_eq!(
+            distribution.support(),
+            vec![Value::integer(1), Value::integer(2), Value::integer(5)]
+        );
+        assert_eq!(
+            (distribution.min(), distribution.max()),
+            (Some(1.), Some(5.))
+        );
+        assert_eq!(
+            distribution.data_type(),
+            DataType::integer_values([1, 2, 5])
+        );
+    }
+
+    #[test]
+    fn test_merge_and_rebucket() -> Result<()> {
+        // A truncated distribution, missing half of its mass
+        let other: Distribution = parse_from_str(
+            r#"{"integer": {"min": "-5", "max": "5", "points": [
+                {"value": "-5", "probability": 0.25},
+                {"value": "5", "probability": 0.25}
+            ]}}"#,
+        )?;
+        let merged = integer().normalized().merge(&other, 1., 1.)?;
+        assert_eq!(
+            merged.numeric_points(),
+            vec![(-5., 0.125), (1., 0.25), (2., 0.125), (5., 0.25)]
+        );
+        assert_eq!(merged.total_probability(), 0.75);
+        assert_eq!((merged.integer().min(), merged.integer().max()), (-5, 10));
+        let boolean: Distribution = parse_from_str(r#"{"boolean": {}}"#)?;
+        assert!(integer().merge(&boolean, 1., 1.).is_err());
+        let rebucketed = integer().rebucket(&[1., 4.])?;
+        assert_eq!(
+            rebucketed.numeric_points(),
+            vec![(1., 2.), (4., 1.), (10., 1.)]
+        );
+        assert_eq!(rebucketed.cdf(4.), integer().cdf(4.));
+        // Bounds are sorted and deduplicated, integer buckets are combined
+        assert_eq!(integer().rebucket(&[4., 1., 4.])?, rebucketed);
+        assert_eq!(integer().rebucket(&[1.2, 1.5, 4.])?, rebucketed);
+        assert!(integer().rebucket(&[f64::NAN]).is_err());
+        // Values above `max` stay above the last bound
+        let mut stale = integer();
+        stale.mut_integer().max = 3;
+        assert_eq!(
+            stale.rebucket(&[1., 4.])?.numeric_points(),
+            vec![(1., 2.), (4., 1.), (5., 1.)]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_data_type() -> Result<()> {
+        let double: Distribution = parse_from_str(r#"{"double": {"min": -1.5, "max": 2}}"#)?;
+        assert_eq!(double.data_type(), DataType::float_interval(-1.5, 2.));
+        assert_eq!((double.min(), double.max()), (Some(-1.5), Some(2.)));
+        // Unset bounds are not folded into the points and inverted bounds are rejected
+        let points: Distribution = parse_from_str(
+            r#"{"double": {"points": [{"value": 1, "probability": 0.5}, {"value": 3, "probability": 0.5}]}}"#,
+        )?;
+        assert_eq!(points.data_type(), DataType::float_interval(1., 3.));
+        let inverted: Distribution = parse_from_str(r#"{"double": {"min": 2, "max": -1.5}}"#)?;
+        assert_eq!(inverted.data_type(), DataType::Any);
+        let inverted: Distribution = parse_from_str(r#"{"integer": {"min": "5", "max": "1"}}"#)?;
+        assert_eq!(inverted.data_type(), DataType::Any);
+        let unset: Distribution = parse_from_str(r#"{"integer": {}}"#)?;
+        assert_eq!(unset.data_type(), DataType::integer());
+        let enum_: Distribution = parse_from_str(
+            r#"{"enum": {"points": [
+                {"name": "a", "value": 0, "probability": 0.5},
+                {"name": "b", "value": 1, "probability": 0.5}
+            ]}}"#,
+        )?;
+        assert_eq!(
+            enum_.data_type(),
+            DataType::Enum([("a", 0), ("b", 1)].into_iter().collect())
+        );
+        assert_eq!(enum_.mean(), Some(0.5));
+        let boolean: Distribution = parse_from_str(
+            r#"{"boolean": {"points": [{"name": true, "value": "1", "probability": 1}]}}"#,
+        )?;
+        assert_eq!(boolean.data_type(), DataType::boolean_values([true]));
+        Ok(())
+    }
+}
@@ -0,0 +1,695 @@
+//! Differentially private profiling of local data files.
+//!
+//! The budget is split evenly between the queries: one noisy count per table, one noisy
+//! count of non null values per Optional column and one noisy histogram per column with
+//! public bins. The bins are derived from the type only: buckets within the bounds of numeric
+//! and temporal types, the values of Boolean and Enum types and the possible values of Text types.
+//!
+//! The rows of each table are grouped by privacy unit: by the `sarus_privacy_unit` column of
+//! its files, by its foreign key to the protected entity, or row by row in the tables of the
+//! protected entity and in datasets without protected entity. At most `k` rows of each privacy
+//! unit are profiled, `k` being the maximum multiplicity of the dataset (1 if unknown),
+//! and each query is computed with the Laplace mechanism, its sensitivity being `k`.
+//! Rows that cannot be read and values that cannot be parsed are left out, numeric values
+//! out of the bounds are clamped, so that the outcome of the profiling does not depend on the data.
+
+use super::{
+    profile::{
+        bucket_bounds, integer_value, profile_type, set_distribution, Cell, DataFile, MAX_POINTS,
+    },
+    structure::{data_type, statistics_skeleton, with_size},
+    Dataset, Error, Result, PID_COLUMN, SIZE_MAX, SIZE_MIN,
+};
+use crate::protobuf::{marginals, scalar, size, statistics, type_};
+use protobuf::MessageField;
+use qrlew::expr::identifier::Identifier;
+use rand::Rng;
+use std::{collections::HashMap, convert::TryInto};
+
+/// Property of the Size and Marginals messages holding the epsilon spent to compute them
+pub const EPSILON: &str = "_EPSILON_";
+/// Property of the Size and Marginals messages holding the delta spent to compute them
+pub const DELTA: &str = "_DELTA_";
+/// Property of the Statistics of the tables of a Size holding the maximum number of rows
+/// profiled per privacy unit
+pub const MAX_CONTRIBUTIONS: &str = "_MAX_CONTRIBUTIONS_";
+/// The probability that the true size of a table is outside its noisy size range
+const SIZE_RANGE_RISK: f64 = 1e-9;
+
+impl scalar::scalar::PrivacyParameters {
+    /// The smallest epsilon of the points, the most conservative budget
+    pub fn epsilon(&self) -> Option<f64> {
+        self.points
+            .iter()
+            .map(|point| point.epsilon)
+            .filter(|epsilon| *epsilon > 0.)
+            .min_by(f64::total_cmp)
+    }
+}
+
+impl Dataset {
+    /// Profile the files of the File or Files spec of a single table dataset
+    /// with an `epsilon`-differentially private mechanism.
+    pub fn dp_profile<R: Rng>(
+        &self,
+        epsilon: f64,
+        rng: &mut R,
+    ) -> Result<(size::Size, marginals::Marginals)> {
+        self.dp_profile_files(self.spec_files()?, epsilon, rng)
+    }
+
+    /// Profile the tables from local files with an `epsilon`-differentially private mechanism.
+    /// The files are given as in `Dataset::profile_files`.
+    /// The sizes of the tables are noisy and widened into ranges holding the true sizes
+    /// with high probability. The enforced `k` is kept in the `MAX_CONTRIBUTIONS` property
+    /// of the tables, their multiplicity is left unmeasured.
+    pub fn dp_profile_files<P, I, R>(
+        &self,
+        files: I,
+        epsilon: f64,
+        rng: &mut R,
+    ) -> Result<(size::Size, marginals::Marginals)>
+    where
+        P: TryInto<Identifier>,
+        Error: From<P::Error>,
+        I: IntoIterator<Item = (P, DataFile)>,
+        R: Rng,
+    {
+        if !(epsilon.is_finite() && epsilon > 0.) {
+            return Err(Error::other(format!(
+                "epsilon must be positive, got {}",
+                epsilon
+            )));
+        }
+        let tables = self.table_files(files)?;
+        let max_contributions = self
+            .max_multiplicity()
+            .map_or(1, |multiplicity| multiplicity.floor().max(1.) as usize);
+        let queries = queries(data_type(&self.schema));
+        let mut mechanism = Laplace {
+            scale: max_contributions as f64 * queries as f64 / epsilon,
+            rng,
+        };
+        let statistics = profile_type(
+            data_type(&self.schema),
+            &[],
+            &tables,
+            &mut |table, path, files| {
+                let privacy_unit = self.privacy_unit_column(path, files)?;
+                dp_profile_table(
+                    table,
+                    files,
+                    privacy_unit.as_deref(),
+                    max_contributions,
+                    &mut mechanism,
+                )
+            },
+        )?;
+        let (mut size, mut marginals) = self.size_and_marginals(statistics);
+        for properties in [&mut size.properties, &mut marginals.properties] {
+            properties.insert(EPSILON.to_string(), epsilon.to_string());
+            properties.insert(DELTA.to_string(), 0.to_string());
+        }
+        Ok((size, marginals))
+    }
+}
+
+impl Dataset {
+    /// The column of the files of the table at `path` holding the privacy unit of the rows,
+    /// None if each row is its own privacy unit
+    fn privacy_unit_column(&self, path: &[String], files: &[DataFile]) -> Result<Option<String>> {
+        let mut has_privacy_unit = !files.is_empty();
+        for file in files {
+            has_privacy_unit &= file.columns()?.iter().any(|column| column == PID_COLUMN);
+        }
+        if has_privacy_unit {
+            return Ok(Some(PID_COLUMN.to_string()));
+        }
+        let relation = self.full_path(path);
+        let protected_tables = self.protected_tables();
+        if protected_tables.is_empty() || protected_tables.contains(&relation) {
+            return Ok(None);
+        }
+        self.foreign_keys()
+            .into_iter()
+            .find(|fk| fk.relation == relation && protected_tables.contains(&fk.referred_relation))
+            .map(|fk| Some(fk.column))
+            .ok_or_else(|| {
+                Error::other(format!(
+                    "Cannot group the rows of `{}` by privacy unit: its files have no `{}` column and it has no foreign key to the protected entity",
+                    path.join("."),
+                    PID_COLUMN
+                ))
+            })
+    }
+}
+
+/// The Laplace mechanism
+struct Laplace<'a, R: Rng> {
+    scale: f64,
+    rng: &'a mut R,
+}
+
+impl<'a, R: Rng> Laplace<'a, R> {
+    fn noise(&mut self) -> f64 {
+        let u: f64 = self.rng.gen_range(-0.5..0.5);
+        -self.scale * u.signum() * (1. - 2. * u.abs()).max(f64::MIN_POSITIVE).ln()
+    }
+
+    /// A noisy count, clamped to be non negative
+    fn count(&mut self, count: usize) -> f64 {
+        (count as f64 + self.noise()).max(0.)
+    }
+
+    /// The error bound of a noisy count, exceeded with probability SIZE_RANGE_RISK
+    fn tail(&self) -> f64 {
+        -self.scale * SIZE_RANGE_RISK.ln()
+    }
+}
+
+/// The number of queries needed to profile the tables below `type_`
+fn queries(type_: &type_::Type) -> usize {
+    match type_.type_.as_ref() {
+        Some(type_::type_::Type::Struct(table)) => {
+            1 + table
+                .fields
+                .iter()
+                .map(|field| column_queries(field.type_()))
+                .sum::<usize>()
+        }
+        Some(type_::type_::Type::Union(union)) => union
+            .fields
+            .iter()
+            .map(|field| queries(field.type_()))
+            .sum(),
+        _ => 0,
+    }
+}
+
+fn column_queries(type_: &type_::Type) -> usize {
+    match type_.type_.as_ref() {
+        Some(type_::type_::Type::Optional(optional)) => 1 + column_queries(optional.type_()),
+        _ => usize::from(bins(type_).is_some()),
+    }
+}
+
+/// Profile a table from the rows of its files, keeping at most `max_contributions` rows
+/// of each privacy unit, read from the `privacy_unit` column. Rows without privacy unit
+/// are left out.
+fn dp_profile_table<R: Rng>(
+    table: &type_::type_::Struct,
+    files: &[DataFile],
+    privacy_unit: Option<&str>,
+    max_contributions: usize,
+    mechanism: &mut Laplace<R>,
+) -> Result<statistics::Statistics> {
+    let columns: Vec<&str> = table
+        .fields
+        .iter()
+        .map(|field| field.name())
+        .chain(privacy_unit)
+        .collect();
+    let mut histograms: Vec<Histogram> = table
+        .fields
+        .iter()
+        .map(|field| Histogram::new(field.type_()))
+        .collect();
+    let mut contributions: HashMap<String, usize> = HashMap::new();
+    let mut rows = 0;
+    for file in files {
+        for mut row in file.rows(&columns)?.flatten() {
+            if privacy_unit.is_some() {
+                let unit = row.pop().filter(|unit| !unit.is_null());
+                let Some(contribution) =
+                    unit.map(|unit| contributions.entry(unit.text()).or_default())
+                else {
+                    continue;
+                };
+                if *contribution >= max_contributions {
+                    continue;
+                }
+                *contribution += 1;
+            }
+            for (histogram, cell) in histograms.iter_mut().zip(row) {
+                histogram.push(cell);
+            }
+            rows += 1;
+        }
+    }
+    let count = mechanism.count(rows);
+    let size = count.round() as i64;
+    let tail = mechanism.tail();
+    let mut result = statistics::statistics::Struct::new();
+    result.name = "Struct".to_string();
+    result.size = size;
+    for (field, histogram) in table.fields.iter().zip(histograms) {
+        result.fields.push(statistics::statistics::struct_::Field {
+            name: field.name.clone(),
+            statistics: MessageField::some(histogram.statistics(size, mechanism)),
+            ..Default::default()
+        });
+    }
+    let mut statistics = statistics::Statistics::new();
+    statistics.name = "Struct".to_string();
+    statistics.set_struct(result);
+    statistics.properties.insert(
+        SIZE_MIN.to_string(),
+        ((count - tail).floor().max(0.) as i64).to_string(),
+    );
+    statistics.properties.insert(
+        SIZE_MAX.to_string(),
+        ((count + tail).ceil() as i64).to_string(),
+    );
+    statistics
+        .properties
+        .insert(MAX_CONTRIBUTIONS.to_string(), max_contributions.to_string());
+    Ok(statistics)
+}
+
+/// The counts of the values of a column in its public bins
+struct Histogram<'a> {
+    type_: &'a type_::Type,
+    bins: Option<Bins>,
+    counts: Vec<usize>,
+    /// The histogram of the non null values of an Optional column, with their count
+    values: Option<(Box<Histogram<'a>>, usize)>,
+}
+
+impl<'a> Histogram<'a> {
+    fn new(type_: &'a type_::Type) -> Self {
+        let values = match type_.type_.as_ref() {
+            Some(type_::type_::Type::Optional(optional)) => {
+                Some((Box::new(Histogram::new(optional.type_())), 0))
+            }
+            _ => None,
+        };
+        let bins = bins(type_);
+        let counts = vec![0; bins.as_ref().map_or(0, Bins::len)];
+        Histogram {
+            type_,
+            bins,
+            counts,
+            values,
+        }
+    }
+
+    /// Count a value in its bin. Nulls of non Optional columns and values that cannot be
+    /// read are left out.
+    fn push(&mut self, cell: Cell) {
+        if let Some((values, count)) = self.values.as_mut() {
+            if !cell.is_null() {
+                *count += 1;
+                values.push(cell);
+            }
+        } else if let Some(Some(bin)) = self
+            .bins
+            .as_ref()
+            .and_then(|bins| bins.bin(self.type_, &cell))
+        {
+            self.counts[bin] += 1;
+        }
+    }
+
+    /// The statistics of a column of `size` values, with noisy counts
+    fn statistics<R: Rng>(self, size: i64, mechanism: &mut Laplace<R>) -> statistics::Statistics {
+        use statistics::statistics::Statistics as S;
+        let mut statistics = with_size(statistics_skeleton(self.type_), size);
+        if let Some((values, count)) = self.values {
+            let values_size = (mechanism.count(count).round() as i64).min(size);
+            if let Some(S::Optional(o)) = statistics.statistics.as_mut() {
+                o.statistics = MessageField::some(values.statistics(values_size, mechanism));
+            }
+        } else if let Some(bins) = self.bins {
+            let noisy_counts: Vec<f64> = self
+                .counts
+                .into_iter()
+                .map(|c| mechanism.count(c))
+                .collect();
+            set_distribution(
+                &mut statistics,
+                bins.distribution(self.type_, &noisy_counts),
+            );
+        }
+        statistics
+    }
+}
+
+/// The public bins of the histogram of a column
+enum Bins {
+    /// Upper bounds of buckets of integer encoded values
+    Integer(Vec<i64>),
+    /// Upper bounds of buckets of floats
+    Float(Vec<f64>),
+    Boolean,
+    /// Names and values
+    Enum(Vec<(String, i64)>),
+    /// Possible values
+    Text(Vec<String>),
+}
+
+fn bins(type_: &type_::Type) -> Option<Bins> {
+    use type_::type_::Type as T;
+    let integer_bins = |min: i64, max: i64| {
+        if min > max {
+            None
+        } else if (max as i128 - min as i128) < MAX_POINTS as i128 {
+            Some(Bins::Integer((min..=max).collect()))
+        } else {
+            let mut bounds: Vec<i64> = bucket_bounds(min as f64, max as f64)
+                .into_iter()
+                .map(|b| (b.floor() as i64).clamp(min, max))
+                .collect();
+            bounds.dedup();
+            Some(Bins::Integer(bounds))
+        }
+    };
+    let temporal_bins = |min: &str, max: &str| {
+        let min = integer_value(type_, &Cell::Text(min.to_string()))?;
+        let max = integer_value(type_, &Cell::Text(max.to_string()))?;
+        integer_bins(min, max)
+    };
+    match type_.type_.as_ref()? {
+        T::Integer(i) => integer_bins(i.min, i.max),
+        T::Duration(d) => integer_bins(d.min, d.max),
+        T::Date(d) => temporal_bins(&d.min, &d.max),
+        T::Time(t) => temporal_bins(&t.min, &t.max),
+        T::Datetime(d) => temporal_bins(&d.min, &d.max),
+        T::Float(f) if f.min.is_finite() && f.max.is_finite() && f.min < f.max => {
+            Some(Bins::Float(bucket_bounds(f.min, f.max)))
+        }
+        T::Boolean(_) => Some(Bins::Boolean),
+        T::Enum(e) if !e.name_values.is_empty() => Some(Bins::Enum(
+            e.name_values
+                .iter()
+                .map(|nv| (nv.name.clone(), nv.value))
+                .collect(),
+        )),
+        T::Text(t) if !t.possible_values.is_empty() => Some(Bins::Text(t.possible_values.clone())),
+        _ => None,
+    }
+}
+
+impl Bins {
+    fn len(&self) -> usize {
+        match self {
+            Bins::Integer(bounds) => bounds.len(),
+            Bins::Float(bounds) => bounds.len(),
+            Bins::Boolean => 2,
+            Bins::Enum(name_values) => name_values.len(),
+            Bins::Text(values) => values.len(),
+        }
+    }
+
+    /// The bin of a cell, `None` if the cell cannot be read,
+    /// `Some(None)` if its value is not in the bins of a Text type.
+    /// Numeric values out of the bounds fall in the first or the last bucket.
+    fn bin(&self, type_: &type_::Type, cell: &Cell) -> Option<Option<usize>> {
+        match self {
+            Bins::Integer(bounds) => {
+                let value = integer_value(type_, cell)?;
+                Some(Some(
+                    bounds
+                        .iter()
+                        .position(|b| value <= *b)
+                        .unwrap_or(bounds.len() - 1),
+                ))
+            }
+            Bins::Float(bounds) => {
+                let value = cell.float()?;
+                Some(Some(
+                    bounds
+                        .iter()
+                        .position(|b| value <= *b)
+                        .unwrap_or(bounds.len() - 1),
+                ))
+            }
+            Bins::Boolean => cell.boolean().map(|b| Some(b as usize)),
+            Bins::Enum(name_values) => name_values
+                .iter()
+                .position(|(name, value)| match cell {
+                    Cell::Integer(i) => value == i,
+                    _ => *name == cell.text(),
+                })
+                .map(Some),
+            Bins::Text(values) => Some(values.iter().position(|v| *v == cell.text())),
+        }
+    }
+
+    /// The distribution of the noisy counts of the bins
+    fn distribution(&self, type_: &type_::Type, counts: &[f64]) -> statistics::Distribution {
+        let total: f64 = counts.iter().sum();
+        let probability = |count: f64| if total > 0. { count / total } else { 0. };
+        let mut distribution = statistics::Distribution::new();
+        match self {
+            Bins::Integer(bounds) => {
+                let mut integer = statistics::distribution::Integer::new();
+                (integer.min, integer.max) = match type_.type_.as_ref() {
+                    Some(type_::type_::Type::Integer(i)) => (i.min, i.max),
+                    _ => (bounds[0], bounds[bounds.len() - 1]),
+                };
+                integer.points = bounds
+                    .iter()
+                    .zip(counts)
+                    .map(|(value, count)| statistics::distribution::integer::Point {
+                        value: *value,
+                        probability: probability(*count),
+                        ..Default::default()
+                    })
+                    .collect();
+                distribution.set_integer(integer);
+            }
+            Bins::Float(bounds) => {
+                let mut double = statistics::distribution::Double::new();
+                if let Some(type_::type_::Type::Float(f)) = type_.type_.as_ref() {
+                    (double.min, double.max) = (f.min, f.max);
+                }
+                double.points = bounds
+                    .iter()
+                    .zip(counts)
+                    .map(|(value, count)| statistics::distribution::double::Point {
+                        value: *value,
+                        probability: probability(*count),
+                        ..Default::default()
+                    })
+                    .collect();
+                distribution.set_double(double);
+            }
+            Bins::Boolean => {
+                let mut boolean = statistics::distribution::Boolean::new();
+                boolean.points = [false, true]
+                    .into_iter()
+                    .zip(counts)
+                    .map(|(name, count)| statistics::distribution::boolean::Point {
+                        name,
+                        value: name as i64,
+                        probability: probability(*count),
+                        ..Default::default()
+                    })
+                    .collect();
+                distribution.set_boolean(boolean);
+            }
+            Bins::Enum(name_values) => {
+                let mut enum_ = statistics::distribution::Enum::new();
+                enum_.points = name_values
+                    .iter()
+                    .zip(counts)
+                    .map(
+                        |((name, value), count)| statistics::distribution::enum_::Point {
+                            name: name.clone(),
+                            value: *value as f64,
+                            probability: probability(*count),
+                            ..Default::default()
+                        },
+                    )
+                    .collect();
+                distribution.set_enum(enum_);
+            }
+            Bins::Text(values) => {
+                let mut enum_ = statistics::distribution::Enum::new();
+                enum_.points = values
+                    .iter()
+                    .enumerate()
+                    .zip(counts)
+                    .map(
+                        |((rank, name), count)| statistics::distribution::enum_::Point {
+                            name: name.clone(),
+                            value: rank as f64,
+                            probability: probability(*count),
+                            ..Default::default()
+                        },
+                    )
+                    .collect();
+                distribution.set_enum(enum_);
+            }
+        }
+        distribution
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protobuf::{parse_from_str, path};
+    use anyhow::Result;
+    use qrlew::relation::Variant as _;
+    use rand::{rngs::StdRng, SeedableRng};
+    use std::fs;
+    use tempfile::tempdir;
+
+    const SCHEMA: &str = r#"
+    {
+        "name": "people",
+        "type": {"name": "Struct", "struct": {"fields": [
+            {"name": "age", "type": {"name": "Integer", "integer": {"min": "0", "max": "9"}}},
+            {"name": "city", "type": {"name": "Text", "text": {"encoding": "UTF-8", "possible_values": ["Paris", "Lyon"]}}},
+            {"name": "income", "type": {"name": "Optional", "optional": {"type": {"name": "Float", "float": {"min": 0, "max": 1000}}}}},
+            {"name": "comment", "type": {"name": "Text", "text": {"encoding": "UTF-8"}}}
+        ]}},
+        "properties": {"max_max_multiplicity": "1"}
+    }
+    "#;
+
+    #[test]
+    fn test_dp_profile() -> Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join("people.csv");
+        let rows: String = (0..1000)
+            .map(|i| {
+                format!(
+                    "{},{},{},hello\n",
+                    i % 10,
+                    if i % 4 == 0 { "Lyon" } else { "Paris" },
+                    if i % 2 == 0 {
+                        format!("{}", i % 500)
+                    } else {
+                        String::new()
+                    }
+                )
+            })
+            .collect();
+        fs::write(&path, format!("age,city,income,comment\n{}", rows))?;
+        let dataset = Dataset::new(parse_from_str("{}")?, parse_from_str(SCHEMA)?, None);
+        let privacy_parameters: scalar::scalar::PrivacyParameters = parse_from_str(
+            r#"{"points": [{"epsilon": 100, "delta": 0}, {"epsilon": 50, "delta": 1e-6}]}"#,
+        )?;
+        let epsilon = privacy_parameters.epsilon().unwrap();
+        assert_eq!(epsilon, 50.);
+        let mut rng = StdRng::seed_from_u64(1234);
+        let (size, marginals) = dataset.dp_profile_files(
+            [(Identifier::empty(), DataFile::Csv(path.clone()))],
+            epsilon,
+            &mut rng,
+        )?;
+        assert_eq!(marginals.properties.get(EPSILON).unwrap(), "50");
+        assert_eq!(size.properties.get(DELTA).unwrap(), "0");
+        // 5 queries with a sensitivity of 1
+        let table = marginals.statistics.struct_();
+        assert!((table.size - 1000).abs() < 10);
+        let (min, max): (i64, i64) = (
+            marginals.statistics.properties[SIZE_MIN].parse()?,
+            marginals.statistics.properties[SIZE_MAX].parse()?,
+        );
+        assert!(min <= 1000 && 1000 <= max);
+        let age = &table.fields[0].statistics.integer().distribution;
+        assert_eq!(age.integer().points.len(), 10);
+        assert!((age.mean().unwrap() - 4.5).abs() < 0.1);
+        let city = &table.fields[1].statistics.text().distribution;
+        assert!((city.enum_().points[1].probability - 0.25).abs() < 0.01);
+        let income = table.fields[2].statistics.optional();
+        assert!((income.statistics.float().size - 500).abs() < 10);
+        assert_eq!(
+            income.statistics.float().distribution.double().points.len(),
+            MAX_POINTS
+        );
+        // No public bins for free text
+        assert!(table.fields[3].statistics.text().distribution.is_none());
+        // The relations are sized with ranges
+        let dataset = Dataset::new(
+            dataset.dataset().clone(),
+            dataset.schema().clone(),
+            Some(size),
+        );
+        let relation = dataset
+            .relations()
+            .get(&["people".to_string()])
+            .unwrap()
+            .clone();
+        assert_eq!(relation.size().min(), Some(&min));
+        assert_eq!(relation.size().max(), Some(&max));
+        assert!(dataset
+            .dp_profile_files([(Identifier::empty(), DataFile::Csv(path))], 0., &mut rng)
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_dp_profile_privacy_units() -> Result<()> {
+        let dir = tempdir()?;
+        let mut rng = StdRng::seed_from_u64(1234);
+        let schema = r#"
+        {
+            "name": "shop",
+            "type": {"name": "Union", "union": {"fields": [
+                {"name": "users", "type": {"name": "Struct", "struct": {"fields": [
+                    {"name": "id", "type": {"name": "Integer", "integer": {"min": "0", "max": "100"}}}
+                ]}}},
+                {"name": "orders", "type": {"name": "Struct", "struct": {"fields": [
+                    {"name": "user_id", "type": {"name": "Integer", "integer": {"min": "0", "max": "100"}}}
+                ]}}}
+            ]}},
+            "properties": {"max_max_multiplicity": "3"}
+        }
+        "#;
+        let dataset = Dataset::new(parse_from_str("{}")?, parse_from_str(schema)?, None);
+        // 10 rows per privacy unit, unreadable values are left out
+        let users = dir.path().join("users.csv");
+        let rows: String = (0..1000)
+            .map(|i| {
+                format!(
+                    "{},{}\n",
+                    if i == 0 {
+                        "x".to_string()
+                    } else {
+                        (i % 100).to_string()
+                    },
+                    i % 100
+                )
+            })
+            .collect();
+        fs::write(&users, format!("id,sarus_privacy_unit\n{}", rows))?;
+        // 20 rows per user
+        let orders = dir.path().join("orders.csv");
+        let rows: String = (0..100).map(|i| format!("{}\n", i % 5)).collect();
+        fs::write(&orders, format!("user_id\n{}", rows))?;
+        let files = || {
+            [
+                (["users"], DataFile::Csv(users.clone())),
+                (["orders"], DataFile::Csv(orders.clone())),
+            ]
+        };
+        // Without protected entity, each order is its own privacy unit
+        let (size, _) = dataset.dp_profile_files(files(), 100., &mut rng)?;
+        let tables = &size.statistics.union().fields;
+        let (users_size, orders_size) = (
+            tables[0].statistics.struct_(),
+            tables[1].statistics.struct_(),
+        );
+        assert_eq!(users_size.multiplicity, 0.);
+        assert_eq!(
+            tables[0].statistics.properties.get(MAX_CONTRIBUTIONS),
+            Some(&"3".to_string())
+        );
+        assert!((users_size.size - 300).abs() < 5);
+        assert!((orders_size.size - 100).abs() < 5);
+        // The orders of a user are its contributions
+        let protected: path::Path =
+            parse_from_str(r#"{"label": "data", "paths": [{"label": "users"}]}"#)?;
+        let dataset = dataset.with_protected_path(protected)?;
+        assert!(dataset.dp_profile_files(files(), 100., &mut rng).is_err());
+        let dataset = dataset.with_foreign_key(["orders", "user_id"], ["users", "id"])?;
+        let (size, _) = dataset.dp_profile_files(files(), 100., &mut rng)?;
+        let orders_size = size.statistics.union().fields[1].statistics.struct_();
+        assert!((orders_size.size - 15).abs() < 5);
+        Ok(())
+    }
+}
@@ -0,0 +1,1443 @@
+//! Batch edits of the schema of a Dataset.
+//!
+//! A list of [`SchemaEdit`]s is applied in a single traversal of the schema type,
+//! the Size statistics are kept in line with renamed, dropped and (un)wrapped Optional fields.
+
+use super::{
+    structure::{multiplicity_of, rename_public_fields, size_of},
+    unresolved_path_error, Dataset, Error, Result, CONSTRAINT, CONSTRAINTS, CONSTRAINT_NOT_NULL,
+    CONSTRAINT_PRIMARY_KEY, POSSIBLE_VALUES, SARUS_DATA,
+};
+use crate::protobuf::{statistics, type_};
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use itertools::Itertools;
+use qrlew::{builder::With, expr::identifier::Identifier};
+use serde::{Deserialize, Serialize};
+use std::{fmt, result};
+
+/// An edit of a schema type.
+/// Paths are relative to the schema type, the SARUS_DATA admin wrapper may be omitted.
+/// Edits are applied in sequence: a path may refer to a field renamed by a previous edit.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "edit", rename_all = "snake_case")]
+pub enum SchemaEdit {
+    /// Set the range of an Integer, Float, Date, Time, Datetime or Duration
+    SetRange {
+        path: Vec<String>,
+        min: Bound,
+        max: Bound,
+    },
+    /// Set the possible values of a Text, Integer, Float, Enum, Boolean,
+    /// Date, Time, Datetime or Duration
+    SetPossibleValues {
+        path: Vec<String>,
+        possible_values: Vec<String>,
+    },
+    /// Set or clear the constraint (unique, primary key or not null).
+    /// Primary keys and not-null columns are made required, a not-null column being
+    /// nothing more than a required one.
+    SetConstraint {
+        path: Vec<String>,
+        constraint: Option<String>,
+    },
+    /// Set or clear the column referred to by a foreign key column
+    SetForeignKey {
+        path: Vec<String>,
+        references: Option<Vec<String>>,
+    },
+    /// Rename a Struct or Union field
+    Rename { path: Vec<String>, name: String },
+    /// Drop a Struct or Union field
+    Drop { path: Vec<String> },
+    /// Wrap the type in an Optional
+    MakeOptional { path: Vec<String> },
+    /// Remove the Optional wrapping the type
+    MakeRequired { path: Vec<String> },
+    /// Set or clear a property of the type
+    SetProperty {
+        path: Vec<String>,
+        key: String,
+        value: Option<String>,
+    },
+}
+
+impl SchemaEdit {
+    pub fn path(&self) -> &[String] {
+        match self {
+            SchemaEdit::SetRange { path, .. }
+            | SchemaEdit::SetPossibleValues { path, .. }
+            | SchemaEdit::SetConstraint { path, .. }
+            | SchemaEdit::SetForeignKey { path, .. }
+            | SchemaEdit::Rename { path, .. }
+            | SchemaEdit::Drop { path }
+            | SchemaEdit::MakeOptional { path }
+            | SchemaEdit::MakeRequired { path }
+            | SchemaEdit::SetProperty { path, .. } => path,
+        }
+    }
+
+    fn mut_path(&mut self) -> &mut Vec<String> {
+        match self {
+            SchemaEdit::SetRange { path, .. }
+            | SchemaEdit::SetPossibleValues { path, .. }
+            | SchemaEdit::SetConstraint { path, .. }
+            | SchemaEdit::SetForeignKey { path, .. }
+            | SchemaEdit::Rename { path, .. }
+            | SchemaEdit::Drop { path }
+            | SchemaEdit::MakeOptional { path }
+            | SchemaEdit::MakeRequired { path }
+            | SchemaEdit::SetProperty { path, .. } => path,
+        }
+    }
+
+    /// Whether the edit makes the column required: primary keys and not-null columns
+    /// are not Optional
+    fn makes_required(&self) -> bool {
+        match self {
+            SchemaEdit::MakeRequired { .. } => true,
+            SchemaEdit::SetConstraint {
+                constraint: Some(c),
+                ..
+            } => c == CONSTRAINT_PRIMARY_KEY || c == CONSTRAINT_NOT_NULL,
+            _ => false,
+        }
+    }
+
+    /// Apply the nullability edits to the statistics at the end of the path.
+    /// Making a column required fails if its statistics count nulls. A zero inner size,
+    /// as in skeleton statistics, leaves the number of nulls unknown.
+    fn apply_to_statistics(&self, statistics: &mut statistics::Statistics) -> Result<()> {
+        match (self, statistics.statistics.as_mut()) {
+            (
+                SchemaEdit::MakeOptional { .. },
+                Some(statistics::statistics::Statistics::Optional(_)) | None,
+            ) => {}
+            (SchemaEdit::MakeOptional { .. }, _) => {
+                let inner = std::mem::take(statistics);
+                let mut optional = statistics::statistics::Optional::new();
+                optional.set_name("Optional".to_string());
+                optional.set_size(size_of(&inner));
+                optional.set_multiplicity(multiplicity_of(&inner));
+                optional.set_statistics(inner);
+                statistics.set_name("Optional".to_string());
+                statistics.set_optional(optional);
+            }
+            (edit, Some(statistics::statistics::Statistics::Optional(o)))
+                if edit.makes_required() =>
+            {
+                let inner = o.take_statistics();
+                let inner_size = size_of(&inner);
+                let nulls = o.size() - inner_size;
+                if inner_size > 0 && nulls > 0 {
+                    return Err(Error::other(format!(
+                        "Cannot apply {}: the statistics count {} nulls",
+                        self, nulls
+                    )));
+                }
+                *statistics = inner;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Apply the edit to the type at the end of its path.
+    /// Renames and drops are applied by the parent Struct or Union.
+    fn apply_to_type(&self, type_: &mut type_::Type) -> Result<()> {
+        match (self, type_.type_.as_mut()) {
+            (
+                SchemaEdit::Rename { .. }
+                | SchemaEdit::Drop { .. }
+                | SchemaEdit::SetForeignKey { .. },
+                _,
+            ) => {}
+            (SchemaEdit::SetConstraint { constraint, .. }, _) => match constraint {
+                Some(c) if !CONSTRAINTS.contains(&c.as_str()) => {
+                    return Err(Error::other(format!(
+                        "Cannot apply {}: unknown constraint {}, expected one of {}",
+                        self,
+                        c,
+                        CONSTRAINTS.join(", ")
+                    )))
+                }
+                // A not-null column is a required one, its nullability is its type
+                Some(c) => {
+                    make_required(type_);
+                    if c != CONSTRAINT_NOT_NULL {
+                        type_
+                            .mut_properties()
+                            .insert(CONSTRAINT.to_string(), c.to_string());
+                    }
+                }
+                None => {
+                    type_.mut_properties().remove(CONSTRAINT);
+                }
+            },
+            (SchemaEdit::SetProperty { key, value, .. }, _) => match value {
+                Some(v) => {
+                    type_.mut_properties().insert(key.clone(), v.clone());
+                }
+                None => {
+                    type_.mut_properties().remove(key);
+                }
+            },
+            (SchemaEdit::MakeOptional { .. }, Some(type_::type_::Type::Optional(_))) => {}
+            (SchemaEdit::MakeOptional { .. }, _) => {
+                let mut inner = type_.clone();
+                let properties = std::mem::take(inner.mut_properties());
+                let mut optional = type_::type_::Optional::new();
+                optional.set_type(inner);
+                *type_ = type_::Type::new();
+                type_.set_name("Optional".to_string());
+                type_.set_properties(properties);
+                type_.set_optional(optional);
+            }
+            (SchemaEdit::MakeRequired { .. }, _) => make_required(type_),
+            (SchemaEdit::SetRange { min, max, .. }, _) => {
+                let name = type_.name().to_string();
+                set_range(type_, min, max).map_err(|reason| {
+                    Error::other(format!(
+                        "Cannot apply {} to type {}: {}",
+                        self, name, reason
+                    ))
+                })?
+            }
+            (
+                SchemaEdit::SetPossibleValues {
+                    possible_values, ..
+                },
+                _,
+            ) => {
+                let name = type_.name().to_string();
+                set_possible_values(type_, possible_values).map_err(|reason| {
+                    Error::other(format!(
+                        "Cannot apply {} to type {}: {}",
+                        self, name, reason
+                    ))
+                })?
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for SchemaEdit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let path = self.path().join(".");
+        match self {
+            SchemaEdit::SetRange { min, max, .. } => {
+                write!(f, "range [{}, {}] at `{}`", min, max, path)
+            }
+            SchemaEdit::SetPossibleValues { .. } => write!(f, "possible values at `{}`", path),
+            SchemaEdit::SetConstraint { .. } => write!(f, "constraint at `{}`", path),
+            SchemaEdit::SetForeignKey { .. } => write!(f, "foreign key at `{}`", path),
+            SchemaEdit::Rename { name, .. } => write!(f, "rename of `{}` to `{}`", path, name),
+            SchemaEdit::Drop { .. } => write!(f, "drop of `{}`", path),
+            SchemaEdit::MakeOptional { .. } => write!(f, "optional at `{}`", path),
+            SchemaEdit::MakeRequired { .. } => write!(f, "required at `{}`", path),
+            SchemaEdit::SetProperty { key, .. } => write!(f, "property {} at `{}`", key, path),
+        }
+    }
+}
+
+/// A bound of a range.
+/// Numbers bound Integers, Floats and Durations (in the unit of the type),
+/// texts are parsed with the format of the type.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Bound {
+    Number(f64),
+    Text(String),
+}
+
+impl From<f64> for Bound {
+    fn from(value: f64) -> Self {
+        Bound::Number(value)
+    }
+}
+
+impl From<i64> for Bound {
+    fn from(value: i64) -> Self {
+        Bound::Number(value as f64)
+    }
+}
+
+impl From<&str> for Bound {
+    fn from(value: &str) -> Self {
+        Bound::Text(value.to_string())
+    }
+}
+
+impl From<String> for Bound {
+    fn from(value: String) -> Self {
+        Bound::Text(value)
+    }
+}
+
+impl fmt::Display for Bound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Bound::Number(n) => write!(f, "{}", n),
+            Bound::Text(t) => write!(f, "{}", t),
+        }
+    }
+}
+
+/// Remove the Optional wrapping the type, if any, keeping its properties
+fn make_required(type_: &mut type_::Type) {
+    if let Some(type_::type_::Type::Optional(o)) = type_.type_.as_mut() {
+        let mut inner = o.take_type_();
+        inner.mut_properties().extend(type_.properties.drain());
+        *type_ = inner;
+    }
+}
+
+type ValueResult<T> = result::Result<T, String>;
+
+/// Set the min, max and possible values of a temporal type from sorted values
+macro_rules! set_temporal_possible_values {
+    ($type_:expr, $values:expr, $format:expr) => {
+        if let (Some(min), Some(max)) = ($values.first(), $values.last()) {
+            $type_.set_min(min.print(&$format));
+            $type_.set_max(max.print(&$format));
+        }
+        $type_.set_possible_values($values.iter().map(|v| v.print(&$format)).collect());
+        $type_.set_format($format);
+    };
+}
+
+/// The range of values an Integer base can represent
+pub(super) fn integer_base_range(base: type_::type_::integer::Base) -> (i64, i64) {
+    use type_::type_::integer::Base;
+    match base {
+        Base::INT64 => (i64::MIN, i64::MAX),
+        Base::INT32 => (i32::MIN as i64, i32::MAX as i64),
+        Base::INT16 => (i16::MIN as i64, i16::MAX as i64),
+        Base::INT8 => (i8::MIN as i64, i8::MAX as i64),
+        Base::UINT64 => (0, i64::MAX),
+        Base::UINT32 => (0, u32::MAX as i64),
+        Base::UINT16 => (0, u16::MAX as i64),
+        Base::UINT8 => (0, u8::MAX as i64),
+    }
+}
+
+/// The largest finite value a Float base can represent
+pub(super) fn float_base_max(base: type_::type_::float::Base) -> f64 {
+    use type_::type_::float::Base;
+    match base {
+        Base::FLOAT64 => f64::MAX,
+        Base::FLOAT32 => f32::MAX as f64,
+        Base::FLOAT16 => 65504.,
+    }
+}
+
+fn integer_value(bound: &Bound, (min, max): (i64, i64)) -> ValueResult<i64> {
+    let value = match bound {
+        Bound::Number(n) if n.is_finite() => {
+            let n = n.round();
+            if n < min as f64 || n > max as f64 {
+                return Err(format!("{} is out of [{}, {}]", bound, min, max));
+            }
+            n as i64
+        }
+        Bound::Number(_) => return Err(format!("{} is not finite", bound)),
+        Bound::Text(t) => t
+            .trim()
+            .parse()
+            .map_err(|_| format!("`{}` is not an integer", t))?,
+    };
+    if value < min || value > max {
+        Err(format!("{} is out of [{}, {}]", value, min, max))
+    } else {
+        Ok(value)
+    }
+}
+
+fn float_value(bound: &Bound, max: f64) -> ValueResult<f64> {
+    let value = match bound {
+        Bound::Number(n) => *n,
+        Bound::Text(t) => t
+            .trim()
+            .parse()
+            .map_err(|_| format!("`{}` is not a float", t))?,
+    };
+    if value.is_nan() {
+        Err("NaN is not a valid bound".to_string())
+    } else if value.abs() > max {
+        Err(format!("{} is out of [{}, {}]", value, -max, max))
+    } else {
+        Ok(value)
+    }
+}
+
+/// Temporal values parsed and printed with the format of their type
+pub(super) trait Temporal: Sized + PartialOrd {
+    const DEFAULT_FORMAT: &'static str;
+    fn parse(value: &str, format: &str) -> chrono::ParseResult<Self>;
+    fn print(&self, format: &str) -> String;
+}
+
+macro_rules! impl_temporal {
+    ($temporal:ty, $default_format:expr) => {
+        impl Temporal for $temporal {
+            const DEFAULT_FORMAT: &'static str = $default_format;
+            fn parse(value: &str, format: &str) -> chrono::ParseResult<Self> {
+                <$temporal>::parse_from_str(value, format)
+            }
+            fn print(&self, format: &str) -> String {
+                self.format(format).to_string()
+            }
+        }
+    };
+}
+
+impl_temporal!(NaiveDate, "%Y-%m-%d");
+impl_temporal!(NaiveTime, "%H:%M:%S%.f");
+impl_temporal!(NaiveDateTime, "%Y-%m-%d %H:%M:%S%.f");
+
+fn temporal_value<T: Temporal>(bound: &Bound, format: &str) -> ValueResult<T> {
+    match bound {
+        Bound::Text(t) => T::parse(t, format)
+            .map_err(|err| format!("`{}` does not match format `{}`: {}", t, format, err)),
+        Bound::Number(_) => Err(format!(
+            "{} is not a string with format `{}`",
+            bound, format
+        )),
+    }
+}
+
+/// The format of a temporal type, the default one if it is not set
+pub(super) fn temporal_format<T: Temporal>(format: &str) -> String {
+    if format.is_empty() {
+        T::DEFAULT_FORMAT.to_string()
+    } else {
+        format.to_string()
+    }
+}
+
+fn ordered<T: PartialOrd + fmt::Display>(min: T, max: T) -> ValueResult<(T, T)> {
+    if min > max {
+        Err(format!("min {} is larger than max {}", min, max))
+    } else {
+        Ok((min, max))
+    }
+}
+
+/// Set the range of an Integer, Float, Date, Time, Datetime or Duration
+/// possibly wrapped in an Optional.
+fn set_range(type_: &mut type_::Type, min: &Bound, max: &Bound) -> ValueResult<()> {
+    match type_.type_.as_mut() {
+        Some(type_::type_::Type::Optional(o)) => set_range(o.mut_type(), min, max)?,
+        Some(type_::type_::Type::Integer(t)) => {
+            let base_range = integer_base_range(t.base());
+            let (min, max) = ordered(
+                integer_value(min, base_range)?,
+                integer_value(max, base_range)?,
+            )?;
+            t.set_min(min);
+            t.set_max(max);
+        }
+        Some(type_::type_::Type::Float(t)) => {
+            let base_max = float_base_max(t.base());
+            let (min, max) = ordered(float_value(min, base_max)?, float_value(max, base_max)?)?;
+            t.set_min(min);
+            t.set_max(max);
+        }
+        Some(type_::type_::Type::Date(t)) => {
+            let format = temporal_format::<NaiveDate>(t.format());
+            let (min, max) = ordered(
+                temporal_value::<NaiveDate>(min, &format)?,
+                temporal_value::<NaiveDate>(max, &format)?,
+            )?;
+            t.set_min(min.print(&format));
+            t.set_max(max.print(&format));
+            t.set_format(format);
+        }
+        Some(type_::type_::Type::Time(t)) => {
+            let format = temporal_format::<NaiveTime>(t.format());
+            let (min, max) = ordered(
+                temporal_value::<NaiveTime>(min, &format)?,
+                temporal_value::<NaiveTime>(max, &format)?,
+            )?;
+            t.set_min(min.print(&format));
+            t.set_max(max.print(&format));
+            t.set_format(format);
+        }
+        Some(type_::type_::Type::Datetime(t)) => {
+            let format = temporal_format::<NaiveDateTime>(t.format());
+            let (min, max) = ordered(
+                temporal_value::<NaiveDateTime>(min, &format)?,
+                temporal_value::<NaiveDateTime>(max, &format)?,
+            )?;
+            t.set_min(min.print(&format));
+            t.set_max(max.print(&format));
+            t.set_format(format);
+        }
+        Some(type_::type_::Type::Duration(t)) => {
+            let (min, max) = ordered(
+                integer_value(min, (i64::MIN, i64::MAX))?,
+                integer_value(max, (i64::MIN, i64::MAX))?,
+            )?;
+            t.set_min(min);
+            t.set_max(max);
+        }
+        _ => return Err("ranges are not supported by this type".to_string()),
+    }
+    Ok(())
+}
+
+/// Set the possible values of a type possibly wrapped in an Optional.
+/// The min and max are set to the smallest and largest values.
+fn set_possible_values(type_: &mut type_::Type, possible_values: &[String]) -> ValueResult<()> {
+    let values = || possible_values.iter().map(|v| Bound::Text(v.clone()));
+    match type_.type_.as_mut() {
+        Some(type_::type_::Type::Optional(o)) => {
+            set_possible_values(o.mut_type(), possible_values)?
+        }
+        Some(type_::type_::Type::Text(t)) => t.set_possible_values(possible_values.to_vec()),
+        Some(type_::type_::Type::Integer(t)) => {
+            let base_range = integer_base_range(t.base());
+            let values: Vec<i64> = values()
+                .map(|v| integer_value(&v, base_range))
+                .collect::<ValueResult<_>>()?;
+            if let (Some(min), Some(max)) = (values.iter().min(), values.iter().max()) {
+                t.set_min(*min);
+                t.set_max(*max);
+            }
+            t.set_possible_values(values);
+        }
+        Some(type_::type_::Type::Float(t)) => {
+            let base_max = float_base_max(t.base());
+            let values: Vec<f64> = values()
+                .map(|v| float_value(&v, base_max))
+                .collect::<ValueResult<_>>()?;
+            if let Some((min, max)) = values.iter().cloned().minmax().into_option() {
+                t.set_min(min);
+                t.set_max(max);
+            }
+            t.set_possible_values(values);
+        }
+        // The names of an Enum are filtered, keeping their values. No names means no restriction.
+        Some(type_::type_::Type::Enum(_)) if possible_values.is_empty() => {}
+        Some(type_::type_::Type::Enum(t)) => {
+            if let Some(unknown) = possible_values
+                .iter()
+                .find(|v| !t.name_values().iter().any(|nv| nv.name() == v.as_str()))
+            {
+                return Err(format!("`{}` is not a name of the enum", unknown));
+            }
+            t.name_values
+                .retain(|nv| possible_values.iter().any(|v| v == nv.name()));
+        }
+        // The possible values of a Boolean are a property. No values means no restriction.
+        Some(type_::type_::Type::Boolean(_)) if possible_values.is_empty() => {
+            type_.mut_properties().remove(POSSIBLE_VALUES);
+        }
+        Some(type_::type_::Type::Boolean(_)) => {
+            let values: Vec<bool> = possible_values
+                .iter()
+                .map(|v| v.trim().to_lowercase().parse())
+                .collect::<result::Result<_, _>>()
+                .map_err(|_| format!("{:?} are not booleans", possible_values))?;
+            type_.mut_properties().insert(
+                POSSIBLE_VALUES.to_string(),
+                serde_json::to_string(&values).unwrap(),
+            );
+        }
+        Some(type_::type_::Type::Date(t)) => {
+            let format = temporal_format::<NaiveDate>(t.format());
+            let values = temporal_values::<NaiveDate>(values(), &format)?;
+            set_temporal_possible_values!(t, values, format);
+        }
+        Some(type_::type_::Type::Time(t)) => {
+            let format = temporal_format::<NaiveTime>(t.format());
+            let values = temporal_values::<NaiveTime>(values(), &format)?;
+            set_temporal_possible_values!(t, values, format);
+        }
+        Some(type_::type_::Type::Datetime(t)) => {
+            let format = temporal_format::<NaiveDateTime>(t.format());
+            let values = temporal_values::<NaiveDateTime>(values(), &format)?;
+            set_temporal_possible_values!(t, values, format);
+        }
+        Some(type_::type_::Type::Duration(t)) => {
+            let values: Vec<i64> = values()
+                .map(|v| integer_value(&v, (i64::MIN, i64::MAX)))
+                .collect::<ValueResult<_>>()?;
+            if let (Some(min), Some(max)) = (values.iter().min(), values.iter().max()) {
+                t.set_min(*min);
+                t.set_max(*max);
+            }
+            t.set_possible_values(values);
+        }
+        _ => return Err("possible values are not supported by this type".to_string()),
+    }
+    Ok(())
+}
+
+/// Parse temporal values and sort them
+fn temporal_values<T: Temporal>(
+    values: impl Iterator<Item = Bound>,
+    format: &str,
+) -> ValueResult<Vec<T>> {
+    let mut values: Vec<T> = values
+        .map(|v| temporal_value(&v, format))
+        .collect::<ValueResult<_>>()?;
+    values.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    Ok(values)
+}
+
+/// Edits arranged by path
+#[derive(Debug, Default)]
+struct EditTree {
+    edits: Vec<SchemaEdit>,
+    children: Vec<(String, EditTree)>,
+}
+
+impl EditTree {
+    fn insert(&mut self, path: &[String], edit: SchemaEdit) {
+        match path.split_first() {
+            None => self.edits.push(edit),
+            Some((head, tail)) => {
+                let index = match self.children.iter().position(|(name, _)| name == head) {
+                    Some(index) => index,
+                    None => {
+                        self.children.push((head.clone(), EditTree::default()));
+                        self.children.len() - 1
+                    }
+                };
+                self.children[index].1.insert(tail, edit)
+            }
+        }
+    }
+
+    fn child(&self, name: &str) -> Option<&EditTree> {
+        self.children
+            .iter()
+            .find_map(|(n, tree)| (n == name).then_some(tree))
+    }
+
+    /// Apply the edits of the descendants, then the edits of the node
+    fn apply_to_type(&self, type_: &mut type_::Type, visited_path: &Identifier) -> Result<()> {
+        self.apply_children_to_type(type_, visited_path)?;
+        self.edits
+            .iter()
+            .try_for_each(|edit| edit.apply_to_type(type_))
+    }
+
+    fn apply_children_to_type(
+        &self,
+        type_: &mut type_::Type,
+        visited_path: &Identifier,
+    ) -> Result<()> {
+        let Some((head, _)) = self.children.first() else {
+            return Ok(());
+        };
+        let name = type_.name().to_string();
+        let changes = match type_.type_.as_mut() {
+            Some(type_::type_::Type::Struct(s)) => {
+                self.apply_to_fields(&mut s.fields, visited_path, true, |tree, t, path| {
+                    tree.apply_to_type(t, path)
+                })?
+            }
+            Some(type_::type_::Type::Union(u)) => {
+                self.apply_to_fields(&mut u.fields, visited_path, true, |tree, t, path| {
+                    tree.apply_to_type(t, path)
+                })?
+            }
+            Some(type_::type_::Type::Optional(o)) => {
+                return self.apply_children_to_type(o.mut_type(), visited_path)
+            }
+            _ => {
+                return Err(Error::other(format!(
+                    "Cannot find `{}` in `{}` of type {}",
+                    head, visited_path, name
+                )))
+            }
+        };
+        rename_public_fields(type_, &changes);
+        Ok(())
+    }
+
+    /// Apply the renames, drops and nullability edits to a statistics tree
+    fn apply_to_statistics(&self, statistics: &mut statistics::Statistics) -> Result<()> {
+        self.apply_children_to_statistics(statistics)?;
+        for edit in &self.edits {
+            edit.apply_to_statistics(statistics)?;
+        }
+        Ok(())
+    }
+
+    fn apply_children_to_statistics(&self, statistics: &mut statistics::Statistics) -> Result<()> {
+        let visited_path = Identifier::empty();
+        match statistics.statistics.as_mut() {
+            Some(statistics::statistics::Statistics::Struct(s)) => {
+                self.apply_to_fields(&mut s.fields, &visited_path, false, |tree, s, _| {
+                    tree.apply_to_statistics(s)
+                })?;
+                Ok(())
+            }
+            Some(statistics::statistics::Statistics::Union(u)) => {
+                // A Union whose fields are dropped or resized sums their new sizes
+                let fields_size = |fields: &[statistics::statistics::union::Field]| -> i64 {
+                    fields.iter().map(|f| size_of(f.statistics())).sum()
+                };
+                let before = fields_size(&u.fields);
+                self.apply_to_fields(&mut u.fields, &visited_path, false, |tree, s, _| {
+                    tree.apply_to_statistics(s)
+                })?;
+                let after = fields_size(&u.fields);
+                if after != before {
+                    u.size = after;
+                }
+                Ok(())
+            }
+            Some(statistics::statistics::Statistics::Optional(o)) => {
+                self.apply_children_to_statistics(o.mut_statistics())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Apply the edits of the children to the matching fields.
+    /// Missing fields and name collisions are errors if `strict`.
+    /// It returns the renamed (with their new name) and dropped fields.
+    fn apply_to_fields<F: Field>(
+        &self,
+        fields: &mut Vec<F>,
+        visited_path: &Identifier,
+        strict: bool,
+        apply: impl Fn(&EditTree, &mut F::Value, &Identifier) -> Result<()>,
+    ) -> Result<Vec<(String, Option<String>)>> {
+        let mut indices = vec![];
+        for (name, tree) in &self.children {
+            match fields.iter().position(|f| f.name() == name) {
+                Some(index) => indices.push((index, tree)),
+                None if strict => {
+                    return Err(unresolved_path_error(
+                        name,
+                        visited_path,
+                        fields.iter().map(|f| f.name()),
+                    ))
+                }
+                None => {}
+            }
+        }
+        let mut dropped = vec![];
+        let mut changes = vec![];
+        for (index, tree) in indices {
+            let original_name = fields[index].name().to_string();
+            let field_path = visited_path.clone().with(original_name.clone());
+            apply(tree, fields[index].mut_value(), &field_path)?;
+            for edit in &tree.edits {
+                match edit {
+                    SchemaEdit::Rename { name, .. } => fields[index].set_name(name.clone()),
+                    SchemaEdit::Drop { .. } => dropped.push(index),
+                    _ => {}
+                }
+            }
+            if dropped.contains(&index) {
+                changes.push((original_name, None));
+            } else if fields[index].name() != original_name {
+                changes.push((original_name, Some(fields[index].name().to_string())));
+            }
+        }
+        let mut index = 0;
+        fields.retain(|_| {
+            index += 1;
+            !dropped.contains(&(index - 1))
+        });
+        if let Some(name) = fields.iter().map(|f| f.name()).duplicates().next() {
+            if strict {
+                return Err(Error::other(format!(
+                    "Several fields are named `{}` in `{}`",
+                    name, visited_path
+                )));
+            }
+        }
+        Ok(changes)
+    }
+}
+
+/// A named field of a Struct or a Union
+trait Field {
+    type Value;
+    fn name(&self) -> &str;
+    fn set_name(&mut self, name: String);
+    fn mut_value(&mut self) -> &mut Self::Value;
+}
+
+macro_rules! impl_field {
+    ($field:ty, $value:ty, $mut_value:ident) => {
+        impl Field for $field {
+            type Value = $value;
+            fn name(&self) -> &str {
+                self.name()
+            }
+            fn set_name(&mut self, name: String) {
+                self.set_name(name)
+            }
+            fn mut_value(&mut self) -> &mut Self::Value {
+                self.$mut_value()
+            }
+        }
+    };
+}
+
+impl_field!(type_::type_::struct_::Field, type_::Type, mut_type);
+impl_field!(type_::type_::union::Field, type_::Type, mut_type);
+impl_field!(
+    statistics::statistics::struct_::Field,
+    statistics::Statistics,
+    mut_statistics
+);
+impl_field!(
+    statistics::statistics::union::Field,
+    statistics::Statistics,
+    mut_statistics
+);
+
+impl Dataset {
+    /// Apply a list of edits to the schema in a single traversal.
+    /// It fails without applying any edit if one of them cannot be applied.
+    pub fn apply_edits(&self, edits: &[SchemaEdit]) -> Result<Self> {
+        let mut tree = EditTree::default();
+        for edit in self.canonical_edits(edits)? {
+            let path = edit.path().to_vec();
+            tree.insert(&path, edit)
+        }
+        let mut schema = self.schema.clone();
+        tree.apply_to_type(schema.mut_type(), &Identifier::empty())?;
+        for edit in edits {
+            let path = data_path(edit.path());
+            match edit {
+                SchemaEdit::Rename { name, .. } => schema.rename_paths(path, Some(name)),
+                SchemaEdit::Drop { .. } => schema.rename_paths(path, None),
+                SchemaEdit::SetForeignKey { references, .. } => {
+                    schema.set_foreign_key(path, references.as_deref().map(data_path))
+                }
+                _ => {}
+            }
+        }
+        if edits
+            .iter()
+            .any(|edit| matches!(edit, SchemaEdit::SetForeignKey { .. }))
+        {
+            schema.check_foreign_keys()?;
+        }
+        let data_tree = if self.schema_has_admin_columns() {
+            tree.child(SARUS_DATA)
+        } else {
+            Some(&tree)
+        };
+        let size = match (self.size.clone(), data_tree) {
+            (Some(mut size), Some(data_tree)) => {
+                data_tree.apply_to_statistics(size.mut_statistics())?;
+                Some(size)
+            }
+            (size, _) => size,
+        };
+        Ok(Dataset::new(self.dataset.clone(), schema, size))
+    }
+
+    /// Make the SARUS_DATA admin wrapper explicit in the paths and map
+    /// the paths referring to renamed fields to the original names.
+    /// Paths still using the name of a field renamed by a previous edit fail.
+    fn canonical_edits(&self, edits: &[SchemaEdit]) -> Result<Vec<SchemaEdit>> {
+        let admin_names: Vec<&str> = self
+            .admin_names_and_types()
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        let mut renames: Vec<(Vec<String>, Vec<String>)> = vec![];
+        let mut drops: Vec<Vec<String>> = vec![];
+        edits
+            .iter()
+            .map(|edit| {
+                let mut edit = edit.clone();
+                let path = edit.mut_path();
+                if self.schema_has_admin_columns()
+                    && path.first().is_some_and(|head| {
+                        head != SARUS_DATA && !admin_names.contains(&head.as_str())
+                    })
+                {
+                    path.insert(0, SARUS_DATA.to_string());
+                }
+                // Undo the previous renames, from the last one: a path referring to the name of
+                // a field before it was renamed is stale
+                let current = path.clone();
+                for (before, after) in renames.iter().rev() {
+                    if path.starts_with(after) {
+                        path.splice(..after.len(), before.iter().cloned());
+                    } else if path.starts_with(before) {
+                        return Err(Error::other(format!(
+                            "Cannot apply {}: `{}` is renamed",
+                            edit,
+                            before.join(".")
+                        )));
+                    }
+                }
+                if let Some(dropped) = drops.iter().find(|dropped| path.starts_with(dropped)) {
+                    return Err(Error::other(format!(
+                        "Cannot apply {}: `{}` is dropped",
+                        edit,
+                        dropped.join(".")
+                    )));
+                }
+                let path = path.clone();
+                match &edit {
+                    SchemaEdit::Rename { .. } | SchemaEdit::Drop { .. } if path.is_empty() => {
+                        return Err(Error::other(format!(
+                            "Cannot apply {} to the schema root",
+                            edit
+                        )))
+                    }
+                    SchemaEdit::Rename { name, .. } => {
+                        let mut renamed = current.clone();
+                        renamed.pop();
+                        renamed.push(name.clone());
+                        renames.push((current, renamed));
+                    }
+                    SchemaEdit::Drop { .. } => drops.push(path),
+                    _ => {}
+                }
+                Ok(edit)
+            })
+            .collect()
+    }
+}
+
+/// A path relative to the data part of the schema
+fn data_path(path: &[String]) -> &[String] {
+    path.strip_prefix(&[SARUS_DATA.to_string()]).unwrap_or(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        data_spec::{ForeignKey, CONSTRAINT_NOT_NULL, CONSTRAINT_PRIMARY_KEY},
+        protobuf::{parse_from_str, print_to_string},
+    };
+    use anyhow::Result;
+    use qrlew::{
+        data_type::{DataType, DataTyped},
+        privacy_unit_tracking::privacy_unit::Step,
+        relation::{Constraint, Variant as _},
+    };
+
+    const SCHEMA: &str = r#"
+    {
+        "name": "a",
+        "type": {
+            "name": "Struct",
+            "struct": {
+                "fields": [
+                    {"name": "sarus_data", "type": {"name": "Union", "union": {"fields": [
+                        {"name": "users", "type": {"name": "Struct", "struct": {"fields": [
+                            {"name": "id", "type": {"name": "Integer", "integer": {"min": "0", "max": "1000"}}},
+                            {"name": "name", "type": {"name": "Text", "text": {"encoding": "UTF-8"}}},
+                            {"name": "age", "type": {"name": "Optional", "optional": {"type": {"name": "Float", "float": {"min": 0, "max": 200}}}}}
+                        ]}}},
+                        {"name": "orders", "type": {"name": "Struct", "struct": {"fields": [
+                            {"name": "user_id", "type": {"name": "Integer", "integer": {"min": "0", "max": "1000"}}}
+                        ]}}}
+                    ]}}},
+                    {"name": "sarus_is_public", "type": {"name": "Boolean", "boolean": {}}},
+                    {"name": "sarus_privacy_unit", "type": {"name": "Optional", "optional": {"type": {"name": "Id", "id": {"base": "STRING"}}}}},
+                    {"name": "sarus_weights", "type": {"name": "Float", "float": {"min": 0, "max": 1}}}
+                ]
+            }
+        }
+    }
+    "#;
+
+    const SIZE: &str = r#"
+    {
+        "statistics": {"name": "Union", "union": {"fields": [
+            {"name": "users", "statistics": {"name": "Struct", "struct": {"size": "100", "fields": []}}},
+            {"name": "orders", "statistics": {"name": "Struct", "struct": {"size": "300", "fields": []}}}
+        ]}}
+    }
+    "#;
+
+    fn dataset() -> Dataset {
+        Dataset::new(
+            parse_from_str("{}").unwrap(),
+            parse_from_str(SCHEMA).unwrap(),
+            parse_from_str(SIZE).ok(),
+        )
+    }
+
+    fn path(path: &[&str]) -> Vec<String> {
+        path.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_apply_edits() -> Result<()> {
+        let edits = vec![
+            SchemaEdit::SetRange {
+                path: path(&["users", "age"]),
+                min: Bound::Number(18.),
+                max: Bound::Number(99.),
+            },
+            SchemaEdit::Rename {
+                path: path(&["users", "name"]),
+                name: "first_name".to_string(),
+            },
+            SchemaEdit::SetPossibleValues {
+                path: path(&["users", "first_name"]),
+                possible_values: path(&["Alice", "Bob"]),
+            },
+            SchemaEdit::MakeOptional {
+                path: path(&["users", "id"]),
+            },
+            SchemaEdit::Rename {
+                path: path(&["orders"]),
+                name: "purchases".to_string(),
+            },
+            SchemaEdit::Drop {
+                path: path(&["purchases", "user_id"]),
+            },
+            SchemaEdit::SetProperty {
+                path: path(&["users"]),
+                key: "doc".to_string(),
+                value: Some("The users".to_string()),
+            },
+        ];
+        let ds = dataset().apply_edits(&edits)?;
+        println!("{}", print_to_string(ds.schema()).unwrap());
+        let relations = ds.relations();
+        let users = relations.get(&path(&["a", "users"])).unwrap();
+        assert_eq!(
+            users.schema().field("age")?.data_type(),
+            DataType::optional(DataType::float_interval(18., 99.))
+        );
+        assert_eq!(
+            users.schema().field("first_name")?.data_type(),
+            DataType::text_values(["Alice".to_string(), "Bob".to_string()])
+        );
+        assert_eq!(
+            users.schema().field("id")?.data_type(),
+            DataType::optional(DataType::integer_interval(0, 1000))
+        );
+        assert!(users.schema().field("name").is_err());
+        let purchases = relations.get(&path(&["a", "purchases"])).unwrap();
+        assert!(purchases.schema().field("user_id").is_err());
+        // The size follows the renamed table
+        assert_eq!(purchases.size().max(), Some(&300));
+        Ok(())
+    }
+
+    #[test]
+    fn test_nullable() -> Result<()> {
+        const SIZE: &str = r#"
+        {
+            "statistics": {"name": "Union", "union": {"fields": [
+                {"name": "users", "statistics": {"name": "Struct", "struct": {"size": "100", "fields": [
+                    {"name": "id", "statistics": {"name": "Integer", "integer": {"size": "100"}}},
+                    {"name": "name", "statistics": {"name": "Text", "text": {"size": "100"}}},
+                    {"name": "age", "statistics": {"name": "Optional", "optional": {"size": "100",
+                        "statistics": {"name": "Float", "float": {"size": "90"}}}}}
+                ]}}}
+            ]}}
+        }
+        "#;
+        let dataset = Dataset::new(
+            parse_from_str("{}")?,
+            parse_from_str(SCHEMA)?,
+            parse_from_str(SIZE).ok(),
+        );
+        let dataset = dataset.with_nullable(["users", "id"], true)?;
+        let users = dataset.relations()[["a", "users"]].clone();
+        assert_eq!(
+            users.schema().field("id")?.data_type(),
+            DataType::optional(DataType::integer_interval(0, 1000))
+        );
+        let users_statistics = &dataset.size_statistics().unwrap().union().fields[0];
+        let id_statistics = users_statistics.statistics().struct_().fields[0].statistics();
+        assert_eq!(id_statistics.optional().size(), 100);
+        assert_eq!(id_statistics.optional().statistics().integer().size(), 100);
+        // Back to non-nullable
+        let dataset = dataset.with_nullable(["users", "id"], false)?;
+        let users_statistics = &dataset.size_statistics().unwrap().union().fields[0];
+        assert!(users_statistics.statistics().struct_().fields[0]
+            .statistics()
+            .has_integer());
+        // The statistics count 10 nulls
+        assert!(dataset.with_nullable(["users", "age"], false).is_err());
+        // The nulls of skeleton statistics are unknown
+        let skeleton = Dataset::new(
+            parse_from_str("{}")?,
+            parse_from_str(SCHEMA)?,
+            parse_from_str(&SIZE.replace(r#""float": {"size": "90"}"#, r#""float": {}"#)).ok(),
+        );
+        assert!(skeleton.with_nullable(["users", "age"], false).is_ok());
+        // No statistics are available for the orders
+        let dataset = dataset.with_nullable(["orders", "user_id"], true)?;
+        assert!(matches!(
+            dataset.relations()[["a", "orders"]]
+                .schema()
+                .field("user_id")?
+                .data_type(),
+            DataType::Optional(_)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_apply_edits_is_atomic() -> Result<()> {
+        let ds = dataset();
+        let edits = vec![
+            SchemaEdit::SetRange {
+                path: path(&["users", "age"]),
+                min: Bound::Number(18.),
+                max: Bound::Number(99.),
+            },
+            SchemaEdit::SetRange {
+                path: path(&["users", "name"]),
+                min: Bound::Number(0.),
+                max: Bound::Number(1.),
+            },
+        ];
+        assert!(ds.apply_edits(&edits).is_err());
+        let edits = vec![
+            SchemaEdit::Drop {
+                path: path(&["users"]),
+            },
+            SchemaEdit::MakeRequired {
+                path: path(&["users", "age"]),
+            },
+        ];
+        assert!(ds.apply_edits(&edits).is_err());
+        let edits = vec![SchemaEdit::Rename {
+            path: path(&["users", "name"]),
+            name: "id".to_string(),
+        }];
+        assert!(ds.apply_edits(&edits).is_err());
+        let edits = vec![SchemaEdit::Drop { path: vec![] }];
+        assert!(ds.apply_edits(&edits).is_err());
+        // The old name of a renamed field cannot be used anymore
+        let edits = vec![
+            SchemaEdit::Rename {
+                path: path(&["users", "name"]),
+                name: "first_name".to_string(),
+            },
+            SchemaEdit::SetPossibleValues {
+                path: path(&["users", "name"]),
+                possible_values: path(&["Alice", "Bob"]),
+            },
+        ];
+        assert!(ds.apply_edits(&edits).is_err());
+        let edits = vec![
+            SchemaEdit::Rename {
+                path: path(&["users"]),
+                name: "clients".to_string(),
+            },
+            SchemaEdit::Rename {
+                path: path(&["clients"]),
+                name: "customers".to_string(),
+            },
+            SchemaEdit::Drop {
+                path: path(&["clients", "age"]),
+            },
+        ];
+        assert!(ds.apply_edits(&edits).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_swap_names() -> Result<()> {
+        let edits = vec![
+            SchemaEdit::Rename {
+                path: path(&["users"]),
+                name: "tmp".to_string(),
+            },
+            SchemaEdit::Rename {
+                path: path(&["orders"]),
+                name: "users".to_string(),
+            },
+            SchemaEdit::Rename {
+                path: path(&["tmp"]),
+                name: "orders".to_string(),
+            },
+        ];
+        let ds = dataset().apply_edits(&edits)?;
+        let relations = ds.relations();
+        let orders = relations.get(&path(&["a", "orders"])).unwrap();
+        assert!(orders.schema().field("age").is_ok());
+        assert_eq!(orders.size().max(), Some(&100));
+        // A field of a renamed table is renamed, then edited under its new names
+        let edits = vec![
+            SchemaEdit::Rename {
+                path: path(&["users"]),
+                name: "clients".to_string(),
+            },
+            SchemaEdit::Rename {
+                path: path(&["clients", "name"]),
+                name: "first_name".to_string(),
+            },
+            SchemaEdit::SetPossibleValues {
+                path: path(&["clients", "first_name"]),
+                possible_values: path(&["Alice", "Bob"]),
+            },
+        ];
+        let ds = dataset().apply_edits(&edits)?;
+        assert_eq!(
+            ds.relations()[["a", "clients"]]
+                .schema()
+                .field("first_name")?
+                .data_type(),
+            DataType::text_values(["Alice".to_string(), "Bob".to_string()])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_constraints() -> Result<()> {
+        let dataset = dataset()
+            .apply_edits(&[SchemaEdit::SetProperty {
+                path: path(&["users", "id"]),
+                key: "comment".to_string(),
+                value: Some("user id".to_string()),
+            }])?
+            .with_constraint(["users", "id"], Some(CONSTRAINT_PRIMARY_KEY))?
+            .with_constraint(["users", "age"], Some(CONSTRAINT_NOT_NULL))?
+            .with_foreign_key(["orders", "user_id"], ["sarus_data", "users", "id"])?;
+        let users = dataset.relations()[["a", "users"]].clone();
+        let orders = dataset.relations()[["a", "orders"]].clone();
+        assert_eq!(
+            users.schema().field("id")?.constraint(),
+            Some(Constraint::PrimaryKey)
+        );
+        assert_eq!(
+            users.schema().field("age")?.data_type(),
+            DataType::float_interval(0., 200.)
+        );
+        // Not-null is the type, it is not stored as a constraint
+        assert_eq!(users.schema().field("age")?.constraint(), None);
+        assert_eq!(
+            orders.schema().field("user_id")?.constraint(),
+            Some(Constraint::ForeignKey)
+        );
+        let Some(type_::type_::Type::Union(u)) = dataset.schema_type_data().type_.as_ref() else {
+            panic!()
+        };
+        let id = u.fields[0].type_().struct_().fields[0].type_();
+        assert_eq!(id.properties().get("comment").unwrap(), "user id");
+        assert_eq!(
+            id.properties().get(CONSTRAINT).unwrap(),
+            CONSTRAINT_PRIMARY_KEY
+        );
+        // Foreign keys are links between relations
+        let foreign_key = ForeignKey {
+            relation: ["a", "orders"].into(),
+            column: "user_id".to_string(),
+            referred_relation: ["a", "users"].into(),
+            referred_column: "id".to_string(),
+        };
+        assert_eq!(dataset.foreign_keys(), vec![foreign_key.clone()]);
+        assert_eq!(
+            Step::from(&foreign_key),
+            Step::from(("user_id", "a.users", "id"))
+        );
+        let renamed = dataset.rename_table(["users"], "customers")?;
+        assert_eq!(
+            renamed.foreign_keys()[0].referred_relation,
+            ["a", "customers"].into()
+        );
+        assert!(dataset
+            .without_foreign_key(["orders", "user_id"])?
+            .foreign_keys()
+            .is_empty());
+        // Errors
+        assert!(dataset
+            .with_constraint(["users", "id"], Some("_CHECK_"))
+            .is_err());
+        assert!(dataset
+            .with_foreign_key(["orders", "user_id"], ["users", "uid"])
+            .is_err());
+        assert!(dataset
+            .with_foreign_key(["orders", "user_id"], ["users"])
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_serialize_edits() -> Result<()> {
+        let edits = vec![
+            SchemaEdit::SetConstraint {
+                path: path(&["users", "id"]),
+                constraint: Some("_UNIQUE_".to_string()),
+            },
+            SchemaEdit::MakeRequired {
+                path: path(&["users", "age"]),
+            },
+        ];
+        let json = serde_json::to_string(&edits).unwrap();
+        println!("{json}");
+        let replayed: Vec<SchemaEdit> = serde_json::from_str(&json).unwrap();
+        assert_eq!(edits, replayed);
+        let ds = dataset().apply_edits(&replayed)?;
+        let relations = ds.relations();
+        let users = relations.get(&path(&["a", "users"])).unwrap();
+        assert_eq!(
+            users.schema().field("age")?.data_type(),
+            DataType::float_interval(0., 200.)
+        );
+        Ok(())
+    }
+
+    const TEMPORAL_SCHEMA: &str = r#"
+    {
+        "name": "events",
+        "type": {"name": "Struct", "struct": {"fields": [
+            {"name": "day", "type": {"name": "Date", "date": {"format": "%Y-%m-%d", "min": "0001-01-01", "max": "9999-12-31"}}},
+            {"name": "hour", "type": {"name": "Time", "time": {"format": "%H:%M:%S", "min": "00:00:00", "max": "23:59:59"}}},
+            {"name": "at", "type": {"name": "Optional", "optional": {"type": {"name": "Datetime", "datetime": {"format": "%Y-%m-%d %H:%M:%S", "min": "0001-01-01 00:00:00", "max": "9999-12-31 00:00:00"}}}}},
+            {"name": "elapsed", "type": {"name": "Duration", "duration": {"unit": "s", "min": "0", "max": "1000000"}}},
+            {"name": "level", "type": {"name": "Integer", "integer": {"base": "INT8", "min": "-128", "max": "127"}}},
+            {"name": "score", "type": {"name": "Float", "float": {"base": "FLOAT32", "min": 0, "max": 1}}},
+            {"name": "color", "type": {"name": "Enum", "enum": {"name_values": [{"name": "red", "value": "0"}, {"name": "green", "value": "1"}, {"name": "blue", "value": "2"}]}}},
+            {"name": "valid", "type": {"name": "Boolean", "boolean": {}}}
+        ]}}
+    }
+    "#;
+
+    fn temporal_dataset() -> Dataset {
+        Dataset::new(
+            parse_from_str("{}").unwrap(),
+            parse_from_str(TEMPORAL_SCHEMA).unwrap(),
+            None,
+        )
+    }
+
+    #[test]
+    fn test_temporal_ranges() -> Result<()> {
+        let ds = temporal_dataset()
+            .with_range(["day"], "2020-01-01", "2020-12-31")?
+            .with_range(["hour"], "08:00:00", "18:00:00")?
+            .with_range(["at"], "2020-01-01 00:00:00", "2021-01-01 00:00:00")?
+            .with_range(["elapsed"], 0., 3600.)?;
+        let relations = ds.relations();
+        let events = relations.get(&path(&["events"])).unwrap();
+        assert_eq!(
+            events.schema().field("day")?.data_type(),
+            DataType::date_interval(
+                NaiveDate::from_ymd_opt(2020, 1, 1).unwrap(),
+                NaiveDate::from_ymd_opt(2020, 12, 31).unwrap()
+            )
+        );
+        assert_eq!(
+            events.schema().field("hour")?.data_type(),
+            DataType::time_interval(
+                NaiveTime::from_hms_opt(8, 0, 0).unwrap(),
+                NaiveTime::from_hms_opt(18, 0, 0).unwrap()
+            )
+        );
+        assert_eq!(
+            events.schema().field("at")?.data_type(),
+            DataType::optional(DataType::date_time_interval(
+                NaiveDate::from_ymd_opt(2020, 1, 1)
+                    .unwrap()
+                    .and_hms_opt(0, 0, 0)
+                    .unwrap(),
+                NaiveDate::from_ymd_opt(2021, 1, 1)
+                    .unwrap()
+                    .and_hms_opt(0, 0, 0)
+                    .unwrap()
+            ))
+        );
+        assert_eq!(
+            events.schema().field("elapsed")?.data_type(),
+            DataType::duration_interval(chrono::Duration::zero(), chrono::Duration::hours(1))
+        );
+        // Unparsable, reversed and out of range bounds
+        let ds = temporal_dataset();
+        assert!(ds.with_range(["day"], "2020/01/01", "2020/12/31").is_err());
+        assert!(ds.with_range(["day"], "2020-12-31", "2020-01-01").is_err());
+        assert!(ds.with_range(["day"], 0., 1.).is_err());
+        assert!(ds.with_range(["level"], -200., 0.).is_err());
+        assert!(ds.with_range(["score"], 0., 1e40).is_err());
+        assert!(ds.with_range(["color"], 0., 1.).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_possible_values() -> Result<()> {
+        let values = |values: &[&str]| path(values);
+        let ds = temporal_dataset()
+            .with_possible_values(["level"], &values(&["1", "5", "3"]))?
+            .with_possible_values(["score"], &values(&["0.5", "0.25"]))?
+            .with_possible_values(["color"], &values(&["red", "blue"]))?
+            .with_possible_values(["valid"], &values(&["true"]))?
+            .with_possible_values(["color"], &[])?
+            .with_possible_values(["day"], &values(&["2020-02-01", "2020-01-01"]))?;
+        let relations = ds.relations();
+        let events = relations.get(&path(&["events"])).unwrap();
+        assert_eq!(
+            events.schema().field("level")?.data_type(),
+            DataType::integer_values([1, 3, 5])
+        );
+        assert_eq!(
+            events.schema().field("score")?.data_type(),
+            DataType::float_values([0.25, 0.5])
+        );
+        assert_eq!(
+            events.schema().field("color")?.data_type(),
+            DataType::Enum([("red", 0), ("blue", 2)].into_iter().collect())
+        );
+        assert_eq!(
+            events.schema().field("valid")?.data_type(),
+            DataType::boolean_values([true])
+        );
+        assert_eq!(
+            events.schema().field("day")?.data_type(),
+            DataType::date_values([
+                NaiveDate::from_ymd_opt(2020, 1, 1).unwrap(),
+                NaiveDate::from_ymd_opt(2020, 2, 1).unwrap()
+            ])
+        );
+        let ds = temporal_dataset();
+        assert!(ds
+            .with_possible_values(["level"], &values(&["1", "500"]))
+            .is_err());
+        assert!(ds
+            .with_possible_values(["color"], &values(&["purple"]))
+            .is_err());
+        assert!(ds
+            .with_possible_values(["valid"], &values(&["yes"]))
+            .is_err());
+        assert!(ds
+            .with_possible_values(["day"], &values(&["tomorrow"]))
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_boolean_possible_values() -> Result<()> {
+        let ds = temporal_dataset().with_possible_values(["valid"], &path(&["True"]))?;
+        let valid = ds.schema_type_data().struct_().fields[7].type_();
+        assert_eq!(valid.to_compact_string(), "bool{true}");
+        assert_eq!(
+            valid.to_json_schema(),
+            serde_json::json!({"type": "boolean", "enum": [true]})
+        );
+        let relations = ds.relations();
+        let events = relations.get(&path(&["events"])).unwrap();
+        assert_eq!(
+            events.schema().field("valid")?.data_type(),
+            DataType::boolean_values([true])
+        );
+        // An empty list lifts the restriction
+        let ds = ds.with_possible_values(["valid"], &[])?;
+        let valid = ds.schema_type_data().struct_().fields[7].type_();
+        assert!(valid.properties.is_empty());
+        let relations = ds.relations();
+        let events = relations.get(&path(&["events"])).unwrap();
+        assert_eq!(
+            events.schema().field("valid")?.data_type(),
+            DataType::boolean()
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,268 @@
+//! Graphviz entity-relationship diagrams of datasets.
+//!
+//! Each relation is a node listing its columns. Foreign keys are solid edges, Id references
+//! dashed edges and Links dotted edges. The tables of the protected entity and the other private
+//! tables are filled with distinct colors, and the chains of edges leading to the protected entity
+//! (the privacy unit paths) are drawn in red.
+
+use super::{
+    display::{constraint_string, dotted, escape},
+    table_structs, Dataset, ForeignKey,
+};
+use crate::protobuf::{links, type_};
+use qrlew::{
+    expr::identifier::Identifier,
+    relation::{Constraint, Variant as _},
+};
+use std::fmt::Write as _;
+
+const PROTECTED_COLOR: &str = "#f4a582";
+const PRIVATE_COLOR: &str = "#fddbc7";
+const PUBLIC_COLOR: &str = "#d1e5f0";
+const PRIVACY_UNIT_COLOR: &str = "#b2182b";
+
+impl Dataset {
+    /// The entity-relationship diagram of the dataset in the Graphviz DOT language
+    pub fn to_dot(&self) -> String {
+        self.dot(None)
+    }
+
+    /// The entity-relationship diagram of the dataset with the edges of `links`
+    pub fn to_dot_with_links(&self, links: &links::Links) -> String {
+        self.dot(Some(links))
+    }
+
+    fn dot(&self, links: Option<&links::Links>) -> String {
+        let schema_name = self.schema().name().to_string();
+        let relations = self.relations();
+        let foreign_keys = self.foreign_keys();
+        let admin_columns = self.admin_names_and_types();
+        let public_tables = self.public_tables();
+        let protected_tables = self.protected_tables();
+        let mut dot = format!(
+            "digraph {} {{\n  rankdir=LR;\n  node [shape=plaintext, fontname=\"Helvetica\"];\n  edge [fontname=\"Helvetica\", fontsize=10];\n",
+            quote(&schema_name)
+        );
+        // The port of each column, by relation
+        let mut ports: Vec<(Identifier, Vec<String>)> = vec![];
+        for (identifier, schema_struct, _) in table_structs(self.schema_type_data(), None) {
+            let identifier = self.full_path(&identifier);
+            let relation = relations.get(identifier.as_ref());
+            let protected = protected_tables.contains(&identifier);
+            let public = public_tables.contains(&identifier);
+            let (color, note) = match (protected, public) {
+                (true, _) => (PROTECTED_COLOR, " (protected entity)"),
+                (false, true) => (PUBLIC_COLOR, " (public)"),
+                (false, false) => (PRIVATE_COLOR, ""),
+            };
+            let mut label = format!(
+                "<table border=\"0\" cellborder=\"1\" cellspacing=\"0\" cellpadding=\"4\">\
+                 <tr><td bgcolor=\"{}\"><b>{}</b>{}</td></tr>",
+                color,
+                escape(&dotted(&identifier)),
+                note
+            );
+            let columns: Vec<(&str, &type_::Type)> = schema_struct
+                .fields
+                .iter()
+                .map(|f| (f.name(), f.type_()))
+                .chain(admin_columns.iter().cloned())
+                .collect();
+            for (i, (column, type_)) in columns.iter().enumerate() {
+                let constraint = relation
+                    .and_then(|relation| relation.schema().field(column).ok())
+                    .and_then(|field| field.constraint());
+                let constraint = match constraint {
+                    // Foreign keys are drawn as edges
+                    Some(Constraint::ForeignKey) => None,
+                    constraint => constraint_string(constraint, &identifier, column, &[]),
+                };
+                let admin = i >= schema_struct.fields.len();
+                let _ = write!(
+                    label,
+                    "<tr><td port=\"c{}\" align=\"left\">{}{}: {}{}{}</td></tr>",
+                    i,
+                    if admin { "<font color=\"grey\">" } else { "" },
+                    escape(column),
+                    escape(&type_.to_compact_string()),
+                    constraint.map_or(String::new(), |c| format!(" <i>{}</i>", escape(&c))),
+                    if admin { "</font>" } else { "" },
+                );
+            }
+            label.push_str("</table>");
+            let _ = writeln!(
+                dot,
+                "  {} [label=<{}>];",
+                quote(&dotted(&identifier)),
+                label
+            );
+            ports.push((
+                identifier,
+                columns.iter().map(|(name, _)| name.to_string()).collect(),
+            ));
+        }
+        let port = |relation: &Identifier, column: &str| -> Option<String> {
+            let (_, columns) = ports.iter().find(|(r, _)| r == relation)?;
+            let i = columns.iter().position(|c| c == column)?;
+            Some(format!("{}:c{}", quote(&dotted(relation)), i))
+        };
+        let mut edges: Vec<(ForeignKey, &str)> = foreign_keys
+            .iter()
+            .map(|fk| (fk.clone(), "label=\"FK\""))
+            .collect();
+        for fk in self.id_references() {
+            if !foreign_keys.contains(&fk) {
+                edges.push((fk, "label=\"Id\", style=dashed"));
+            }
+        }
+        for link in links.iter().flat_map(|links| &links.links_statistics) {
+            let pointing = link
+                .pointing
+                .as_ref()
+                .and_then(|path| self.path_column(path));
+            let pointed = link
+                .pointed
+                .as_ref()
+                .and_then(|path| self.path_column(path));
+            if let (Some((relation, column)), Some((referred_relation, referred_column))) =
+                (pointing, pointed)
+            {
+                let fk = ForeignKey {
+                    relation,
+                    column,
+                    referred_relation,
+                    referred_column,
+                };
+                edges.push((fk, "label=\"link\", style=dotted"));
+            }
+        }
+        // The privacy unit paths are the chains of edges leading to the protected entity
+        let mut privacy_unit_tables: Vec<&Identifier> = ports
+            .iter()
+            .map(|(relation, _)| relation)
+            .filter(|relation| protected_tables.contains(relation))
+            .collect();
+        let mut privacy_unit_edges = vec![false; edges.len()];
+        while let Some(i) = (0..edges.len()).find(|&i| {
+            !privacy_unit_edges[i] && privacy_unit_tables.contains(&&edges[i].0.referred_relation)
+        }) {
+            privacy_unit_edges[i] = true;
+            privacy_unit_tables.push(&edges[i].0.relation);
+        }
+        for ((fk, attributes), privacy_unit) in edges.iter().zip(privacy_unit_edges) {
+            if let (Some(from), Some(to)) = (
+                port(&fk.relation, &fk.column),
+                port(&fk.referred_relation, &fk.referred_column),
+            ) {
+                let _ = writeln!(
+                    dot,
+                    "  {} -> {} [{}{}];",
+                    from,
+                    to,
+                    attributes,
+                    if privacy_unit {
+                        format!(", color=\"{}\", penwidth=2", PRIVACY_UNIT_COLOR)
+                    } else {
+                        String::new()
+                    }
+                );
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+fn quote(id: &str) -> String {
+    format!("\"{}\"", id.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protobuf::parse_from_str;
+    use anyhow::Result;
+
+    const SCHEMA: &str = r#"
+    {
+        "name": "shop",
+        "properties": {"foreign_keys": "[[[\"visits\",\"user_id\"],[\"users\",\"id\"]]]", "public_fields": "[[\"cities\"]]"},
+        "protected": {"label": "sarus_data", "paths": [{"label": "users"}]},
+        "type": {"name": "Struct", "struct": {"fields": [
+            {"name": "sarus_data", "type": {"name": "Union", "union": {"fields": [
+                {"name": "users", "type": {"name": "Struct", "struct": {"fields": [
+                    {"name": "id", "type": {"name": "Integer", "properties": {"_CONSTRAINT_": "_PRIMARY_KEY_"},
+                        "integer": {"base": "INT64", "min": "0", "max": "120"}}},
+                    {"name": "city", "type": {"name": "Text", "text": {"encoding": "UTF-8"}}}
+                ]}}},
+                {"name": "visits", "type": {"name": "Struct", "struct": {"fields": [
+                    {"name": "user_id", "type": {"name": "Integer", "integer": {"base": "INT64", "min": "0", "max": "120"}}},
+                    {"name": "city", "type": {"name": "Id", "id": {"base": "STRING",
+                        "reference": {"label": "sarus_data", "paths": [{"label": "cities", "paths": [{"label": "name"}]}]}}}}
+                ]}}},
+                {"name": "cities", "type": {"name": "Struct", "struct": {"fields": [
+                    {"name": "name", "type": {"name": "Text", "properties": {"_CONSTRAINT_": "_UNIQUE_"}, "text": {"encoding": "UTF-8"}}}
+                ]}}}
+            ]}}},
+            {"name": "sarus_is_public", "type": {"name": "Boolean", "boolean": {}}},
+            {"name": "sarus_privacy_unit", "type": {"name": "Optional", "optional": {"type": {"name": "Id", "id": {"base": "STRING"}}}}},
+            {"name": "sarus_weights", "type": {"name": "Float", "float": {"min": 0, "max": 1}}}
+        ]}}
+    }
+    "#;
+
+    #[test]
+    fn test_to_dot() -> Result<()> {
+        let dataset = Dataset::new(parse_from_str("{}")?, parse_from_str(SCHEMA)?, None);
+        let dot = dataset.to_dot();
+        println!("{}", dot);
+        assert!(dot.starts_with("digraph \"shop\" {"));
+        assert!(dot.contains(&format!(
+            "<td bgcolor=\"{}\"><b>shop.users</b> (protected entity)</td>",
+            PROTECTED_COLOR
+        )));
+        assert!(dot.contains(&format!(
+            "<td bgcolor=\"{}\"><b>shop.visits</b></td>",
+            PRIVATE_COLOR
+        )));
+        assert!(dot.contains("id: int[0, 120] <i>primary key</i>"));
+        assert!(dot.contains("<font color=\"grey\">sarus_weights: float[0, 1]</font>"));
+        assert!(dot.contains(&format!(
+            "\"shop.visits\":c0 -> \"shop.users\":c0 [label=\"FK\", color=\"{}\", penwidth=2];",
+            PRIVACY_UNIT_COLOR
+        )));
+        assert!(
+            dot.contains("\"shop.visits\":c1 -> \"shop.cities\":c0 [label=\"Id\", style=dashed];")
+        );
+        let links: links::Links = parse_from_str(
+            r#"{"links_statistics": [{
+                "pointing": {"label": "sarus_data", "paths": [{"label": "users", "paths": [{"label": "city"}]}]},
+                "pointed": {"label": "sarus_data", "paths": [{"label": "cities", "paths": [{"label": "name"}]}]}
+            }]}"#,
+        )?;
+        let dot = dataset.to_dot_with_links(&links);
+        assert!(
+            dot.contains("\"shop.users\":c1 -> \"shop.cities\":c0 [label=\"link\", style=dotted];")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_to_dot_without_protected_entity() -> Result<()> {
+        let schema = SCHEMA.replace(
+            r#""protected": {"label": "sarus_data", "paths": [{"label": "users"}]},"#,
+            "",
+        );
+        let dataset = Dataset::new(parse_from_str("{}")?, parse_from_str(&schema)?, None);
+        let dot = dataset.to_dot();
+        println!("{}", dot);
+        assert!(!dot.contains("(protected entity)"));
+        assert!(!dot.contains(PRIVACY_UNIT_COLOR));
+        assert!(dot.contains(&format!(
+            "<td bgcolor=\"{}\"><b>shop.users</b></td>",
+            PRIVATE_COLOR
+        )));
+        assert!(dot.contains("\"shop.visits\":c0 -> \"shop.users\":c0 [label=\"FK\"];"));
+        Ok(())
+    }
+}
@@ -0,0 +1,295 @@
+//! Conversion of a schema into a JSON Schema (draft 2020-12) validating its records.
+//!
+//! A Union record is an object with a single property named after its variant,
+//! and temporal bounds are kept in the `formatMinimum` and `formatMaximum` keywords. Temporal
+//! values get the standard `date`, `time` or `date-time` format only when written in RFC 3339,
+//! times and date-times with an offset.
+
+use super::{POSSIBLE_VALUES, SARUS_DATA};
+use crate::protobuf::{schema, type_};
+use serde_json::{json, Map, Value};
+
+/// The JSON Schema dialect of the generated schemas
+pub const JSON_SCHEMA_DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+impl schema::Schema {
+    /// The JSON Schema of the records of the schema,
+    /// with the admin columns if `admin_columns` is true or of the data only otherwise.
+    pub fn to_json_schema(&self, admin_columns: bool) -> Value {
+        let type_ = match self.type_().type_.as_ref() {
+            Some(type_::type_::Type::Struct(s)) if !admin_columns => s
+                .fields
+                .iter()
+                .find(|f| f.name() == SARUS_DATA)
+                .map(|f| f.type_())
+                .unwrap_or(self.type_()),
+            _ => self.type_(),
+        };
+        let mut json_schema = Map::new();
+        json_schema.insert("$schema".to_string(), json!(JSON_SCHEMA_DIALECT));
+        json_schema.insert("title".to_string(), json!(self.name()));
+        if let Value::Object(type_schema) = type_.to_json_schema() {
+            json_schema.extend(type_schema);
+        }
+        Value::Object(json_schema)
+    }
+}
+
+impl type_::Type {
+    /// The JSON Schema of the values of the type
+    pub fn to_json_schema(&self) -> Value {
+        use type_::type_::Type as T;
+        match self.type_.as_ref() {
+            None | Some(T::Null(_)) | Some(T::Unit(_)) => json!({"type": "null"}),
+            Some(T::Boolean(_)) => {
+                let mut json_schema = json!({"type": "boolean"});
+                if let Some(possible_values) = self
+                    .properties
+                    .get(POSSIBLE_VALUES)
+                    .and_then(|values| serde_json::from_str::<Vec<bool>>(values).ok())
+                {
+                    json_schema["enum"] = json!(possible_values);
+                }
+                json_schema
+            }
+            Some(T::Integer(i)) => {
+                let mut json_schema =
+                    json!({"type": "integer", "minimum": i.min, "maximum": i.max});
+                if !i.possible_values.is_empty() {
+                    json_schema["enum"] = json!(i.possible_values);
+                }
+                json_schema
+            }
+            Some(T::Enum(e)) => json!({
+                "type": "string",
+                "enum": e.name_values.iter().map(|nv| nv.name()).collect::<Vec<_>>(),
+            }),
+            Some(T::Float(f)) => {
+                let mut json_schema = json!({"type": "number"});
+                if f.min.is_finite() {
+                    json_schema["minimum"] = json!(f.min);
+                }
+                if f.max.is_finite() {
+                    json_schema["maximum"] = json!(f.max);
+                }
+                if !f.possible_values.is_empty() {
+                    json_schema["enum"] = json!(f.possible_values);
+                }
+                json_schema
+            }
+            Some(T::Text(t)) => {
+                let mut json_schema = json!({"type": "string"});
+                if !t.possible_values.is_empty() {
+                    json_schema["enum"] = json!(t.possible_values);
+                }
+                json_schema
+            }
+            Some(T::Bytes(_)) => json!({"type": "string", "contentEncoding": "base64"}),
+            Some(T::Id(i)) => {
+                use type_::type_::id::Base;
+                match i.base() {
+                    Base::STRING => json!({"type": "string"}),
+                    Base::BYTES => json!({"type": "string", "contentEncoding": "base64"}),
+                    _ => json!({"type": "integer"}),
+                }
+            }
+            Some(T::Date(d)) => temporal_json_schema(
+                (d.format == "%Y-%m-%d").then_some("date"),
+                &d.min,
+                &d.max,
+                &d.possible_values,
+            ),
+            Some(T::Time(t)) => temporal_json_schema(
+                rfc3339_with_offset(&t.format, "%H:%M:%S").then_some("time"),
+                &t.min,
+                &t.max,
+                &t.possible_values,
+            ),
+            Some(T::Datetime(d)) => temporal_json_schema(
+                rfc3339_with_offset(&d.format, "%Y-%m-%dT%H:%M:%S").then_some("date-time"),
+                &d.min,
+                &d.max,
+                &d.possible_values,
+            ),
+            Some(T::Duration(d)) => {
+                let mut json_schema = json!({
+                    "type": "integer",
+                    "minimum": d.min,
+                    "maximum": d.max,
+                    "description": format!("Duration in {}", d.unit),
+                });
+                if !d.possible_values.is_empty() {
+                    json_schema["enum"] = json!(d.possible_values);
+                }
+                json_schema
+            }
+            Some(T::Struct(s)) => {
+                let properties: Map<String, Value> = s
+                    .fields
+                    .iter()
+                    .map(|f| (f.name().to_string(), f.type_().to_json_schema()))
+                    .collect();
+                let required: Vec<&str> = s
+                    .fields
+                    .iter()
+                    .filter(|f| !matches!(f.type_().type_, Some(T::Optional(_))))
+                    .map(|f| f.name())
+                    .collect();
+                json!({
+                    "type": "object",
+                    "properties": properties,
+                    "required": required,
+                    "additionalProperties": false,
+                })
+            }
+            Some(T::Union(u)) => json!({
+                "oneOf": u
+                    .fields
+                    .iter()
+                    .map(|f| json!({
+                        "type": "object",
+                        "properties": {f.name(): f.type_().to_json_schema()},
+                        "required": [f.name()],
+                        "additionalProperties": false,
+                    }))
+                    .collect::<Vec<_>>(),
+            }),
+            Some(T::Optional(o)) => json!({
+                "anyOf": [o.type_().to_json_schema(), {"type": "null"}],
+            }),
+            Some(T::List(l)) => json!({
+                "type": "array",
+                "items": l.type_().to_json_schema(),
+                "maxItems": l.max_size,
+            }),
+            Some(T::Array(a)) => {
+                a.shape
+                    .iter()
+                    .rev()
+                    .fold(a.type_().to_json_schema(), |items, size| {
+                        json!({
+                            "type": "array",
+                            "items": items,
+                            "minItems": size,
+                            "maxItems": size,
+                        })
+                    })
+            }
+            Some(T::Constrained(c)) => c.type_().to_json_schema(),
+            Some(T::Hypothesis(h)) => json!({
+                "anyOf": h
+                    .types
+                    .iter()
+                    .map(|scored| scored.type_().to_json_schema())
+                    .collect::<Vec<_>>(),
+            }),
+        }
+    }
+}
+
+/// Whether values written with the chrono `format` are RFC 3339 times or date-times:
+/// `prefix`, optional fractional seconds, then a `+hh:mm` or `Z` offset
+fn rfc3339_with_offset(format: &str, prefix: &str) -> bool {
+    let Some(rest) = format.strip_prefix(prefix) else {
+        return false;
+    };
+    let offset = ["%.f", "%.3f", "%.6f", "%.9f"]
+        .into_iter()
+        .find_map(|fraction| rest.strip_prefix(fraction))
+        .unwrap_or(rest);
+    offset == "%:z" || offset == "Z"
+}
+
+/// A string with the standard `json_format`, if the values are written in this format
+fn temporal_json_schema(
+    json_format: Option<&str>,
+    min: &str,
+    max: &str,
+    possible_values: &[String],
+) -> Value {
+    let mut json_schema = json!({"type": "string"});
+    if let Some(json_format) = json_format {
+        json_schema["format"] = json!(json_format);
+    }
+    if !min.is_empty() {
+        json_schema["formatMinimum"] = json!(min);
+    }
+    if !max.is_empty() {
+        json_schema["formatMaximum"] = json!(max);
+    }
+    if !possible_values.is_empty() {
+        json_schema["enum"] = json!(possible_values);
+    }
+    json_schema
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protobuf::parse_from_str;
+    use anyhow::Result;
+
+    /// The tables of the test schema, in its `sarus_data` union
+    const TABLES: &str = r#"
+        {"name": "users", "type": {"name": "Struct", "struct": {"fields": [
+            {"name": "id", "type": {"name": "Integer", "integer": {"min": "0", "max": "1000"}}},
+            {"name": "name", "type": {"name": "Text", "text": {"encoding": "UTF-8", "possible_values": ["Alice", "Bob"]}}},
+            {"name": "age", "type": {"name": "Optional", "optional": {"type": {"name": "Float", "float": {"min": 0, "max": 200}}}}},
+            {"name": "tags", "type": {"name": "List", "list": {"type": {"name": "Text", "text": {}}, "max_size": "3"}}}
+        ]}}},
+        {"name": "visits", "type": {"name": "Struct", "struct": {"fields": [
+            {"name": "day", "type": {"name": "Date", "date": {"format": "%Y-%m-%d", "min": "2020-01-01", "max": "2030-01-01"}}},
+            {"name": "at", "type": {"name": "Time", "time": {"format": "%H:%M:%S"}}},
+            {"name": "start", "type": {"name": "Datetime", "datetime": {"format": "%Y-%m-%dT%H:%M:%S%.f%:z"}}},
+            {"name": "end", "type": {"name": "Datetime", "datetime": {"format": "%Y-%m-%dT%H:%M:%S"}}}
+        ]}}}
+    "#;
+
+    /// A Sarus schema with `tables` in its `sarus_data` union, followed by the admin columns
+    fn schema(tables: &str) -> String {
+        format!(
+            r#"{{"name": "a", "type": {{"name": "Struct", "struct": {{"fields": [
+                {{"name": "sarus_data", "type": {{"name": "Union", "union": {{"fields": [{}]}}}}}},
+                {{"name": "sarus_is_public", "type": {{"name": "Boolean", "boolean": {{}}}}}},
+                {{"name": "sarus_privacy_unit", "type": {{"name": "Optional", "optional": {{"type": {{"name": "Id", "id": {{"base": "STRING"}}}}}}}}}},
+                {{"name": "sarus_weights", "type": {{"name": "Float", "float": {{"min": 0, "max": 1}}}}}}
+            ]}}}}}}"#,
+            tables
+        )
+    }
+
+    #[test]
+    fn test_to_json_schema() -> Result<()> {
+        let schema: schema::Schema = parse_from_str(&schema(TABLES))?;
+        let json_schema = schema.to_json_schema(false);
+        println!("{}", serde_json::to_string_pretty(&json_schema)?);
+        assert_eq!(json_schema["$schema"], JSON_SCHEMA_DIALECT);
+        let users = &json_schema["oneOf"][0]["properties"]["users"];
+        assert_eq!(
+            users["properties"]["id"],
+            json!({"type": "integer", "minimum": 0, "maximum": 1000})
+        );
+        assert_eq!(users["properties"]["name"]["enum"], json!(["Alice", "Bob"]));
+        assert_eq!(
+            users["properties"]["age"]["anyOf"][1],
+            json!({"type": "null"})
+        );
+        assert_eq!(users["properties"]["tags"]["maxItems"], 3);
+        assert_eq!(users["required"], json!(["id", "name", "tags"]));
+        let visits = &json_schema["oneOf"][1]["properties"]["visits"]["properties"];
+        assert_eq!(visits["day"]["format"], "date");
+        assert_eq!(visits["day"]["formatMinimum"], "2020-01-01");
+        // Times and date-times have a standard format only with an offset
+        assert_eq!(visits["at"], json!({"type": "string"}));
+        assert_eq!(visits["start"]["format"], "date-time");
+        assert_eq!(visits["end"], json!({"type": "string"}));
+        // Admin columns are properties of the root object
+        let json_schema = schema.to_json_schema(true);
+        assert_eq!(json_schema["type"], "object");
+        assert_eq!(
+            json_schema["properties"]["sarus_privacy_unit"]["anyOf"][0],
+            json!({"type": "string"})
+        );
+        Ok(())
+    }
+}
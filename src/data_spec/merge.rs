@@ -0,0 +1,354 @@
+//! Merge of the statistics of partitions of a dataset.
+//!
+//! Sizes are summed and multiplicities too, as a privacy unit may contribute to each partition.
+//! Distributions are mixed with the sizes of their nodes as weights.
+//! A distribution known on one side only is dropped, as it does not describe the whole data.
+
+use super::{
+    structure::{size_of, variant_name},
+    Error, Result, SIZE_MAX, SIZE_MIN,
+};
+use crate::protobuf::{marginals, size, statistics};
+use protobuf::MessageField;
+
+impl statistics::Statistics {
+    /// Merge the statistics of two partitions of the same data
+    pub fn merge(&self, other: &Self) -> Result<Self> {
+        merge(self, other, &[])
+    }
+}
+
+impl size::Size {
+    /// Merge the sizes of two partitions of the same dataset
+    pub fn merge(&self, other: &Self) -> Result<Self> {
+        check_dataset(&self.dataset, &other.dataset)?;
+        let mut result = self.clone();
+        result.statistics = MessageField::some(self.statistics.merge(&other.statistics)?);
+        Ok(result)
+    }
+}
+
+impl marginals::Marginals {
+    /// Merge the marginals of two partitions of the same dataset
+    pub fn merge(&self, other: &Self) -> Result<Self> {
+        check_dataset(&self.dataset, &other.dataset)?;
+        let mut result = self.clone();
+        result.statistics = MessageField::some(self.statistics.merge(&other.statistics)?);
+        Ok(result)
+    }
+}
+
+fn check_dataset(dataset: &str, other: &str) -> Result<()> {
+    if !dataset.is_empty() && !other.is_empty() && dataset != other {
+        Err(Error::other(format!(
+            "Cannot merge the statistics of datasets {} and {}",
+            dataset, other
+        )))
+    } else {
+        Ok(())
+    }
+}
+
+fn merge(
+    left: &statistics::Statistics,
+    right: &statistics::Statistics,
+    path: &[String],
+) -> Result<statistics::Statistics> {
+    use statistics::statistics::Statistics as S;
+    let mut result = left.clone();
+    let child_path =
+        |name: &str| -> Vec<String> { path.iter().cloned().chain([name.to_string()]).collect() };
+    macro_rules! merge_size {
+        ($s:ident, $r:ident) => {{
+            $s.size += $r.size;
+            $s.multiplicity += $r.multiplicity;
+        }};
+    }
+    macro_rules! merge_distribution {
+        ($s:ident, $r:ident) => {
+            let distribution =
+                merge_distributions(&$s.distribution, &$r.distribution, $s.size, $r.size, path)?;
+            $s.distribution = distribution;
+        };
+    }
+    match (result.statistics.as_mut(), right.statistics.as_ref()) {
+        (Some(S::Null(s)), Some(S::Null(r))) => merge_size!(s, r),
+        (Some(S::Unit(s)), Some(S::Unit(r))) => merge_size!(s, r),
+        (Some(S::Bytes(s)), Some(S::Bytes(r))) => merge_size!(s, r),
+        (Some(S::Id(s)), Some(S::Id(r))) => merge_size!(s, r),
+        (Some(S::Boolean(s)), Some(S::Boolean(r))) => {
+            merge_distribution!(s, r);
+            merge_size!(s, r)
+        }
+        (Some(S::Integer(s)), Some(S::Integer(r))) => {
+            merge_distribution!(s, r);
+            merge_size!(s, r)
+        }
+        (Some(S::Enum(s)), Some(S::Enum(r))) => {
+            merge_distribution!(s, r);
+            merge_size!(s, r)
+        }
+        (Some(S::Float(s)), Some(S::Float(r))) => {
+            merge_distribution!(s, r);
+            merge_size!(s, r)
+        }
+        (Some(S::Text(s)), Some(S::Text(r))) => {
+            merge_distribution!(s, r);
+            if s.example.is_empty() {
+                s.example = r.example.clone();
+            }
+            merge_size!(s, r)
+        }
+        (Some(S::Datetime(s)), Some(S::Datetime(r))) => {
+            merge_distribution!(s, r);
+            merge_size!(s, r)
+        }
+        (Some(S::Date(s)), Some(S::Date(r))) => {
+            merge_distribution!(s, r);
+            merge_size!(s, r)
+        }
+        (Some(S::Time(s)), Some(S::Time(r))) => {
+            merge_distribution!(s, r);
+            merge_size!(s, r)
+        }
+        (Some(S::Duration(s)), Some(S::Duration(r))) => {
+            merge_distribution!(s, r);
+            merge_size!(s, r)
+        }
+        (Some(S::Struct(s)), Some(S::Struct(r))) => {
+            check_names(
+                s.fields.iter().map(|f| f.name()),
+                r.fields.iter().map(|f| f.name()),
+                path,
+            )?;
+            for field in s.fields.iter_mut() {
+                let other = r.fields.iter().find(|f| f.name == field.name).unwrap();
+                field.statistics = MessageField::some(merge(
+                    &field.statistics,
+                    &other.statistics,
+                    &child_path(&field.name),
+                )?);
+            }
+            merge_size!(s, r)
+        }
+        (Some(S::Union(s)), Some(S::Union(r))) => {
+            check_names(
+                s.fields.iter().map(|f| f.name()),
+                r.fields.iter().map(|f| f.name()),
+                path,
+            )?;
+            for field in s.fields.iter_mut() {
+                let other = r.fields.iter().find(|f| f.name == field.name).unwrap();
+                field.statistics = MessageField::some(merge(
+                    &field.statistics,
+                    &other.statistics,
+                    &child_path(&field.name),
+                )?);
+            }
+            merge_size!(s, r)
+        }
+        (Some(S::Optional(s)), Some(S::Optional(r))) => {
+            s.statistics = MessageField::some(merge(&s.statistics, &r.statistics, path)?);
+            merge_size!(s, r)
+        }
+        (Some(S::Constrained(s)), Some(S::Constrained(r))) => {
+            s.statistics = MessageField::some(merge(&s.statistics, &r.statistics, path)?);
+            merge_size!(s, r)
+        }
+        (Some(S::List(s)), Some(S::List(r))) => {
+            merge_distribution!(s, r);
+            s.statistics = MessageField::some(merge(&s.statistics, &r.statistics, path)?);
+            merge_size!(s, r)
+        }
+        (Some(S::Array(s)), Some(S::Array(r))) => {
+            if s.distributions.len() != r.distributions.len() {
+                return Err(mismatch_error(
+                    path,
+                    format!(
+                        "{} and {} distributions",
+                        s.distributions.len(),
+                        r.distributions.len()
+                    ),
+                ));
+            }
+            s.distributions = s
+                .distributions
+                .iter()
+                .zip(r.distributions.iter())
+                .map(|(left, right)| left.merge(right, s.size as f64, r.size as f64))
+                .collect::<Result<_>>()
+                .map_err(|err| mismatch_error(path, err))?;
+            s.statistics = MessageField::some(merge(&s.statistics, &r.statistics, path)?);
+            merge_size!(s, r)
+        }
+        (None, None) => {}
+        _ => {
+            return Err(mismatch_error(
+                path,
+                format!("{} and {}", variant_name(left), variant_name(right)),
+            ))
+        }
+    }
+    merge_size_range(&mut result, left, right);
+    Ok(result)
+}
+
+/// Sum the size ranges if any side has one
+fn merge_size_range(
+    result: &mut statistics::Statistics,
+    left: &statistics::Statistics,
+    right: &statistics::Statistics,
+) {
+    let bound = |statistics: &statistics::Statistics, key: &str| {
+        statistics
+            .properties
+            .get(key)
+            .and_then(|bound| bound.parse::<i64>().ok())
+    };
+    for key in [SIZE_MIN, SIZE_MAX] {
+        if bound(left, key).is_some() || bound(right, key).is_some() {
+            let sum = bound(left, key).unwrap_or(size_of(left))
+                + bound(right, key).unwrap_or(size_of(right));
+            result.properties.insert(key.to_string(), sum.to_string());
+        }
+    }
+}
+
+fn merge_distributions(
+    left: &MessageField<statistics::Distribution>,
+    right: &MessageField<statistics::Distribution>,
+    left_size: i64,
+    right_size: i64,
+    path: &[String],
+) -> Result<MessageField<statistics::Distribution>> {
+    match (left.as_ref(), right.as_ref()) {
+        (Some(left), Some(right)) => Ok(MessageField::some(
+            left.merge(right, left_size as f64, right_size as f64)
+                .map_err(|err| mismatch_error(path, err))?,
+        )),
+        _ => Ok(MessageField::none()),
+    }
+}
+
+fn check_names<'a>(
+    left: impl Iterator<Item = &'a str>,
+    right: impl Iterator<Item = &'a str>,
+    path: &[String],
+) -> Result<()> {
+    let mut left: Vec<&str> = left.collect();
+    let mut right: Vec<&str> = right.collect();
+    left.sort();
+    right.sort();
+    if left != right {
+        Err(mismatch_error(
+            path,
+            format!("fields [{}] and [{}]", left.join(", "), right.join(", ")),
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+fn mismatch_error(path: &[String], description: impl std::fmt::Display) -> Error {
+    Error::other(format!(
+        "Cannot merge statistics at `{}`: {}",
+        path.join("."),
+        description.to_string().trim_end()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protobuf::parse_from_str;
+    use anyhow::Result;
+
+    fn partition(size: i64, values: &[i64]) -> statistics::Statistics {
+        let points = values
+            .iter()
+            .map(|v| {
+                format!(
+                    r#"{{"value": "{}", "probability": {}}}"#,
+                    v,
+                    1. / values.len() as f64
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+        parse_from_str(&format!(
+            r#"{{"name": "Union", "union": {{"size": "{size}", "multiplicity": 1, "fields": [
+                {{"name": "users", "statistics": {{"name": "Struct", "struct": {{"size": "{size}", "multiplicity": 1, "fields": [
+                    {{"name": "age", "statistics": {{"name": "Integer", "integer": {{"size": "{size}", "multiplicity": 1,
+                        "distribution": {{"integer": {{"min": "{min}", "max": "{max}", "points": [{points}]}}}}}}}}}},
+                    {{"name": "nick", "statistics": {{"name": "Optional", "optional": {{"size": "{size}", "statistics":
+                        {{"name": "Text", "text": {{"size": "1", "example": "{size}"}}}}}}}}}}
+                ]}}}}}}
+            ]}}}}"#,
+            size = size,
+            min = values.iter().min().unwrap(),
+            max = values.iter().max().unwrap(),
+            points = points,
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_merge() -> Result<()> {
+        let merged = partition(1, &[10]).merge(&partition(3, &[20, 30]))?;
+        let union = merged.union();
+        assert_eq!((union.size, union.multiplicity), (4, 2.));
+        let users = union.fields[0].statistics.struct_();
+        assert_eq!(users.size, 4);
+        let age = users.fields[0].statistics.integer();
+        assert_eq!(age.size, 4);
+        assert_eq!(
+            age.distribution.numeric_points(),
+            vec![(10., 0.25), (20., 0.375), (30., 0.375)]
+        );
+        assert_eq!(
+            (
+                age.distribution.integer().min,
+                age.distribution.integer().max
+            ),
+            (10, 30)
+        );
+        let nick = users.fields[1].statistics.optional();
+        assert_eq!((nick.size, nick.statistics.text().size), (4, 2));
+        assert_eq!(nick.statistics.text().example, "1");
+        // Mismatching trees cannot be merged
+        let mut other = partition(3, &[20]);
+        other.mut_union().fields[0]
+            .mut_statistics()
+            .mut_struct()
+            .fields
+            .pop();
+        let error = partition(1, &[10]).merge(&other).unwrap_err();
+        assert!(error.to_string().contains("`users`"));
+        let other: statistics::Statistics =
+            parse_from_str(r#"{"name": "Struct", "struct": {"size": "1"}}"#)?;
+        assert!(partition(1, &[10]).merge(&other).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_merge_size() -> Result<()> {
+        let mut left = size::Size::new();
+        left.dataset = "a".to_string();
+        left.statistics = MessageField::some(partition(1, &[10]));
+        left.mut_statistics().mut_union().fields[0]
+            .mut_statistics()
+            .properties
+            .insert(SIZE_MAX.to_string(), "2".to_string());
+        let mut right = left.clone();
+        right.statistics = MessageField::some(partition(3, &[20]));
+        let merged = left.merge(&right)?;
+        assert_eq!(merged.statistics.union().size, 4);
+        assert_eq!(
+            merged.statistics.union().fields[0].statistics.properties[SIZE_MAX],
+            "5"
+        );
+        right.dataset = "b".to_string();
+        assert!(left.merge(&right).is_err());
+        Ok(())
+    }
+}
@@ -4,27 +4,74 @@
 //! https://www.sqlalchemy.org/
 //! https://www.postgresql.org/docs/14/index.html
 
-use crate::protobuf::{
-    dataset, parse_from_str, print_to_string, schema, size, statistics, type_, ParseError,
-};
+use crate::protobuf::{dataset, parse_from_str, path, schema, size, statistics, type_, ParseError};
 use chrono::{self, Duration, NaiveDate, NaiveDateTime, NaiveTime};
+use itertools::Itertools;
+use protobuf::MessageField;
 use qrlew::{
     builder::{Ready, With},
     data_type::{self, DataType, DataTyped},
+    differential_privacy::DpParameters,
     expr::identifier::Identifier,
     hierarchy::Hierarchy,
-    relation::{field, schema::Schema, Constraint, Relation, Variant as _},
+    privacy_unit_tracking::privacy_unit::Step,
+    relation::{field, schema::Schema, Constraint, Relation, Table, Variant as _},
 };
 use std::{
-    collections::HashSet,
-    convert::{TryFrom, TryInto},
+    collections::{BTreeMap, HashSet},
+    convert::{Infallible, TryFrom, TryInto},
     error, fmt, result,
     str::FromStr,
     sync::Arc,
 };
 
+mod arrow;
+mod avro;
+mod ddl;
+mod dictionary;
+mod display;
+mod distribution;
+mod dp_profile;
+mod edit;
+mod graph;
+mod json_schema;
+mod merge;
+mod naming;
+mod parquet_schema;
+mod privacy;
+mod profile;
+mod source;
+mod structure;
+mod validate;
+mod yaml;
+
+pub use arrow::ARROW_TYPE;
+pub use dictionary::DictionaryFormat;
+pub use dp_profile::{DELTA, EPSILON, MAX_CONTRIBUTIONS};
+pub use edit::{Bound, SchemaEdit};
+pub use json_schema::JSON_SCHEMA_DIALECT;
+pub use naming::RelationName;
+pub use profile::{DataFile, MAX_POINTS};
+pub use validate::Mismatch;
+
 pub const CONSTRAINT: &str = "_CONSTRAINT_";
-pub const CONSTRAINT_UNIQUE: &str = "_UNIQUE_"; // We ignore other constraints
+pub const CONSTRAINT_UNIQUE: &str = "_UNIQUE_";
+pub const CONSTRAINT_PRIMARY_KEY: &str = "_PRIMARY_KEY_";
+pub const CONSTRAINT_NOT_NULL: &str = "_NOT_NULL_";
+/// The values of the CONSTRAINT property
+const CONSTRAINTS: [&str; 3] = [
+    CONSTRAINT_UNIQUE,
+    CONSTRAINT_PRIMARY_KEY,
+    CONSTRAINT_NOT_NULL,
+];
+/// Possible values of types without a possible_values field (Boolean), as a JSON list
+const POSSIBLE_VALUES: &str = "_POSSIBLE_VALUES_";
+/// Schema property listing the foreign keys as a JSON list of [pointing, pointed] paths
+pub const FOREIGN_KEYS: &str = "foreign_keys";
+/// Union property listing the public tables as a JSON list of names
+pub const PUBLIC_FIELDS: &str = "public_fields";
+/// Schema property listing the aliases of tables as a JSON list of [alias, table] paths
+pub const ALIASES: &str = "aliases";
 
 // Error management
 
@@ -70,6 +117,36 @@ impl From<chrono::ParseError> for Error {
     }
 }
 
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        Error::other(err)
+    }
+}
+
+impl From<csv::Error> for Error {
+    fn from(err: csv::Error) -> Self {
+        Error::parsing_error(err)
+    }
+}
+
+impl From<parquet::errors::ParquetError> for Error {
+    fn from(err: parquet::errors::ParquetError) -> Self {
+        Error::parsing_error(err)
+    }
+}
+
+impl From<serde_yaml::Error> for Error {
+    fn from(err: serde_yaml::Error) -> Self {
+        Error::parsing_error(err)
+    }
+}
+
+impl From<Infallible> for Error {
+    fn from(err: Infallible) -> Self {
+        match err {}
+    }
+}
+
 impl From<qrlew::expr::Error> for Error {
     fn from(err: qrlew::expr::Error) -> Self {
         Error::other(err)
@@ -82,6 +159,12 @@ pub type Result<T> = result::Result<T, Error>;
 Definition of the dataset
  */
 
+/// Statistics properties widening the size of a table into a range
+pub const SIZE_MIN: &str = "_SIZE_MIN_";
+pub const SIZE_MAX: &str = "_SIZE_MAX_";
+/// Schema property bounding the multiplicity of the privacy units
+const MAX_MAX_MULTIPLICITY: &str = "max_max_multiplicity";
+
 const SARUS_DATA: &str = "sarus_data";
 const PID_COLUMN: &str = "sarus_privacy_unit";
 const WEIGHTS: &str = "sarus_weights";
@@ -207,13 +290,40 @@ impl Dataset {
         }
     }
 
+    /// The columns of each table, followed by the admin columns, with the relation identifiers
+    fn table_columns(&self) -> Vec<(Identifier, Vec<(&str, &type_::Type)>)> {
+        let admin_names_and_types = self.admin_names_and_types();
+        table_structs(self.schema_type_data(), None)
+            .into_iter()
+            .map(|(identifier, schema_struct, _)| {
+                let identifier = self.full_path(&identifier);
+                let columns = schema_struct
+                    .fields
+                    .iter()
+                    .map(|f| (f.name(), f.type_()))
+                    .chain(admin_names_and_types.iter().cloned())
+                    .collect();
+                (identifier, columns)
+            })
+            .collect()
+    }
+
+    /// The relations of the tables, keyed by their path prefixed with the schema name.
+    /// The `sarus_is_public` column of the public tables only holds `true`.
     pub fn relations(&self) -> Hierarchy<Arc<Relation>> {
         let admin_cols_and_types = self.admin_names_and_types();
         let schema_name = self.schema().name();
+        let public_tables = self.public_tables();
         let relations_without_prefix: Hierarchy<Arc<Relation>> =
             table_structs(self.schema_type_data(), self.size_statistics())
                 .into_iter()
-                .map(|(identifier, schema_struct, size_struct)| {
+                .map(|(identifier, schema_struct, size_statistics)| {
+                    let key_constraints = self.key_constraints(&identifier);
+                    let public = public_tables.iter().any(|table| {
+                        table.split_first().is_some_and(|(schema, path)| {
+                            schema == schema_name && path == identifier.as_ref()
+                        })
+                    });
                     let identifier: Identifier = if identifier.len() == 0 {
                         [schema_name].into()
                     } else {
@@ -224,8 +334,10 @@ impl Dataset {
                         Arc::new(relation_from_struct(
                             identifier,
                             schema_struct,
-                            size_struct,
+                            size_statistics,
                             &admin_cols_and_types,
+                            &key_constraints,
+                            public,
                         )),
                     )
                 })
@@ -233,340 +345,233 @@ impl Dataset {
         relations_without_prefix.prepend(&[schema_name.to_string()])
     }
 
-    pub fn with_range(
-        &self,
-        schema_name: Option<&str>,
-        table_name: &str,
-        field_name: &str,
-        min: f64,
-        max: f64,
-    ) -> Result<Self> {
-        let change_type = ChangeType::Range(Range { min, max });
-
-        let type_ident = if let Some(s) = schema_name {
-            Identifier::from(vec![
-                s.to_string(),
-                table_name.to_string(),
-                field_name.to_string(),
-            ])
-        } else {
-            Identifier::from(vec![table_name.to_string(), field_name.to_string()])
+    /// The constraints of the columns of the table at `table_path` (relative to the data
+    /// part of the schema) listed in the foreign keys of the schema properties.
+    /// Primary keys are column constraints.
+    fn key_constraints(&self, table_path: &[String]) -> BTreeMap<String, Constraint> {
+        let foreign_keys: Vec<(Vec<String>, Vec<String>)> =
+            structure::parse_property(self.schema.properties(), FOREIGN_KEYS).unwrap_or_default();
+        foreign_keys
+            .iter()
+            .filter_map(|(pointing, _)| {
+                let (column, table) = pointing.split_last()?;
+                (table == table_path).then(|| (column.clone(), Constraint::ForeignKey))
+            })
+            .collect()
+    }
+
+    /// The foreign keys of the schema, as links between the relations of `relations`
+    pub fn foreign_keys(&self) -> Vec<ForeignKey> {
+        let foreign_keys: Vec<(Vec<String>, Vec<String>)> =
+            structure::parse_property(self.schema.properties(), FOREIGN_KEYS).unwrap_or_default();
+        let relation_and_column = |path: &[String]| {
+            let (column, table) = path.split_last()?;
+            Some((self.full_path(table), column.clone()))
         };
+        foreign_keys
+            .iter()
+            .filter_map(|(pointing, pointed)| {
+                let (relation, column) = relation_and_column(pointing)?;
+                let (referred_relation, referred_column) = relation_and_column(pointed)?;
+                Some(ForeignKey {
+                    relation,
+                    column,
+                    referred_relation,
+                    referred_column,
+                })
+            })
+            .collect()
+    }
 
-        let new_schema = self
-            .schema
-            .try_with_change_type_and_identifier(&change_type, &type_ident)?;
-        Ok(Dataset::new(
-            self.dataset.clone(),
-            new_schema,
-            self.size.clone(),
-        ))
+    /// Set the range of the Integer, Float, Date, Time, Datetime or Duration column at `path`.
+    /// The path is relative to the schema type and may be an `Identifier`
+    /// or a `path::Path`. Temporal bounds are parsed with the format of the column,
+    /// Duration bounds are expressed in its unit.
+    pub fn with_range<P, B>(&self, path: P, min: B, max: B) -> Result<Self>
+    where
+        P: TryInto<Identifier>,
+        Error: From<P::Error>,
+        B: Into<Bound>,
+    {
+        self.apply_edits(&[SchemaEdit::SetRange {
+            path: path.try_into()?.to_vec(),
+            min: min.into(),
+            max: max.into(),
+        }])
     }
 
-    pub fn with_possible_values(
-        &self,
-        schema_name: Option<&str>,
-        table_name: &str,
-        field_name: &str,
-        possible_values: &[String],
-    ) -> Result<Self> {
-        let change_type = ChangeType::PossibleValues(possible_values.to_vec());
-
-        let type_ident = if let Some(s) = schema_name {
-            Identifier::from(vec![
-                s.to_string(),
-                table_name.to_string(),
-                field_name.to_string(),
-            ])
-        } else {
-            Identifier::from(vec![table_name.to_string(), field_name.to_string()])
-        };
+    /// Set the possible values of the column at `path`.
+    pub fn with_possible_values<P>(&self, path: P, possible_values: &[String]) -> Result<Self>
+    where
+        P: TryInto<Identifier>,
+        Error: From<P::Error>,
+    {
+        self.apply_edits(&[SchemaEdit::SetPossibleValues {
+            path: path.try_into()?.to_vec(),
+            possible_values: possible_values.to_vec(),
+        }])
+    }
 
-        let new_schema = self
-            .schema
-            .try_with_change_type_and_identifier(&change_type, &type_ident)?;
-        Ok(Dataset::new(
-            self.dataset.clone(),
-            new_schema,
-            self.size.clone(),
-        ))
+    /// Set or clear (if `constraint` is `None`) the constraint of the column at `path`.
+    /// The constraint is one of `CONSTRAINT_UNIQUE`, `CONSTRAINT_PRIMARY_KEY`
+    /// or `CONSTRAINT_NOT_NULL`.
+    pub fn with_constraint<P>(&self, path: P, constraint: Option<&str>) -> Result<Self>
+    where
+        P: TryInto<Identifier>,
+        Error: From<P::Error>,
+    {
+        self.apply_edits(&[SchemaEdit::SetConstraint {
+            path: path.try_into()?.to_vec(),
+            constraint: constraint.map(|c| c.to_string()),
+        }])
     }
 
-    pub fn with_constraint(
-        &self,
-        schema_name: Option<&str>,
-        table_name: &str,
-        field_name: &str,
-        constraint: Option<&str>,
-    ) -> Result<Self> {
-        let change_type = ChangeType::Constrained(constraint.and_then(|f| Some(f.to_string())));
-        let type_ident = if let Some(s) = schema_name {
-            Identifier::from(vec![
-                s.to_string(),
-                table_name.to_string(),
-                field_name.to_string(),
-            ])
-        } else {
-            Identifier::from(vec![table_name.to_string(), field_name.to_string()])
-        };
-        let new_schema = self
-            .schema
-            .try_with_change_type_and_identifier(&change_type, &type_ident)?;
+    /// Set the range of the size of the table at `path`.
+    /// The table must have Size statistics, its size is kept in the range.
+    pub fn with_size_range<P>(&self, path: P, min: i64, max: i64) -> Result<Self>
+    where
+        P: TryInto<Identifier>,
+        Error: From<P::Error>,
+    {
+        let path = self.data_path(path.try_into()?);
+        if min > max {
+            return Err(Error::other(format!(
+                "Invalid size range [{}, {}] for `{}`",
+                min,
+                max,
+                path.join(".")
+            )));
+        }
+        let mut size = self.size.clone();
+        let statistics = size
+            .as_mut()
+            .and_then(|size| structure::statistics_at_mut(size.mut_statistics(), &path))
+            .filter(|statistics| statistics.has_struct())
+            .ok_or_else(|| Error::other(format!("No table statistics at `{}`", path.join("."))))?;
+        let table = statistics.mut_struct();
+        table.set_size(table.size().clamp(min, max));
+        let properties = statistics.mut_properties();
+        properties.insert(SIZE_MIN.to_string(), min.to_string());
+        properties.insert(SIZE_MAX.to_string(), max.to_string());
         Ok(Dataset::new(
             self.dataset.clone(),
-            new_schema,
-            self.size.clone(),
+            self.schema.clone(),
+            size,
         ))
     }
-}
 
-/// Enum to hold the type of change we want to apply
-enum ChangeType {
-    Constrained(Option<String>),
-    PossibleValues(Vec<String>),
-    Range(Range),
-}
+    /// The maximum multiplicity of the privacy units across tables and columns,
+    /// from the Size statistics or the `max_max_multiplicity` schema property.
+    /// None if no multiplicity was measured.
+    pub fn max_multiplicity(&self) -> Option<f64> {
+        let from_statistics = self.size_statistics().and_then(|statistics| {
+            table_structs(self.schema_type_data(), Some(statistics))
+                .into_iter()
+                .filter_map(|(_, _, statistics)| statistics.and_then(table_multiplicity))
+                .reduce(f64::max)
+        });
+        from_statistics.or_else(|| {
+            self.schema
+                .properties()
+                .get(MAX_MAX_MULTIPLICITY)
+                .and_then(|multiplicity| multiplicity.parse().ok())
+        })
+    }
 
-impl ChangeType {
-    /// It applies the constraint to the type_::Type properties if
-    /// the variant is Constrained. It returns the type_::Type otherwise.
-    fn apply_constraint(&self, type_: &type_::Type) -> type_::Type {
-        let mut type_ = type_.clone();
-        match self {
-            ChangeType::Constrained(Some(c)) => {
-                type_.set_properties([(CONSTRAINT.to_string(), c.to_string())].into());
-                type_
+    /// Bound the privacy unit multiplicity of DP parameters by the maximum multiplicity
+    pub fn bounded_dp_parameters(&self, parameters: DpParameters) -> DpParameters {
+        match self.max_multiplicity() {
+            Some(multiplicity) => {
+                let bound = parameters.privacy_unit_max_multiplicity.min(multiplicity);
+                parameters.with_privacy_unit_max_multiplicity(bound)
             }
-            ChangeType::Constrained(None) => {
-                type_.mut_properties().remove(CONSTRAINT);
-                type_
-            }
-            _ => type_,
+            None => parameters,
         }
     }
 
-    /// It applies possible values to text if the variant is PossibleValues.
-    /// It returns the type_::type_::Text otherwise.
-    fn apply_possible_values(&self, type_: &type_::type_::Text) -> type_::type_::Text {
-        let mut type_ = type_.clone();
-        match self {
-            ChangeType::PossibleValues(p) => {
-                type_.set_possible_values(p.iter().cloned().collect());
-                type_
-            }
-            _ => type_,
-        }
+    /// Make the column at `path` nullable or not, by wrapping its type in an Optional
+    /// or removing the wrapper. The Size statistics follow, a column cannot be made
+    /// non-nullable if its statistics count nulls.
+    pub fn with_nullable<P>(&self, path: P, nullable: bool) -> Result<Self>
+    where
+        P: TryInto<Identifier>,
+        Error: From<P::Error>,
+    {
+        let path = path.try_into()?.to_vec();
+        self.apply_edits(&[if nullable {
+            SchemaEdit::MakeOptional { path }
+        } else {
+            SchemaEdit::MakeRequired { path }
+        }])
     }
 
-    /// It applies range to int if the variant is PossibleValues.
-    /// It returns the type_::type_::Integer otherwise.
-    fn apply_range_to_int(&self, type_: &type_::type_::Integer) -> type_::type_::Integer {
-        let mut type_ = type_.clone();
-        match self {
-            ChangeType::Range(r) => {
-                type_.set_min(r.min.round() as i64);
-                type_.set_max(r.max.round() as i64);
-                type_
-            }
-            _ => type_,
-        }
+    /// Make the column at `path` a foreign key referring to the column at `references`.
+    /// Both paths are relative to the schema type.
+    pub fn with_foreign_key<P, R>(&self, path: P, references: R) -> Result<Self>
+    where
+        P: TryInto<Identifier>,
+        R: TryInto<Identifier>,
+        Error: From<P::Error> + From<R::Error>,
+    {
+        self.apply_edits(&[SchemaEdit::SetForeignKey {
+            path: path.try_into()?.to_vec(),
+            references: Some(references.try_into()?.to_vec()),
+        }])
     }
 
-    /// It applies range to float if the variant is PossibleValues.
-    /// It returns the type_::type_::Float otherwise.
-    fn apply_range_to_float(&self, type_: &type_::type_::Float) -> type_::type_::Float {
-        let mut type_ = type_.clone();
-        match self {
-            ChangeType::Range(r) => {
-                type_.set_min(r.min);
-                type_.set_max(r.max);
-                type_
-            }
-            _ => type_,
-        }
+    /// Remove the foreign key of the column at `path`
+    pub fn without_foreign_key<P>(&self, path: P) -> Result<Self>
+    where
+        P: TryInto<Identifier>,
+        Error: From<P::Error>,
+    {
+        self.apply_edits(&[SchemaEdit::SetForeignKey {
+            path: path.try_into()?.to_vec(),
+            references: None,
+        }])
     }
 }
 
-struct Range {
-    min: f64,
-    max: f64,
+/// A foreign key: a link from a column of a relation to a column of another relation
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ForeignKey {
+    pub relation: Identifier,
+    pub column: String,
+    pub referred_relation: Identifier,
+    pub referred_column: String,
 }
 
-impl schema::Schema {
-    /// Generate a new schema by applying the change to the type
-    /// identified by the identifier. It raises an error if the
-    /// the schema type is not compatible with the identifier.
-    fn try_with_change_type_and_identifier(
-        &self,
-        change_type: &ChangeType,
-        identifier: &Identifier,
-    ) -> Result<schema::Schema> {
-        let mut new_schema = self.clone();
-        let new_types = self.type_().try_with_change_type_and_identifier(
-            change_type,
-            identifier,
-            &Identifier::empty(),
-        )?;
-        new_schema.set_type(new_types);
-        Ok(new_schema)
+/// A privacy unit tracking step following the foreign key.
+/// The referred relation is designated by its full path, dotted as in `Dataset::privacy_unit`.
+impl<'a> From<&'a ForeignKey> for Step {
+    fn from(value: &'a ForeignKey) -> Self {
+        Step::new(
+            value.column.clone(),
+            value.referred_relation.to_vec().join("."),
+            value.referred_column.clone(),
+        )
     }
 }
 
-impl type_::Type {
-    /// Generate a new type_::Type by applying a change to che child
-    /// identified by the identifier. It passes over children recursively.
-    fn try_with_change_type_and_identifier(
-        &self,
-        change_type: &ChangeType,
-        identifier: &Identifier,
-        visited_path: &Identifier,
-    ) -> Result<type_::Type> {
-        let mut new_type = if identifier == visited_path {
-            change_type.apply_constraint(self)
-        } else {
-            self.clone()
-        };
-
-        match self.type_.as_ref() {
-            Some(type_::type_::Type::Union(u)) => {
-                let fields = u.fields();
-
-                // fail if no children is compatible with the identifier.
-                let _ = fields
-                    .iter()
-                    .find(|f| {
-                        let field_path: Identifier =
-                            visited_path.clone().with(f.name().to_string());
-                        is_prefix_of(&field_path, identifier)
-                    })
-                    .ok_or_else(|| {
-                        Error::Other(format!(
-                            "Path {}, is not compatible with schema type",
-                            identifier
-                        ))
-                    })?;
+/// Builds an Identifier out of a protobuf Path.
+/// The path must be linear: each node has at most one child.
+impl<'a> TryFrom<&'a path::Path> for Identifier {
+    type Error = Error;
 
-                let fields = fields
-                    .iter()
-                    .map(|f| {
-                        let field_path = visited_path.clone().with(f.name().to_string());
-                        let new_field_type = if is_prefix_of(&field_path, identifier) {
-                            f.type_().try_with_change_type_and_identifier(
-                                change_type,
-                                identifier,
-                                &field_path,
-                            )?
-                        } else {
-                            f.type_().clone()
-                        };
-                        let mut new_field = f.clone();
-                        new_field.set_type(new_field_type);
-                        Ok(new_field)
-                    })
-                    .collect::<Result<Vec<_>>>()?;
-                let mut new_union = u.clone();
-                new_union.set_fields(fields);
-                let mut new_type = self.clone();
-                new_type.set_union(new_union);
-                Ok(new_type)
+    fn try_from(value: &'a path::Path) -> Result<Self> {
+        let mut labels = vec![value.label().to_string()];
+        let mut node = value;
+        while let Some(child) = node.paths.first() {
+            if node.paths.len() > 1 {
+                return Err(Error::other(format!(
+                    "Path `{}` has several branches and cannot be used as an identifier",
+                    labels.join(".")
+                )));
             }
-            Some(type_::type_::Type::Struct(s)) => {
-                let fields = s.fields();
-                let mut new_struct = s.clone();
-                let has_sarus_data = fields.iter().find(|f| f.name() == SARUS_DATA).is_some();
-
-                // fail if no children is compatible with the identifier. skip the check if struct has sarus_data
-                let _ = fields
-                    .iter()
-                    .find(|f| {
-                        let field_path: Identifier =
-                            visited_path.clone().with(f.name().to_string());
-                        is_prefix_of(&field_path, identifier) || has_sarus_data
-                    })
-                    .ok_or_else(|| {
-                        Error::Other(format!(
-                            "Path {}, is not compatible with schema type",
-                            identifier
-                        ))
-                    })?;
-                // recreate fields. call the function recursively for the child
-                // in the identifier
-                let new_fields = fields
-                    .iter()
-                    .map(|f| {
-                        let field_path: Identifier =
-                            visited_path.clone().with(f.name().to_string());
-                        let new_field_type = if f.name() == SARUS_DATA {
-                            f.type_().try_with_change_type_and_identifier(
-                                change_type,
-                                identifier,
-                                &visited_path,
-                            )?
-                        } else if is_prefix_of(&field_path, identifier) {
-                            f.type_().try_with_change_type_and_identifier(
-                                change_type,
-                                identifier,
-                                &field_path,
-                            )?
-                        } else {
-                            f.type_().clone()
-                        };
-                        let mut new_field = f.clone();
-                        new_field.set_type(new_field_type);
-                        Ok(new_field)
-                    })
-                    .collect::<Result<Vec<_>>>()?;
-                new_struct.set_fields(new_fields);
-
-                new_type.set_struct(new_struct);
-                Ok(new_type)
-            }
-            Some(type_::type_::Type::Text(t)) => {
-                let new_t = change_type.apply_possible_values(t);
-                new_type.set_text(new_t);
-                Ok(new_type)
-            }
-            Some(type_::type_::Type::Integer(int)) => {
-                let new_int = change_type.apply_range_to_int(int);
-                new_type.set_integer(new_int);
-                Ok(new_type)
-            }
-            Some(type_::type_::Type::Float(f)) => {
-                let new_f = change_type.apply_range_to_float(f);
-                new_type.set_float(new_f);
-                Ok(new_type)
-            }
-            Some(type_::type_::Type::Optional(o)) => {
-                let mut new_o = o.clone();
-                let new_opt_type = o.type_().try_with_change_type_and_identifier(
-                    change_type,
-                    identifier,
-                    visited_path,
-                )?;
-                new_o.set_type(new_opt_type);
-                new_type.set_optional(new_o);
-                Ok(new_type)
-            }
-            _ => Err(Error::Other(format!(
-                "Should never get here. Got identifier: {} and visited_path: {}, Type: \n{}",
-                identifier, visited_path, self
-            ))),
+            labels.push(child.label().to_string());
+            node = child;
         }
-    }
-}
-
-/// Display a dataset
-impl fmt::Display for Dataset {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(
-            f,
-            "Dataset: {}\nSchema: {}\nSize: {}",
-            print_to_string(&self.dataset).unwrap(),
-            print_to_string(&self.schema).unwrap(),
-            self.size
-                .as_ref()
-                .map_or(String::new(), |s| print_to_string(s).unwrap())
-        )
+        Ok(labels.into())
     }
 }
 
@@ -675,6 +680,64 @@ impl<'a> TryFrom<&'a Hierarchy<Arc<Relation>>> for schema::Schema {
 
 // Utility functions.
 
+/// Error raised when a path segment matches none of the `names` of the
+/// fields at `visited_path`. The closest names are suggested.
+fn unresolved_path_error<'a>(
+    segment: &str,
+    visited_path: &Identifier,
+    names: impl Iterator<Item = &'a str>,
+) -> Error {
+    let names: Vec<&str> = names.collect();
+    let location = if visited_path.is_empty() {
+        "the schema root".to_string()
+    } else {
+        format!("`{}`", visited_path)
+    };
+    let threshold = (segment.chars().count() / 3).max(1);
+    let suggestions: Vec<String> = names
+        .iter()
+        .map(|name| {
+            (
+                edit_distance(&segment.to_lowercase(), &name.to_lowercase()),
+                name,
+            )
+        })
+        .filter(|(distance, _)| *distance <= threshold)
+        .sorted()
+        .map(|(_, name)| format!("`{}`", name))
+        .collect();
+    if suggestions.is_empty() {
+        Error::other(format!(
+            "Cannot find `{}` in {}. Available fields are: {}",
+            segment,
+            location,
+            names.join(", ")
+        ))
+    } else {
+        Error::other(format!(
+            "Cannot find `{}` in {}. Did you mean {}?",
+            segment,
+            location,
+            suggestions.join(" or ")
+        ))
+    }
+}
+
+/// Levenshtein distance between two strings
+fn edit_distance(left: &str, right: &str) -> usize {
+    let right: Vec<char> = right.chars().collect();
+    let mut previous: Vec<usize> = (0..=right.len()).collect();
+    for (i, l) in left.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, r) in right.iter().enumerate() {
+            let substitution = previous[j] + usize::from(l != *r);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[right.len()]
+}
+
 fn is_prefix_of(left: &[String], right: &[String]) -> bool {
     left.iter().zip(right.iter()).all(|(pr, pa)| pr == pa)
 }
@@ -716,9 +779,14 @@ fn type_from_relations(
                     let mut proto_field = type_::type_::struct_::Field::new();
                     let mut proto_field_type: type_::Type = (&field.data_type()).try_into()?;
                     proto_field.set_name(field.name().to_string());
-                    if let Some(Constraint::Unique) = field.constraint() {
+                    let constraint = match field.constraint() {
+                        Some(Constraint::Unique) => Some(CONSTRAINT_UNIQUE),
+                        Some(Constraint::PrimaryKey) => Some(CONSTRAINT_PRIMARY_KEY),
+                        _ => None,
+                    };
+                    if let Some(constraint) = constraint {
                         proto_field_type.set_properties(
-                            [(CONSTRAINT.to_string(), CONSTRAINT_UNIQUE.to_string())].into(),
+                            [(CONSTRAINT.to_string(), constraint.to_string())].into(),
                         );
                     }
                     proto_field.set_type(proto_field_type);
@@ -772,7 +840,9 @@ fn weight_type_from_relations(relations: &Hierarchy<Arc<Relation>>) -> Result<ty
     Ok(proto_type)
 }
 
-/// Create a Statistics protobuf from relations
+/// Create a Statistics protobuf from relations.
+/// Each table gets a Struct with a node per column, Unions carry the sum of the sizes.
+/// Tables with a unique privacy unit column have a multiplicity of 1.
 fn statistics_from_relations(
     relations: &Hierarchy<Arc<Relation>>,
     prefix: &Vec<String>,
@@ -781,10 +851,36 @@ fn statistics_from_relations(
     let common_paths: HashSet<Vec<String>> = extract_paths_with_prefix(relations, prefix);
     if common_paths.is_empty() {
         if let Some(rel) = relations.get(prefix) {
-            let mut struct_proto = statistics::statistics::Struct::new();
             if let Some(rel_size) = rel.size().max() {
+                let mut struct_proto = statistics::statistics::Struct::new();
+                struct_proto.set_name("Struct".to_string());
                 struct_proto.set_size(*rel_size);
-                stat_proto.set_struct(struct_proto)
+                // A unique privacy unit column bounds the multiplicity
+                if rel
+                    .schema()
+                    .field(PID_COLUMN)
+                    .is_ok_and(|field| field.constraint() == Some(Constraint::Unique))
+                {
+                    struct_proto.set_multiplicity(1.);
+                }
+                for field in rel.schema().fields() {
+                    if ![PID_COLUMN, WEIGHTS, PUBLIC].contains(&field.name()) {
+                        let mut proto_field = statistics::statistics::struct_::Field::new();
+                        proto_field.set_name(field.name().to_string());
+                        proto_field.set_statistics(statistics_from_data_type(
+                            &field.data_type(),
+                            *rel_size,
+                        ));
+                        struct_proto.fields.push(proto_field);
+                    }
+                }
+                stat_proto.set_name("Struct".to_string());
+                stat_proto.set_struct(struct_proto);
+                if let Some(rel_min_size) = rel.size().min().filter(|min| *min < rel_size) {
+                    let properties = stat_proto.mut_properties();
+                    properties.insert(SIZE_MIN.to_string(), rel_min_size.to_string());
+                    properties.insert(SIZE_MAX.to_string(), rel_size.to_string());
+                }
             };
         } else {
             return None;
@@ -792,9 +888,10 @@ fn statistics_from_relations(
     } else {
         let mut union_proto = statistics::statistics::Union::new();
         let mut proto_fields: Vec<statistics::statistics::union::Field> = vec![];
-        for path in common_paths.iter() {
+        for path in common_paths.iter().sorted() {
             if let Some(field_statistics) = statistics_from_relations(relations, path) {
                 let field_name = &path[path.len() - 1];
+                union_proto.size += structure::size_of(&field_statistics);
                 let mut data_field = statistics::statistics::union::Field::new();
                 data_field.set_name(field_name.clone());
                 data_field.set_statistics(field_statistics);
@@ -802,12 +899,81 @@ fn statistics_from_relations(
             }
         }
         union_proto.set_fields(proto_fields);
+        union_proto.set_name("Union".to_string());
         stat_proto.set_name("Union".to_string());
         stat_proto.set_union(union_proto);
     }
     Some(stat_proto)
 }
 
+/// Create the Statistics protobuf of a column of a table of size `size`.
+/// Integer and Float distributions carry the bounds of the DataType.
+/// Multiplicities and the number of nulls are not known from a DataType and are left unset.
+fn statistics_from_data_type(data_type: &DataType, size: i64) -> statistics::Statistics {
+    use statistics::statistics::Statistics as S;
+    let mut stat_proto = statistics::Statistics::new();
+    macro_rules! leaf {
+        ($variant:ident, $name:expr) => {{
+            stat_proto.set_name($name.to_string());
+            Some(S::$variant(statistics::statistics::$variant {
+                size,
+                ..Default::default()
+            }))
+        }};
+    }
+    stat_proto.statistics = match data_type {
+        DataType::Null => leaf!(Null, "Null"),
+        DataType::Unit(_) => leaf!(Unit, "Unit"),
+        DataType::Boolean(_) => leaf!(Boolean, "Boolean"),
+        DataType::Integer(i) => {
+            let mut distribution = statistics::distribution::Integer::new();
+            distribution.min = i.min().cloned().unwrap_or(i64::MIN);
+            distribution.max = i.max().cloned().unwrap_or(i64::MAX);
+            let mut distribution_proto = statistics::Distribution::new();
+            distribution_proto.set_integer(distribution);
+            stat_proto.set_name("Integer".to_string());
+            Some(S::Integer(statistics::statistics::Integer {
+                distribution: MessageField::some(distribution_proto),
+                size,
+                ..Default::default()
+            }))
+        }
+        DataType::Enum(_) => leaf!(Enum, "Enum"),
+        DataType::Float(f) => {
+            let mut distribution = statistics::distribution::Double::new();
+            distribution.min = f.min().cloned().unwrap_or(f64::MIN);
+            distribution.max = f.max().cloned().unwrap_or(f64::MAX);
+            let mut distribution_proto = statistics::Distribution::new();
+            distribution_proto.set_double(distribution);
+            stat_proto.set_name("Float".to_string());
+            Some(S::Float(statistics::statistics::Float {
+                distribution: MessageField::some(distribution_proto),
+                size,
+                ..Default::default()
+            }))
+        }
+        DataType::Text(_) => leaf!(Text, "Text"),
+        DataType::Bytes(_) => leaf!(Bytes, "Bytes"),
+        DataType::Date(_) => leaf!(Date, "Date"),
+        DataType::Time(_) => leaf!(Time, "Time"),
+        DataType::DateTime(_) => leaf!(Datetime, "Datetime"),
+        DataType::Duration(_) => leaf!(Duration, "Duration"),
+        DataType::Id(_) => leaf!(Id, "Id"),
+        DataType::Optional(o) => {
+            stat_proto.set_name("Optional".to_string());
+            Some(S::Optional(statistics::statistics::Optional {
+                // An inner size of 0 leaves the number of nulls unknown
+                statistics: MessageField::some(statistics_from_data_type(o.data_type(), 0)),
+                size,
+                name: "Optional".to_string(),
+                ..Default::default()
+            }))
+        }
+        _ => None,
+    };
+    stat_proto
+}
+
 /*
 A few utilities to visit types and statistics
  */
@@ -817,15 +983,17 @@ fn table_structs<'a>(
 ) -> Vec<(
     Identifier,
     &'a type_::type_::Struct,
-    Option<&'a statistics::statistics::Struct>,
+    Option<&'a statistics::Statistics>,
 )> {
     if let Some(t) = t.type_.as_ref() {
         match t {
             type_::type_::Type::Struct(t) => {
                 // If the type is a Struct
-                let s = s.and_then(|s| s.statistics.as_ref()).and_then(|s| match s {
-                    statistics::statistics::Statistics::Struct(s) => Some(s),
-                    _ => None,
+                let s = s.filter(|s| {
+                    matches!(
+                        s.statistics,
+                        Some(statistics::statistics::Statistics::Struct(_))
+                    )
                 });
                 vec![(Identifier::empty(), t, s)]
             }
@@ -866,7 +1034,11 @@ impl<'a> From<&'a type_::Type> for DataType {
         value.type_.as_ref().map_or(DataType::Any, |t| match t {
             type_::type_::Type::Null(type_::type_::Null { .. }) => DataType::Null,
             type_::type_::Type::Unit(type_::type_::Unit { .. }) => DataType::unit(),
-            type_::type_::Type::Boolean(type_::type_::Boolean { .. }) => DataType::boolean(),
+            type_::type_::Type::Boolean(type_::type_::Boolean { .. }) => value
+                .properties()
+                .get(POSSIBLE_VALUES)
+                .and_then(|values| serde_json::from_str::<Vec<bool>>(values).ok())
+                .map_or_else(DataType::boolean, DataType::boolean_values),
             type_::type_::Type::Integer(type_::type_::Integer {
                 min,
                 max,
@@ -1044,7 +1216,7 @@ impl<'a> From<&'a type_::Type> for DataType {
                 unique,
                 reference: _,
                 ..
-            }) => DataType::Id(data_type::Id::new(None, *unique)),
+            }) => DataType::Id(data_type::Id::new(None, *unique, BTreeMap::new())),
             _ => DataType::Any,
         })
     }
@@ -1355,25 +1527,23 @@ impl<'a> From<&'a type_::type_::Struct> for Schema {
                 (
                     f.name(),
                     DataType::from(f.type_()),
-                    unique_constraint_from_field_type(f.type_()),
+                    constraint_from_field_type(f.type_()),
                 )
             })
             .collect()
     }
 }
 
-/// It returns a unique constraint if it is present in the property type
-/// or if the type is an unique Id.
-fn unique_constraint_from_field_type<'a>(type_: &'a type_::Type) -> Option<Constraint> {
+/// It returns a unique or primary key constraint if it is present in the property type
+/// or a unique constraint if the type is an unique Id.
+fn constraint_from_field_type(type_: &type_::Type) -> Option<Constraint> {
     let field_constraint = type_
         .properties()
         .get(CONSTRAINT)
-        .and_then(|constraint| {
-            if constraint == CONSTRAINT_UNIQUE {
-                Some(Constraint::Unique)
-            } else {
-                None
-            }
+        .and_then(|constraint| match constraint.as_str() {
+            CONSTRAINT_UNIQUE => Some(Constraint::Unique),
+            CONSTRAINT_PRIMARY_KEY => Some(Constraint::PrimaryKey),
+            _ => None,
         })
         .or_else(|| {
             let dtype = DataType::from(type_);
@@ -1392,16 +1562,42 @@ fn unique_constraint_from_field_type<'a>(type_: &'a type_::Type) -> Option<Const
 fn relation_from_struct<'a>(
     identifier: Identifier,
     schema_struct: &'a type_::type_::Struct,
-    size_struct: Option<&'a statistics::statistics::Struct>,
+    size_statistics: Option<&'a statistics::Statistics>,
     admin_fields: &Vec<(&str, &'a type_::Type)>,
+    key_constraints: &BTreeMap<String, Constraint>,
+    public: bool,
 ) -> Relation {
     let data_schema: Schema = schema_struct.try_into().unwrap();
-    let data_fields = data_schema.to_vec();
+    let data_fields: Vec<field::Field> = data_schema
+        .iter()
+        .map(|f| match (f.constraint(), key_constraints.get(f.name())) {
+            (None, Some(constraint)) => {
+                field::Field::from((f.name(), f.data_type(), Some(*constraint)))
+            }
+            _ => f.clone(),
+        })
+        .collect();
+    // A privacy unit contributing at most one row appears once in the table,
+    // unless the rows were only bounded by a differentially private profiling
+    let single_contribution = size_statistics
+        .filter(|statistics| !statistics.properties.contains_key(MAX_CONTRIBUTIONS))
+        .and_then(table_multiplicity)
+        .is_some_and(|multiplicity| multiplicity <= 1.);
     let admin_fields = admin_fields
         .iter()
         .map(|(field_name, field_type)| {
-            let dtype = DataType::from(*field_type);
-            let field_constraint = unique_constraint_from_field_type(field_type);
+            let dtype = match DataType::from(*field_type) {
+                DataType::Boolean(_) if public && *field_name == PUBLIC => {
+                    DataType::boolean_values([true])
+                }
+                dtype => dtype,
+            };
+            let field_constraint = match constraint_from_field_type(field_type) {
+                None if single_contribution && *field_name == PID_COLUMN => {
+                    Some(Constraint::Unique)
+                }
+                constraint => constraint,
+            };
             field::Field::from((*field_name, dtype, field_constraint))
         })
         .collect();
@@ -1410,15 +1606,57 @@ fn relation_from_struct<'a>(
     let mut builder = Relation::table().schema(schema);
     // Create a table builder with a name
     builder = builder.path(identifier);
-    if let Some(s) = size_struct {
-        builder = builder.size(s.size())
+    match size_statistics.map(size_range) {
+        Some((min, max)) if min < max => {
+            let table: Table = builder.build();
+            Table::new(
+                table.name().to_string(),
+                table.path().clone(),
+                table.schema().clone(),
+                data_type::Integer::from_interval(min, max),
+            )
+            .into()
+        }
+        Some((size, _)) => builder.size(size).build(),
+        None => builder.build(),
     }
-    builder.build()
+}
+
+/// The maximum multiplicity of a table and its columns, None if none was measured
+fn table_multiplicity(statistics: &statistics::Statistics) -> Option<f64> {
+    let table = statistics.struct_();
+    [table.multiplicity()]
+        .into_iter()
+        .chain(
+            table
+                .fields
+                .iter()
+                .map(|f| structure::multiplicity_of(f.statistics())),
+        )
+        .filter(|multiplicity| *multiplicity > 0.)
+        .reduce(f64::max)
+}
+
+/// The range of the size of a table: the size of its Struct statistics,
+/// widened by the SIZE_MIN and SIZE_MAX properties if any.
+fn size_range(statistics: &statistics::Statistics) -> (i64, i64) {
+    let size = statistics.struct_().size();
+    let bound = |key| {
+        statistics
+            .properties()
+            .get(key)
+            .and_then(|bound: &String| bound.parse::<i64>().ok())
+    };
+    (
+        bound(SIZE_MIN).map_or(size, |min| min.min(size)),
+        bound(SIZE_MAX).map_or(size, |max| max.max(size)),
+    )
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::protobuf::print_to_string;
     use anyhow::Result;
     use qrlew::{data_type::Id, display::Dot, relation::Table};
 
@@ -1564,7 +1802,7 @@ mod tests {
         assert!(pu_vec.len() == pu_admin_cols.len());
         let pu_field = field::Field::from((
             PID_COLUMN,
-            DataType::optional(DataType::from(Id::new(None, true))),
+            DataType::optional(DataType::from(Id::new(None, true, BTreeMap::new()))),
             Constraint::Unique,
         ));
         assert!(pu_vec.contains(&&pu_field));
@@ -1675,7 +1913,7 @@ mod tests {
             .collect();
         assert!(pu_vec.len() == pu_admin_cols.len());
 
-        let new_ds = dataset.with_constraint(None, "user", "user_id", Some(CONSTRAINT_UNIQUE))?;
+        let new_ds = dataset.with_constraint(["user", "user_id"], Some(CONSTRAINT_UNIQUE))?;
         assert!(
             new_ds
                 .schema()
@@ -1697,7 +1935,7 @@ mod tests {
                 .unwrap()
                 == CONSTRAINT_UNIQUE
         );
-        let new_ds = new_ds.with_constraint(None, "user", "user_id", None)?;
+        let new_ds = new_ds.with_constraint(["user", "user_id"], None)?;
         assert!(new_ds
             .schema()
             .type_()
@@ -1717,7 +1955,7 @@ mod tests {
             .get(CONSTRAINT)
             .is_none());
 
-        let new_ds = new_ds.with_range(None, "user", "user_id", -100.0, 100.0)?;
+        let new_ds = new_ds.with_range(["user", "user_id"], -100.0, 100.0)?;
         assert!(
             new_ds
                 .schema()
@@ -1740,9 +1978,7 @@ mod tests {
         );
 
         let new_ds = new_ds.with_possible_values(
-            None,
-            "user",
-            "first_name",
+            ["user", "first_name"],
             &["AA".to_string(), "BB".to_string()],
         )?;
         assert!(
@@ -1886,7 +2122,7 @@ mod tests {
         assert!(pu_vec.len() == pu_admin_cols.len());
         let pu_field = field::Field::from((
             PID_COLUMN,
-            DataType::optional(DataType::from(Id::new(None, false))),
+            DataType::optional(DataType::from(Id::new(None, false, BTreeMap::new()))),
             None,
         ));
         assert!(pu_vec.contains(&&pu_field));
@@ -1909,12 +2145,15 @@ mod tests {
         assert!(pu_vec.len() == pu_admin_cols.len());
         let pu_field = field::Field::from((
             PID_COLUMN,
-            DataType::optional(DataType::from(Id::new(None, false))),
+            DataType::optional(DataType::from(Id::new(None, false, BTreeMap::new()))),
             None,
         ));
         assert!(pu_vec.contains(&&pu_field));
-        let id_col =
-            field::Field::from(("b", DataType::from(Id::new(None, true)), Constraint::Unique));
+        let id_col = field::Field::from((
+            "b",
+            DataType::from(Id::new(None, true, BTreeMap::new())),
+            Constraint::Unique,
+        ));
         assert!(fields.contains(&&id_col));
         Ok(())
     }
@@ -2104,6 +2343,122 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_statistics_from_relations() -> Result<()> {
+        let relations = Hierarchy::from([
+            (vec!["a", "b"], Arc::new(relation_with_pu())),
+            (vec!["a", "c"], Arc::new(relation_with_pu())),
+        ]);
+        let ds = Dataset::try_from(&relations)?;
+        let statistics = ds.size_statistics().unwrap();
+        println!("STATS: \n{}\n", print_to_string(statistics).unwrap());
+        let union = statistics.union();
+        assert_eq!(union.name(), "Union");
+        assert_eq!(union.size(), 400);
+        let table = union.fields[0].statistics().struct_();
+        assert_eq!(table.name(), "Struct");
+        // Multiplicities are not measured
+        assert_eq!((table.size(), table.multiplicity()), (200, 0.));
+        assert_eq!(
+            table.fields.iter().map(|f| f.name()).collect::<Vec<_>>(),
+            vec!["a", "b"]
+        );
+        let a = table.fields[0].statistics().integer();
+        assert_eq!(a.size(), 200);
+        assert_eq!(
+            (
+                a.distribution().integer().min(),
+                a.distribution().integer().max()
+            ),
+            (-1, 1)
+        );
+        let b = table.fields[1].statistics().float();
+        assert_eq!(
+            (
+                b.distribution().double().min(),
+                b.distribution().double().max()
+            ),
+            (-2., 2.)
+        );
+        // The number of nulls of an Optional column is unknown
+        let optional = statistics_from_data_type(&DataType::optional(DataType::integer()), 200);
+        assert_eq!(optional.optional().size(), 200);
+        assert_eq!(structure::size_of(optional.optional().statistics()), 0);
+        // The statistics match the schema
+        let relations = ds.relations();
+        assert_eq!(relations[["a", "c"]].size().max(), Some(&200));
+        Ok(())
+    }
+
+    #[test]
+    fn test_size_range_and_multiplicity() -> Result<()> {
+        let relations = Hierarchy::from([
+            (vec!["a", "b"], Arc::new(relation_with_pu())),
+            (vec!["a", "c"], Arc::new(relation_with_pu())),
+        ]);
+        let ds = Dataset::try_from(&relations)?.with_size_range(["b"], 150, 250)?;
+        let relations = ds.relations();
+        assert_eq!(
+            relations[["a", "b"]].size(),
+            &data_type::Integer::from_interval(150, 250)
+        );
+        assert_eq!(
+            relations[["a", "c"]].size(),
+            &data_type::Integer::from_value(200)
+        );
+        // The range is kept when going back to a Dataset
+        let ds = Dataset::try_from(&relations)?;
+        assert_eq!(
+            ds.relations()[["a", "b"]].size(),
+            &data_type::Integer::from_interval(150, 250)
+        );
+        assert!(ds.with_size_range(["b"], 250, 150).is_err());
+        assert!(ds.with_size_range(["d"], 150, 250).is_err());
+        // Multiplicities are only known when measured
+        assert_eq!(ds.max_multiplicity(), None);
+        let parameters = ds.bounded_dp_parameters(DpParameters::from_epsilon_delta(1., 1e-5));
+        assert_eq!(
+            parameters.privacy_unit_max_multiplicity,
+            DpParameters::from_epsilon_delta(1., 1e-5).privacy_unit_max_multiplicity
+        );
+        let mut size = ds.size().unwrap().clone();
+        let tables = &mut size.mut_statistics().mut_union().fields;
+        tables[0].mut_statistics().mut_struct().set_multiplicity(1.);
+        tables[1].mut_statistics().mut_struct().set_multiplicity(3.);
+        let ds = Dataset::new(ds.dataset().clone(), ds.schema().clone(), Some(size));
+        assert_eq!(ds.max_multiplicity(), Some(3.));
+        let parameters = ds.bounded_dp_parameters(DpParameters::from_epsilon_delta(1., 1e-5));
+        assert_eq!(parameters.privacy_unit_max_multiplicity, 3.);
+        // A multiplicity of 1 makes the privacy unit column unique, and back
+        let relations = ds.relations();
+        let constraint = |path: [&str; 2]| {
+            relations[path]
+                .schema()
+                .field(PID_COLUMN)
+                .unwrap()
+                .constraint()
+        };
+        assert_eq!(constraint(["a", "b"]), Some(Constraint::Unique));
+        assert_eq!(constraint(["a", "c"]), None);
+        let ds = Dataset::try_from(&relations)?;
+        assert_eq!(ds.max_multiplicity(), Some(1.));
+        // Not when the rows were bounded by a differentially private profiling
+        let mut size = ds.size().unwrap().clone();
+        size.mut_statistics().mut_union().fields[0]
+            .mut_statistics()
+            .properties
+            .insert(MAX_CONTRIBUTIONS.to_string(), "1".to_string());
+        let ds = Dataset::new(ds.dataset().clone(), ds.schema().clone(), Some(size));
+        assert_eq!(
+            ds.relations()[["a", "b"]]
+                .schema()
+                .field(PID_COLUMN)?
+                .constraint(),
+            None
+        );
+        Ok(())
+    }
+
     #[test]
     fn test_relations_unions_case_2() -> Result<()> {
         let tab_as_relation = relation_with_pu();
@@ -2597,7 +2952,7 @@ mod tests {
         let new_proto_data_type: type_::Type = (&sarus_type).try_into()?;
         assert!(new_proto_data_type.float().min() == 0.0);
         assert!(new_proto_data_type.float().max() == 1.0);
-        assert!(new_proto_data_type.float().possible_values() == &[]);
+        assert!(new_proto_data_type.float().possible_values() == &[] as &[f64]);
         Ok(())
     }
 
@@ -3349,7 +3704,7 @@ mod tests {
         ]);
         let ds = Dataset::try_from(&relations)?;
         println!("SCHEMA: \n{}", ds.schema());
-        let ds = ds.with_range(Some("b"), "d", "b", -101., 101.)?;
+        let ds = ds.with_range(["b", "d", "b"], -101., 101.)?;
         assert!(
             ds.schema()
                 .type_()
@@ -3375,7 +3730,7 @@ mod tests {
                 .min()
                 == -101.
         );
-        let ds = ds.with_range(Some("fake"), "d", "b", -101., 101.);
+        let ds = ds.with_range(["fake", "d", "b"], -101., 101.);
         assert!(ds.is_err());
         Ok(())
     }
@@ -3389,7 +3744,7 @@ mod tests {
             (vec!["a", "c"], Arc::new(tab_as_relation.clone())),
         ]);
         let ds = Dataset::try_from(&relations)?;
-        let ds = ds.with_range(Some("b"), "d", "b", -101., 101.)?;
+        let ds = ds.with_range(["b", "d", "b"], -101., 101.)?;
         println!("SCHEMA: \n{}\n", ds.schema());
         assert!(
             ds.schema()
@@ -3422,11 +3777,79 @@ mod tests {
                 .min()
                 == -101.
         );
-        let ds = ds.with_range(Some("fake"), "d", "b", -101., 101.);
+        let ds = ds.with_range(["fake", "d", "b"], -101., 101.);
         assert!(ds.is_err());
         Ok(())
     }
 
+    #[test]
+    fn test_range_with_path() -> Result<()> {
+        let tab_as_relation = relation_with_pu();
+        let relations = Hierarchy::from([
+            (vec!["a", "b", "d"], Arc::new(tab_as_relation.clone())),
+            (vec!["a", "b", "e", "f"], Arc::new(tab_as_relation.clone())),
+            (vec!["a", "c"], Arc::new(tab_as_relation.clone())),
+        ]);
+        let ds = Dataset::try_from(&relations)?;
+        // A table nested two unions deep, addressed with a protobuf Path
+        let mut path = path::Path::new();
+        path.set_label("b".to_string());
+        let mut table = path::Path::new();
+        table.set_label("e".to_string());
+        let mut inner_table = path::Path::new();
+        inner_table.set_label("f".to_string());
+        let mut field = path::Path::new();
+        field.set_label("a".to_string());
+        inner_table.paths.push(field);
+        table.paths.push(inner_table);
+        path.paths.push(table);
+        let ds = ds.with_range(&path, -5., 5.)?;
+        let relations = ds.relations();
+        let relation = relations
+            .get(&["a", "b", "e", "f"].map(String::from))
+            .unwrap();
+        assert_eq!(
+            relation.schema().field("a")?.data_type(),
+            DataType::integer_interval(-5, 5)
+        );
+        // The admin wrapper may be explicit
+        let ds = ds.with_range(["sarus_data", "c", "b"], 0., 1.)?;
+        let relations = ds.relations();
+        let relation = relations.get(&["a", "c"].map(String::from)).unwrap();
+        assert_eq!(
+            relation.schema().field("b")?.data_type(),
+            DataType::float_interval(0., 1.)
+        );
+        // Branching paths are not identifiers
+        path.paths.push(path::Path::new());
+        assert!(ds.with_range(&path, -5., 5.).is_err());
+        // Range is not compatible with Boolean
+        assert!(ds
+            .with_range(["sarus_data", "c", "sarus_is_public"], 0., 1.)
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_unresolved_path_suggestions() -> Result<()> {
+        let tab_as_relation = relation();
+        let relations = Hierarchy::from([
+            (vec!["a", "beacon"], Arc::new(tab_as_relation.clone())),
+            (vec!["a", "census"], Arc::new(tab_as_relation.clone())),
+        ]);
+        let ds = Dataset::try_from(&relations)?;
+        let err = ds.with_range(["Beacn", "a"], 0., 1.).unwrap_err();
+        println!("{err}");
+        assert!(err.to_string().contains("Did you mean `beacon`?"));
+        let err = ds.with_range(["users", "a"], 0., 1.).unwrap_err();
+        println!("{err}");
+        assert!(err.to_string().contains("Available fields are"));
+        let err = ds.with_range(["beacon", "a", "x"], 0., 1.).unwrap_err();
+        println!("{err}");
+        assert!(err.to_string().contains("Cannot find `x` in `beacon.a`"));
+        Ok(())
+    }
+
     #[test]
     fn test_constraint() -> Result<()> {
         let tab_as_relation = relation();
@@ -3485,7 +3908,7 @@ mod tests {
                 .get(CONSTRAINT)
                 == None
         );
-        let ds = ds.with_constraint(Some("b"), "d", "a", Some(CONSTRAINT_UNIQUE))?;
+        let ds = ds.with_constraint(["b", "d", "a"], Some(CONSTRAINT_UNIQUE))?;
         println!(
             "{:?}",
             ds.schema()
@@ -3536,7 +3959,7 @@ mod tests {
                 .unwrap()
                 == CONSTRAINT_UNIQUE
         );
-        let ds = ds.with_constraint(Some("b"), "d", "a", None)?;
+        let ds = ds.with_constraint(["b", "d", "a"], None)?;
         assert!(
             ds.schema()
                 .type_()
@@ -3562,7 +3985,7 @@ mod tests {
                 .get(CONSTRAINT)
                 == None
         );
-        let ds = ds.with_constraint(Some("b"), "fake", "a", Some(CONSTRAINT_UNIQUE));
+        let ds = ds.with_constraint(["b", "fake", "a"], Some(CONSTRAINT_UNIQUE));
         assert!(ds.is_err());
         Ok(())
     }
@@ -3637,7 +4060,7 @@ mod tests {
                 .get(CONSTRAINT)
                 == None
         );
-        let ds = ds.with_constraint(Some("b"), "d", "a", Some(CONSTRAINT_UNIQUE))?;
+        let ds = ds.with_constraint(["b", "d", "a"], Some(CONSTRAINT_UNIQUE))?;
         println!("SCHEMA:\n{}", ds.schema());
         println!(
             "{:?}",
@@ -3701,7 +4124,7 @@ mod tests {
                 .unwrap()
                 == CONSTRAINT_UNIQUE
         );
-        let ds = ds.with_constraint(Some("b"), "d", "a", None)?;
+        let ds = ds.with_constraint(["b", "d", "a"], None)?;
         assert!(
             ds.schema()
                 .type_()
@@ -3734,7 +4157,7 @@ mod tests {
                 == None
         );
 
-        let ds = ds.with_constraint(Some("b"), "fake", "a", Some(CONSTRAINT_UNIQUE));
+        let ds = ds.with_constraint(["b", "fake", "a"], Some(CONSTRAINT_UNIQUE));
         assert!(ds.is_err());
         Ok(())
     }
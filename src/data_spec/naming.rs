@@ -0,0 +1,277 @@
+//! Alternative names to look relations up.
+//!
+//! `Dataset::relations` keys relations by their full path, prefixed with the schema name.
+//! `Dataset::relations_with_names` adds the suffixes of these paths matching a single relation,
+//! the aliases of the `aliases` schema property, the tables of the `Sql` spec of the dataset
+//! and the dotted full paths.
+//! Each name is an exact key of the Hierarchy, so that a relation can be found under
+//! several names. As `Hierarchy::get` falls back on the unique key ending with a path, the
+//! suffixes of a name ending other keys of its relation are added as keys too, and a name is
+//! skipped if it would make a suffix of another relation ambiguous.
+
+use super::{structure::parse_property, Dataset, Error, Result, ALIASES};
+use qrlew::{expr::identifier::Identifier, hierarchy::Hierarchy, relation::Relation};
+use std::{convert::TryInto, sync::Arc};
+
+/// A kind of alternative name of relations
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RelationName {
+    /// The suffixes of the path of a relation matching no other relation, as `beacon` or
+    /// `extract.beacon` for `shop.extract.extract.beacon`
+    Suffix,
+    /// The aliases listed in the `aliases` schema property
+    Alias,
+    /// The `schema.table` source paths of `Dataset::sql_table_matches` matching a single relation
+    SqlTable,
+    /// The full path of a relation as a single dotted label, as `shop.extract.beacon`,
+    /// designating the tables of `Dataset::privacy_unit` and of foreign key steps
+    Dotted,
+}
+
+impl RelationName {
+    /// All the kinds of names, by decreasing priority
+    pub const ALL: [RelationName; 4] = [
+        RelationName::Alias,
+        RelationName::SqlTable,
+        RelationName::Suffix,
+        RelationName::Dotted,
+    ];
+}
+
+impl Dataset {
+    /// The aliases of the tables, as (alias, table path) pairs.
+    /// Table paths are relative to the data part of the schema.
+    pub fn aliases(&self) -> Vec<(Identifier, Identifier)> {
+        parse_property::<Vec<(Vec<String>, Vec<String>)>>(self.schema.properties(), ALIASES)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(alias, table)| (alias.into(), table.into()))
+            .collect()
+    }
+
+    /// Add the alias `alias` to the table at `path`, replacing any previous use of the alias
+    pub fn with_alias<A, P>(&self, alias: A, path: P) -> Result<Self>
+    where
+        A: TryInto<Identifier>,
+        P: TryInto<Identifier>,
+        Error: From<A::Error> + From<P::Error>,
+    {
+        let alias: Vec<String> = alias.try_into()?.to_vec();
+        if alias.is_empty() {
+            return Err(Error::other("An alias cannot be empty"));
+        }
+        let path = self.data_path(path.try_into()?);
+        self.check_table(&path)?;
+        let mut aliases: Vec<(Vec<String>, Vec<String>)> =
+            parse_property(self.schema.properties(), ALIASES).unwrap_or_default();
+        aliases.retain(|(a, _)| a != &alias);
+        aliases.push((alias, path));
+        let mut schema = self.schema.clone();
+        schema.mut_properties().insert(
+            ALIASES.to_string(),
+            serde_json::to_string(&aliases).unwrap(),
+        );
+        Ok(Dataset::new(
+            self.dataset.clone(),
+            schema,
+            self.size.clone(),
+        ))
+    }
+
+    /// The relations keyed by their full path and by the alternative `names`.
+    /// Names are added in the order of `names` and a name already used is not reassigned,
+    /// so full paths come first.
+    pub fn relations_with_names(&self, names: &[RelationName]) -> Hierarchy<Arc<Relation>> {
+        let relations = self.relations();
+        let paths: Vec<Vec<String>> = relations.keys().cloned().collect();
+        let mut named: Vec<(Vec<String>, Vec<String>)> = paths
+            .iter()
+            .map(|path| (path.clone(), path.clone()))
+            .collect();
+        let mut add = |name: Vec<String>, path: &Vec<String>| {
+            if name.is_empty() || named.iter().any(|(n, _)| n == &name) {
+                return;
+            }
+            // The suffixes of the name which are not names, with the relations they resolve to
+            let mut suffixes = vec![];
+            for start in 1..name.len() {
+                let suffix = &name[start..];
+                if named.iter().any(|(n, _)| n == suffix) {
+                    continue;
+                }
+                let mut targets: Vec<&Vec<String>> = named
+                    .iter()
+                    .filter(|(n, _)| n.ends_with(suffix))
+                    .map(|(_, p)| p)
+                    .collect();
+                targets.sort();
+                targets.dedup();
+                match targets.as_slice() {
+                    [] => {}
+                    [target] if *target == path => suffixes.push(suffix.to_vec()),
+                    [_] => return,
+                    _ => {}
+                }
+            }
+            named.push((name, path.clone()));
+            named.extend(suffixes.into_iter().map(|suffix| (suffix, path.clone())));
+        };
+        // The relation whose path without schema name ends with `suffix`, if it is the only one
+        let unique = |suffix: &[String]| -> Option<&Vec<String>> {
+            let mut matches = paths.iter().filter(|path| path[1..].ends_with(suffix));
+            match (matches.next(), matches.next()) {
+                (Some(path), None) => Some(path),
+                _ => None,
+            }
+        };
+        for name in names {
+            match name {
+                RelationName::Suffix => {
+                    for path in &paths {
+                        for start in (1..path.len()).rev() {
+                            if unique(&path[start..]).is_some() {
+                                add(path[start..].to_vec(), path);
+                            }
+                        }
+                    }
+                }
+                RelationName::Alias => {
+                    for (alias, table) in self.aliases() {
+                        if let Some(path) = paths.iter().find(|path| path[1..] == *table.as_ref()) {
+                            add(alias.to_vec(), path);
+                        }
+                    }
+                }
+                RelationName::Dotted => {
+                    for path in &paths {
+                        add(vec![path.join(".")], path);
+                    }
+                }
+                RelationName::SqlTable => {
+                    let matches = self.sql_table_matches();
+                    for (path, source_path) in &matches {
+                        // A source path matching several relations is ambiguous
+                        if matches.iter().all(|(p, s)| s != source_path || p == path) {
+                            add(source_path.clone(), path);
+                        }
+                    }
+                }
+            }
+        }
+        named
+            .into_iter()
+            .filter_map(|(name, path)| Some((name, relations.get(&path)?.clone())))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protobuf::parse_from_str;
+    use anyhow::Result;
+    use qrlew::relation::Variant as _;
+
+    const DATASET: &str = r#"
+    {
+        "name": "shop",
+        "spec": {"sql": {"uri": "postgresql://localhost/shop", "tables": [
+            {"schema": "analytics", "table": "beacon"},
+            {"schema": "public", "table": "users"},
+            {}
+        ]}}
+    }
+    "#;
+
+    const SCHEMA: &str = r#"
+    {
+        "name": "shop",
+        "type": {"name": "Union", "union": {"fields": [
+            {"name": "extract", "type": {"name": "Union", "union": {"fields": [
+                {"name": "extract", "type": {"name": "Union", "union": {"fields": [
+                    {"name": "beacon", "type": {"name": "Struct", "struct": {"fields": [
+                        {"name": "id", "type": {"name": "Integer", "integer": {"min": "0", "max": "10"}}}
+                    ]}}}
+                ]}}},
+                {"name": "users", "type": {"name": "Struct", "struct": {"fields": [
+                    {"name": "id", "type": {"name": "Integer", "integer": {"min": "0", "max": "10"}}}
+                ]}}}
+            ]}}},
+            {"name": "archive", "type": {"name": "Union", "union": {"fields": [
+                {"name": "users", "type": {"name": "Struct", "struct": {"fields": [
+                    {"name": "id", "type": {"name": "Integer", "integer": {"min": "0", "max": "10"}}}
+                ]}}}
+            ]}}}
+        ]}}
+    }
+    "#;
+
+    fn path(labels: &[&str]) -> Vec<String> {
+        labels.iter().map(|label| label.to_string()).collect()
+    }
+
+    #[test]
+    fn test_relations_with_names() -> Result<()> {
+        let dataset = Dataset::new(parse_from_str(DATASET)?, parse_from_str(SCHEMA)?, None)
+            .with_alias(["events"], ["extract", "extract", "beacon"])?
+            .with_alias(["legacy", "users"], ["archive", "users"])?;
+        assert_eq!(dataset.aliases().len(), 2);
+        let relations = dataset.relations_with_names(&RelationName::ALL);
+        for (name, relation) in relations.iter() {
+            println!("{} -> {}", name.join("."), relation.name());
+        }
+        let beacon = relations.get(&path(&["shop", "extract", "extract", "beacon"]));
+        assert!(beacon.is_some());
+        for name in [
+            path(&["beacon"]),
+            path(&["extract", "beacon"]),
+            path(&["events"]),
+            path(&["analytics", "beacon"]),
+        ] {
+            assert_eq!(relations.get(&name), beacon);
+        }
+        let users = relations.get(&path(&["shop", "extract", "users"]));
+        assert_eq!(relations.get(&path(&["extract", "users"])), users);
+        // `users` is ambiguous, so is the Sql table `public.users`
+        assert!(relations.get(&path(&["public", "users"])).is_none());
+        assert_eq!(
+            relations.get(&path(&["legacy", "users"])),
+            relations.get(&path(&["archive", "users"]))
+        );
+        // Without alternative names only full paths are keys
+        assert_eq!(dataset.relations_with_names(&[]).len(), 3);
+        // Aliases follow the tables
+        let dataset = dataset.drop_table(["archive", "users"])?;
+        assert_eq!(dataset.aliases().len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_relation_names() -> Result<()> {
+        let dataset = Dataset::new(parse_from_str(DATASET)?, parse_from_str(SCHEMA)?, None)
+            .with_alias(["events"], ["extract", "extract", "beacon"])?
+            .with_alias(["old", "beacon"], ["archive", "users"])?;
+        let beacon = path(&["shop", "extract", "extract", "beacon"]);
+        for (name, names) in [
+            (RelationName::Suffix, vec![path(&["extract", "beacon"])]),
+            (RelationName::Alias, vec![path(&["events"])]),
+            (RelationName::SqlTable, vec![path(&["analytics", "beacon"])]),
+            (
+                RelationName::Dotted,
+                vec![path(&["shop.extract.extract.beacon"])],
+            ),
+        ] {
+            let relations = dataset.relations_with_names(&[name]);
+            let expected = relations.get(&beacon);
+            assert!(expected.is_some());
+            // The names do not shadow the suffixes of the full paths
+            for name in names.iter().chain([&path(&["beacon"])]) {
+                assert_eq!(relations.get(name), expected, "{:?} {:?}", name, names);
+            }
+        }
+        // `old.beacon` would make `beacon` ambiguous
+        let relations = dataset.relations_with_names(&[RelationName::Alias]);
+        assert!(relations.get(&path(&["old", "beacon"])).is_none());
+        Ok(())
+    }
+}
@@ -0,0 +1,387 @@
+//! Parquet message types of the tables of a dataset.
+//!
+//! Parquet fields have no metadata of their own, so bounds and possible values are kept
+//! in the file key-value metadata: a `sarus:<column path>` key per column,
+//! holding the `sarus:` keys of the Arrow metadata as a JSON object.
+
+use super::{arrow::sarus_metadata, Dataset, Error, Result};
+use crate::protobuf::type_;
+use parquet::{
+    basic::{LogicalType, Repetition, TimeUnit, Type as PhysicalType},
+    file::metadata::KeyValue,
+    format::{MicroSeconds, MilliSeconds, NanoSeconds},
+    schema::types::Type,
+};
+use qrlew::expr::identifier::Identifier;
+use std::sync::Arc;
+
+impl Dataset {
+    /// A Parquet message type per table, with the admin columns,
+    /// and the key-value metadata to write along
+    pub fn to_parquet_schemas(&self) -> Result<Vec<(Identifier, Type, Vec<KeyValue>)>> {
+        self.table_columns()
+            .into_iter()
+            .map(|(identifier, columns)| {
+                let mut key_value_metadata = Vec::new();
+                let fields = columns
+                    .into_iter()
+                    .map(|(column, type_)| {
+                        parquet_type(&[column], type_, &mut key_value_metadata).map(Arc::new)
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                let message_type = Type::group_type_builder(identifier.last()?)
+                    .with_fields(fields)
+                    .build()?;
+                Ok((identifier, message_type, key_value_metadata))
+            })
+            .collect()
+    }
+}
+
+/// The Parquet type of the column at `path`, required unless Optional
+fn parquet_type(
+    path: &[&str],
+    type_: &type_::Type,
+    key_value_metadata: &mut Vec<KeyValue>,
+) -> Result<Type> {
+    match type_.type_.as_ref() {
+        Some(type_::type_::Type::Optional(o)) => {
+            parquet_value_type(path, o.type_(), Repetition::OPTIONAL, key_value_metadata)
+        }
+        _ => parquet_value_type(path, type_, Repetition::REQUIRED, key_value_metadata),
+    }
+}
+
+fn parquet_value_type(
+    path: &[&str],
+    type_: &type_::Type,
+    repetition: Repetition,
+    key_value_metadata: &mut Vec<KeyValue>,
+) -> Result<Type> {
+    use type_::type_::Type as T;
+    let name = path.last().copied().unwrap_or_default();
+    let primitive = |physical_type: PhysicalType, logical_type: Option<LogicalType>| {
+        Type::primitive_type_builder(name, physical_type)
+            .with_repetition(repetition)
+            .with_logical_type(logical_type)
+    };
+    let integer = |bit_width: i8, is_signed: bool| {
+        Some(LogicalType::Integer {
+            bit_width,
+            is_signed,
+        })
+    };
+    let string = || primitive(PhysicalType::BYTE_ARRAY, Some(LogicalType::String));
+    let fields = |fields: Vec<(&str, &type_::Type)>,
+                  key_value_metadata: &mut Vec<KeyValue>|
+     -> Result<Vec<_>> {
+        fields
+            .into_iter()
+            .map(|(field, type_)| {
+                let path: Vec<&str> = path.iter().copied().chain([field]).collect();
+                parquet_type(&path, type_, key_value_metadata).map(Arc::new)
+            })
+            .collect()
+    };
+    let parquet_type = match type_.type_.as_ref() {
+        None | Some(T::Null(_)) | Some(T::Unit(_)) => {
+            primitive(PhysicalType::INT32, Some(LogicalType::Unknown)).build()?
+        }
+        Some(T::Boolean(_)) => primitive(PhysicalType::BOOLEAN, None).build()?,
+        Some(T::Integer(i)) => {
+            use type_::type_::integer::Base;
+            match i.base() {
+                Base::INT8 => primitive(PhysicalType::INT32, integer(8, true)),
+                Base::INT16 => primitive(PhysicalType::INT32, integer(16, true)),
+                Base::INT32 => primitive(PhysicalType::INT32, integer(32, true)),
+                Base::INT64 => primitive(PhysicalType::INT64, integer(64, true)),
+                Base::UINT8 => primitive(PhysicalType::INT32, integer(8, false)),
+                Base::UINT16 => primitive(PhysicalType::INT32, integer(16, false)),
+                Base::UINT32 => primitive(PhysicalType::INT32, integer(32, false)),
+                Base::UINT64 => primitive(PhysicalType::INT64, integer(64, false)),
+            }
+            .build()?
+        }
+        Some(T::Enum(_)) => primitive(PhysicalType::BYTE_ARRAY, Some(LogicalType::Enum)).build()?,
+        Some(T::Float(f)) => {
+            use type_::type_::float::Base;
+            match f.base() {
+                Base::FLOAT16 => primitive(
+                    PhysicalType::FIXED_LEN_BYTE_ARRAY,
+                    Some(LogicalType::Float16),
+                )
+                .with_length(2),
+                Base::FLOAT32 => primitive(PhysicalType::FLOAT, None),
+                Base::FLOAT64 => primitive(PhysicalType::DOUBLE, None),
+            }
+            .build()?
+        }
+        Some(T::Text(_)) => string().build()?,
+        Some(T::Bytes(_)) => primitive(PhysicalType::BYTE_ARRAY, None).build()?,
+        Some(T::Struct(s)) => Type::group_type_builder(name)
+            .with_repetition(repetition)
+            .with_fields(fields(
+                s.fields.iter().map(|f| (f.name(), f.type_())).collect(),
+                key_value_metadata,
+            )?)
+            .build()?,
+        // A group with a field per variant, only one of them being set
+        Some(T::Union(u)) => Type::group_type_builder(name)
+            .with_repetition(repetition)
+            .with_fields(
+                fields(
+                    u.fields.iter().map(|f| (f.name(), f.type_())).collect(),
+                    key_value_metadata,
+                )?
+                .into_iter()
+                .map(|field| {
+                    let field = if field.is_optional() {
+                        field
+                    } else {
+                        Arc::new(optional(&field)?)
+                    };
+                    Ok(field)
+                })
+                .collect::<Result<Vec<_>>>()?,
+            )
+            .build()?,
+        Some(T::Optional(o)) => {
+            return parquet_value_type(path, o.type_(), repetition, key_value_metadata)
+        }
+        Some(T::List(l)) => list(
+            name,
+            repetition,
+            parquet_type(
+                &path.iter().copied().chain(["element"]).collect::<Vec<_>>(),
+                l.type_(),
+                key_value_metadata,
+            )?,
+        )?,
+        Some(T::Array(a)) => {
+            let element = parquet_type(
+                &path.iter().copied().chain(["element"]).collect::<Vec<_>>(),
+                a.type_(),
+                key_value_metadata,
+            )?;
+            let element = a.shape.iter().skip(1).try_fold(element, |element, _| {
+                list("element", Repetition::REQUIRED, element)
+            })?;
+            list(name, repetition, element)?
+        }
+        Some(T::Date(d)) => match d.base() {
+            type_::type_::date::Base::INT32 => {
+                primitive(PhysicalType::INT32, Some(LogicalType::Date))
+            }
+            type_::type_::date::Base::STRING => string(),
+        }
+        .build()?,
+        Some(T::Time(t)) => {
+            use type_::type_::time::Base;
+            let time = |unit| {
+                Some(LogicalType::Time {
+                    is_adjusted_to_u_t_c: false,
+                    unit,
+                })
+            };
+            match t.base() {
+                Base::INT32_MS => {
+                    primitive(PhysicalType::INT32, time(TimeUnit::MILLIS(MilliSeconds {})))
+                }
+                Base::INT64_US => {
+                    primitive(PhysicalType::INT64, time(TimeUnit::MICROS(MicroSeconds {})))
+                }
+                Base::INT64_NS => {
+                    primitive(PhysicalType::INT64, time(TimeUnit::NANOS(NanoSeconds {})))
+                }
+                Base::STRING => string(),
+            }
+            .build()?
+        }
+        Some(T::Datetime(d)) => {
+            use type_::type_::datetime::Base;
+            let timestamp = |unit| {
+                Some(LogicalType::Timestamp {
+                    is_adjusted_to_u_t_c: false,
+                    unit,
+                })
+            };
+            match d.base() {
+                Base::INT64_MS => primitive(
+                    PhysicalType::INT64,
+                    timestamp(TimeUnit::MILLIS(MilliSeconds {})),
+                ),
+                Base::INT64_NS => primitive(
+                    PhysicalType::INT64,
+                    timestamp(TimeUnit::NANOS(NanoSeconds {})),
+                ),
+                Base::STRING => string(),
+            }
+            .build()?
+        }
+        // Parquet has no duration type, the unit is kept in the metadata
+        Some(T::Duration(_)) => primitive(PhysicalType::INT64, None).build()?,
+        Some(T::Id(i)) => {
+            use type_::type_::id::Base;
+            match i.base() {
+                Base::INT8 | Base::INT16 | Base::INT32 => primitive(PhysicalType::INT32, None),
+                Base::INT64 => primitive(PhysicalType::INT64, None),
+                Base::STRING => string(),
+                Base::BYTES => primitive(PhysicalType::BYTE_ARRAY, None),
+            }
+            .build()?
+        }
+        Some(T::Constrained(c)) => {
+            return parquet_value_type(path, c.type_(), repetition, key_value_metadata)
+        }
+        Some(T::Hypothesis(_)) => {
+            return Err(Error::other(format!(
+                "Cannot convert the Hypothesis type of {} to Parquet",
+                path.join(".")
+            )))
+        }
+    };
+    let mut metadata = match type_.type_.as_ref() {
+        // The metadata of the fields have their own keys
+        Some(T::Struct(_)) | Some(T::Union(_)) => Default::default(),
+        _ => sarus_metadata(type_)?,
+    };
+    if let Some(T::Duration(d)) = type_.type_.as_ref() {
+        metadata.insert("sarus:unit".to_string(), d.unit.clone());
+    }
+    if !metadata.is_empty() {
+        key_value_metadata.push(KeyValue::new(
+            format!("sarus:{}", path.join(".")),
+            serde_json::to_string(&metadata).unwrap(),
+        ));
+    }
+    Ok(parquet_type)
+}
+
+/// A three-level list of `element`
+fn list(name: &str, repetition: Repetition, element: Type) -> Result<Type> {
+    let element = Type::group_type_builder("list")
+        .with_repetition(Repetition::REPEATED)
+        .with_fields(vec![Arc::new(rename(&element, "element")?)])
+        .build()?;
+    Ok(Type::group_type_builder(name)
+        .with_repetition(repetition)
+        .with_logical_type(Some(LogicalType::List))
+        .with_fields(vec![Arc::new(element)])
+        .build()?)
+}
+
+/// The same type with another name
+fn rename(type_: &Type, name: &str) -> Result<Type> {
+    with_info(type_, name, type_.get_basic_info().repetition())
+}
+
+/// The same type, optional
+fn optional(type_: &Type) -> Result<Type> {
+    with_info(type_, type_.name(), Repetition::OPTIONAL)
+}
+
+fn with_info(type_: &Type, name: &str, repetition: Repetition) -> Result<Type> {
+    let info = type_.get_basic_info();
+    Ok(match type_ {
+        Type::PrimitiveType {
+            physical_type,
+            type_length,
+            scale,
+            precision,
+            ..
+        } => Type::primitive_type_builder(name, *physical_type)
+            .with_repetition(repetition)
+            .with_logical_type(info.logical_type())
+            .with_length(*type_length)
+            .with_scale(*scale)
+            .with_precision(*precision)
+            .build()?,
+        Type::GroupType { fields, .. } => Type::group_type_builder(name)
+            .with_repetition(repetition)
+            .with_logical_type(info.logical_type())
+            .with_fields(fields.clone())
+            .build()?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protobuf::parse_from_str;
+    use anyhow::Result;
+    use parquet::schema::printer::print_schema;
+
+    const SCHEMA: &str = r#"
+    {
+        "name": "shop",
+        "type": {"name": "Struct", "struct": {"fields": [
+            {"name": "sarus_data", "type": {"name": "Union", "union": {"fields": [
+                {"name": "users", "type": {"name": "Struct", "struct": {"fields": [
+                    {"name": "id", "type": {"name": "Integer", "integer": {"base": "UINT16", "min": "0", "max": "1000"}}},
+                    {"name": "name", "type": {"name": "Text", "text": {"encoding": "UTF-8", "possible_values": ["Alice", "Bob"]}}},
+                    {"name": "age", "type": {"name": "Optional", "optional": {"type": {"name": "Float", "float": {"min": 0, "max": 120}}}}},
+                    {"name": "tags", "type": {"name": "List", "list": {"type": {"name": "Text", "text": {}}, "max_size": "3"}}}
+                ]}}},
+                {"name": "visits", "type": {"name": "Struct", "struct": {"fields": [
+                    {"name": "at", "type": {"name": "Datetime", "datetime": {"format": "%Y-%m-%d %H:%M:%S", "min": "2020-01-01 00:00:00", "max": "2030-01-01 00:00:00", "base": "INT64_MS"}}},
+                    {"name": "length", "type": {"name": "Duration", "duration": {"unit": "ms", "min": "0", "max": "3600000"}}}
+                ]}}}
+            ]}}},
+            {"name": "sarus_is_public", "type": {"name": "Boolean", "boolean": {}}},
+            {"name": "sarus_privacy_unit", "type": {"name": "Optional", "optional": {"type": {"name": "Id", "id": {"base": "STRING"}}}}},
+            {"name": "sarus_weights", "type": {"name": "Float", "float": {"min": 0, "max": 1}}}
+        ]}}
+    }
+    "#;
+
+    #[test]
+    fn test_to_parquet_schemas() -> Result<()> {
+        let dataset = Dataset::new(parse_from_str("{}")?, parse_from_str(SCHEMA)?, None);
+        let schemas = dataset.to_parquet_schemas()?;
+        for (_, message_type, key_value_metadata) in &schemas {
+            print_schema(&mut std::io::stdout(), message_type);
+            println!("{:?}", key_value_metadata);
+        }
+        assert_eq!(schemas.len(), 2);
+        let (identifier, users, key_value_metadata) = &schemas[0];
+        assert_eq!(identifier, &Identifier::from(["shop", "users"]));
+        assert_eq!(users.name(), "users");
+        let fields = users.get_fields();
+        assert_eq!(fields.len(), 7);
+        assert_eq!(
+            fields[0].get_basic_info().logical_type(),
+            Some(LogicalType::Integer {
+                bit_width: 16,
+                is_signed: false
+            })
+        );
+        assert!(fields[2].is_optional());
+        assert_eq!(
+            fields[3].get_basic_info().logical_type(),
+            Some(LogicalType::List)
+        );
+        let id = key_value_metadata
+            .iter()
+            .find(|kv| kv.key == "sarus:id")
+            .unwrap();
+        assert_eq!(
+            id.value.as_deref(),
+            Some(r#"{"sarus:max":"1000","sarus:min":"0"}"#)
+        );
+        assert!(key_value_metadata.iter().any(|kv| kv.key == "sarus:tags"));
+        let (_, visits, key_value_metadata) = &schemas[1];
+        assert_eq!(
+            visits.get_fields()[0].get_basic_info().logical_type(),
+            Some(LogicalType::Timestamp {
+                is_adjusted_to_u_t_c: false,
+                unit: TimeUnit::MILLIS(MilliSeconds {})
+            })
+        );
+        assert!(key_value_metadata[1]
+            .value
+            .as_ref()
+            .unwrap()
+            .contains(r#""sarus:unit":"ms""#));
+        Ok(())
+    }
+}
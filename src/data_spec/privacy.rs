@@ -0,0 +1,329 @@
+//! The protected entity and the public tables of a Dataset.
+//!
+//! The protected entity is designated by the `protected` path of the schema,
+//! the public tables are listed in the `public_fields` property of the Unions holding them.
+
+use super::{
+    structure::{data_type, data_type_mut, parse_property, type_at, type_at_mut},
+    table_structs, Dataset, Error, Result, PID_COLUMN, PUBLIC_FIELDS, SARUS_DATA, WEIGHTS,
+};
+use crate::protobuf::{path, type_};
+use qrlew::{
+    builder::With, expr::identifier::Identifier, hierarchy::Hierarchy,
+    privacy_unit_tracking::PrivacyUnit, relation::Relation,
+};
+use std::{convert::TryInto, sync::Arc};
+
+impl Dataset {
+    /// The path to the protected entity
+    pub fn protected_path(&self) -> &path::Path {
+        self.schema.protected()
+    }
+
+    /// Set the path to the protected entity.
+    /// The labels below the root designate fields of the data part of the schema.
+    pub fn with_protected_path(&self, protected: path::Path) -> Result<Self> {
+        let mut labels = vec![];
+        for child in &protected.paths {
+            check_path(data_type(&self.schema), child, &mut labels)?;
+        }
+        let mut schema = self.schema.clone();
+        schema.set_protected(protected);
+        Ok(Dataset::new(
+            self.dataset.clone(),
+            schema,
+            self.size.clone(),
+        ))
+    }
+
+    /// The paths of the tables of the protected entity, as in `relations`: the tables
+    /// designated by the leaves of the protected path, or holding them, or held by them.
+    /// The root of the path designates the data part of the schema.
+    /// Empty if the schema has no protected path.
+    pub fn protected_tables(&self) -> Vec<Identifier> {
+        if !self.schema.has_protected() {
+            return vec![];
+        }
+        let mut leaves = vec![];
+        protected_leaves(self.protected_path(), &[], true, &mut leaves);
+        table_structs(self.schema_type_data(), None)
+            .into_iter()
+            .map(|(table, _, _)| table.to_vec())
+            .filter(|table| {
+                leaves
+                    .iter()
+                    .any(|leaf| leaf.starts_with(table) || table.starts_with(leaf))
+            })
+            .map(|table| self.full_path(&table))
+            .collect()
+    }
+
+    /// The paths of the public tables, as in `relations`
+    pub fn public_tables(&self) -> Vec<Identifier> {
+        let mut tables = vec![];
+        public_tables(
+            data_type(&self.schema),
+            Identifier::empty(),
+            false,
+            &mut tables,
+        );
+        tables
+            .into_iter()
+            .map(|table| self.full_path(&table))
+            .collect()
+    }
+
+    /// Make the table (or the Union of tables) at `path` public or not
+    pub fn with_public<P>(&self, path: P, public: bool) -> Result<Self>
+    where
+        P: TryInto<Identifier>,
+        Error: From<P::Error>,
+    {
+        let path = self.data_path(path.try_into()?);
+        let Some((name, parent_path)) = path.split_last() else {
+            return Err(Error::other("Cannot make the schema root public"));
+        };
+        let mut schema = self.schema.clone();
+        let parent = type_at_mut(
+            data_type_mut(&mut schema),
+            parent_path,
+            &Identifier::empty(),
+        )?;
+        match parent.type_.as_ref() {
+            Some(type_::type_::Type::Union(u)) if u.fields.iter().any(|f| f.name() == name) => {}
+            _ => {
+                return Err(Error::other(format!(
+                    "`{}` is not a table in a Union of tables",
+                    path.join(".")
+                )))
+            }
+        }
+        let properties = parent.mut_properties();
+        let mut public_fields: Vec<String> =
+            parse_property(properties, PUBLIC_FIELDS).unwrap_or_default();
+        public_fields.retain(|field| field != name);
+        if public {
+            public_fields.push(name.clone());
+        }
+        properties.insert(
+            PUBLIC_FIELDS.to_string(),
+            serde_json::to_string(&public_fields).unwrap(),
+        );
+        Ok(Dataset::new(
+            self.dataset.clone(),
+            schema,
+            self.size.clone(),
+        ))
+    }
+
+    /// The relations of the public tables
+    pub fn public_relations(&self) -> Hierarchy<Arc<Relation>> {
+        let public_tables = self.public_tables();
+        self.relations()
+            .into_iter()
+            .filter(|(path, _)| public_tables.iter().any(|table| table.as_ref() == path))
+            .collect()
+    }
+
+    /// The privacy unit of the private tables, if the schema has admin columns.
+    /// Public tables are left out so that qrlew handles them as public relations.
+    /// Tables are designated by their full dotted path, resolved in
+    /// `relations_with_names(&[RelationName::Dotted])`.
+    pub fn privacy_unit(&self) -> Option<PrivacyUnit> {
+        if !self.schema_has_admin_columns() {
+            return None;
+        }
+        let public_tables = self.public_tables();
+        let private_tables: Vec<String> = self
+            .relations()
+            .into_iter()
+            .filter(|(path, _)| !public_tables.iter().any(|table| table.as_ref() == path))
+            .map(|(path, _)| path.join("."))
+            .collect();
+        Some(PrivacyUnit::from(
+            private_tables
+                .iter()
+                .map(|table| (table.as_str(), vec![], PID_COLUMN, WEIGHTS))
+                .collect::<Vec<_>>(),
+        ))
+    }
+}
+
+/// The data paths designated by the leaves of a protected path below `prefix`
+fn protected_leaves(
+    path: &path::Path,
+    prefix: &[String],
+    root: bool,
+    leaves: &mut Vec<Vec<String>>,
+) {
+    let mut prefix = prefix.to_vec();
+    if !root && path.label() != SARUS_DATA {
+        prefix.push(path.label().to_string());
+    }
+    if path.paths.is_empty() {
+        leaves.push(prefix.clone());
+    }
+    for child in &path.paths {
+        protected_leaves(child, &prefix, false, leaves);
+    }
+}
+
+/// Check the labels of a path tree designate fields of `type_`
+fn check_path(type_: &type_::Type, path: &path::Path, labels: &mut Vec<String>) -> Result<()> {
+    labels.push(path.label().to_string());
+    type_at(type_, labels, &Identifier::empty())?;
+    for child in &path.paths {
+        check_path(type_, child, labels)?;
+    }
+    labels.pop();
+    Ok(())
+}
+
+/// Collect the paths of the public tables below `type_`
+fn public_tables(
+    type_: &type_::Type,
+    path: Identifier,
+    public: bool,
+    tables: &mut Vec<Identifier>,
+) {
+    match type_.type_.as_ref() {
+        Some(type_::type_::Type::Struct(_)) if public => tables.push(path),
+        Some(type_::type_::Type::Union(u)) => {
+            let public_fields: Vec<String> =
+                parse_property(type_.properties(), PUBLIC_FIELDS).unwrap_or_default();
+            for field in &u.fields {
+                public_tables(
+                    field.type_(),
+                    path.clone().with(field.name().to_string()),
+                    public || public_fields.iter().any(|name| name == field.name()),
+                    tables,
+                )
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        data_spec::{RelationName, PUBLIC},
+        protobuf::parse_from_str,
+    };
+    use anyhow::Result;
+    use qrlew::{
+        data_type::{DataType, DataTyped},
+        relation::Variant as _,
+    };
+
+    const SCHEMA: &str = r#"
+    {
+        "name": "a",
+        "type": {
+            "name": "Struct",
+            "struct": {
+                "fields": [
+                    {"name": "sarus_data", "type": {"name": "Union", "union": {"fields": [
+                        {"name": "users", "type": {"name": "Struct", "struct": {"fields": [
+                            {"name": "id", "type": {"name": "Integer", "integer": {"min": "0", "max": "1000"}}}
+                        ]}}},
+                        {"name": "orders", "type": {"name": "Struct", "struct": {"fields": [
+                            {"name": "user_id", "type": {"name": "Integer", "integer": {"min": "0", "max": "1000"}}}
+                        ]}}}
+                    ]}, "properties": {"public_fields": "[]"}}},
+                    {"name": "sarus_is_public", "type": {"name": "Boolean", "boolean": {}}},
+                    {"name": "sarus_privacy_unit", "type": {"name": "Optional", "optional": {"type": {"name": "Id", "id": {"base": "STRING"}}}}},
+                    {"name": "sarus_weights", "type": {"name": "Float", "float": {"min": 0, "max": 1}}}
+                ]
+            }
+        },
+        "protected": {"label": "data", "paths": []}
+    }
+    "#;
+
+    fn dataset() -> Dataset {
+        Dataset::new(
+            parse_from_str("{}").unwrap(),
+            parse_from_str(SCHEMA).unwrap(),
+            None,
+        )
+    }
+
+    #[test]
+    fn test_protected_path() -> Result<()> {
+        let dataset = dataset();
+        assert_eq!(dataset.protected_path().label(), "data");
+        let protected: path::Path = parse_from_str(
+            r#"{"label": "data", "paths": [{"label": "users", "paths": [{"label": "id"}]}]}"#,
+        )?;
+        let dataset = dataset.with_protected_path(protected.clone())?;
+        assert_eq!(dataset.protected_path(), &protected);
+        let protected: path::Path =
+            parse_from_str(r#"{"label": "data", "paths": [{"label": "user"}]}"#)?;
+        assert!(dataset.with_protected_path(protected).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_public_tables() -> Result<()> {
+        let dataset = dataset();
+        assert!(dataset.public_tables().is_empty());
+        assert_eq!(dataset.privacy_unit().unwrap().len(), 2);
+        let dataset = dataset.with_public(["orders"], true)?;
+        assert_eq!(dataset.public_tables(), vec![["a", "orders"].into()]);
+        assert_eq!(dataset.public_relations().len(), 1);
+        let privacy_unit = dataset.privacy_unit().unwrap();
+        assert_eq!(
+            privacy_unit
+                .iter()
+                .map(|(table, _)| table.as_str())
+                .collect::<Vec<_>>(),
+            vec!["a.users"]
+        );
+        // Public tables are marked in their relation
+        let relations = dataset.relations();
+        assert_eq!(
+            relations[["a", "orders"]]
+                .schema()
+                .field(PUBLIC)?
+                .data_type(),
+            DataType::boolean_values([true])
+        );
+        assert_eq!(
+            relations[["a", "users"]]
+                .schema()
+                .field(PUBLIC)?
+                .data_type(),
+            DataType::boolean()
+        );
+        // Renamed public tables stay public
+        let dataset = dataset.rename_table(["orders"], "purchases")?;
+        assert_eq!(dataset.public_tables(), vec![["a", "purchases"].into()]);
+        let dataset = dataset.with_public(["sarus_data", "purchases"], false)?;
+        assert!(dataset.public_tables().is_empty());
+        assert!(dataset.with_public(["users", "id"], true).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_privacy_unit_of_nested_tables() -> Result<()> {
+        let schema = SCHEMA.replace(
+            r#"{"name": "orders", "type": {"name": "Struct""#,
+            r#"{"name": "archive", "type": {"name": "Union", "union": {"fields": [{"name": "users", "type": {"name": "Struct", "struct": {"fields": []}}}]}}},
+                        {"name": "orders", "type": {"name": "Struct""#,
+        );
+        let dataset = Dataset::new(parse_from_str("{}")?, parse_from_str(&schema)?, None);
+        let privacy_unit = dataset.privacy_unit().unwrap();
+        let names: Vec<&str> = privacy_unit
+            .iter()
+            .map(|(table, _)| table.as_str())
+            .collect();
+        assert_eq!(names, vec!["a.archive.users", "a.orders", "a.users"]);
+        let relations = dataset.relations_with_names(&[RelationName::Dotted]);
+        for name in names {
+            assert!(relations.get(&[name.to_string()]).is_some());
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,1136 @@
+//! Profiling of local data files: Size and Marginals are computed from CSV or Parquet files
+//! holding the tables of a Dataset.
+//! Files are read row by row, only the counts of the values of each column are kept in memory.
+//! These counts are bounded: beyond `MAX_COUNTS` distinct values, neighbouring numeric values
+//! are merged, keeping the extreme ones, and the least frequent texts are dropped.
+//!
+//! Each column is read according to its type. Temporal values are described by Integer
+//! distributions: dates in days since 1970-01-01, times in microseconds since midnight,
+//! datetimes in microseconds since 1970-01-01 00:00:00 and durations in the unit of their type.
+//! Text values are described by the Enum distribution of their most frequent values.
+
+use super::{
+    edit::{temporal_format, Temporal},
+    structure::{data_type, size_of, statistics_skeleton, with_size},
+    Dataset, Error, Result,
+};
+use crate::protobuf::{dataset, marginals, size, statistics, type_};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime};
+use protobuf::MessageField;
+use qrlew::expr::identifier::Identifier;
+use std::{
+    collections::{BTreeMap, HashMap},
+    convert::TryInto,
+    fmt, fs, mem,
+    path::{Path, PathBuf},
+};
+
+/// The maximum number of points of a profiled distribution
+pub const MAX_POINTS: usize = 100;
+
+/// The number of distinct values of a column whose counts are kept in memory.
+/// The counts are compacted back to this number when they reach twice as many values.
+pub const MAX_COUNTS: usize = 10_000;
+
+/// A local data file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DataFile {
+    Csv(PathBuf),
+    Parquet(PathBuf),
+}
+
+impl DataFile {
+    /// A data file from the `format` and `uri` of a File spec
+    pub fn new(format: &str, uri: &str) -> Result<Self> {
+        let path = local_path(uri)?;
+        match format.to_lowercase().as_str() {
+            "csv" => Ok(DataFile::Csv(path)),
+            "parquet" => Ok(DataFile::Parquet(path)),
+            _ => Err(Error::other(format!(
+                "Cannot profile files in format `{}`",
+                format
+            ))),
+        }
+    }
+
+    /// The names of the columns of the file
+    pub(super) fn columns(&self) -> Result<Vec<String>> {
+        match self {
+            DataFile::Csv(path) => Ok(csv::Reader::from_path(path)?
+                .headers()?
+                .iter()
+                .map(str::to_string)
+                .collect()),
+            DataFile::Parquet(path) => parquet_columns(path),
+        }
+    }
+
+    /// The rows of the file, read one at a time, with one cell per name of `columns`
+    pub(super) fn rows(&self, columns: &[&str]) -> Result<Rows> {
+        match self {
+            DataFile::Csv(path) => csv_rows(path, columns),
+            DataFile::Parquet(path) => parquet_rows(path, columns),
+        }
+    }
+}
+
+/// The rows of a file, read one at a time
+pub(super) type Rows = Box<dyn Iterator<Item = Result<Vec<Cell>>>>;
+
+impl Dataset {
+    /// Profile the files of the File or Files spec of a single table dataset.
+    /// All the files matching the pattern of a Files spec are partitions of the table.
+    pub fn profile(&self) -> Result<(size::Size, marginals::Marginals)> {
+        self.profile_files(self.spec_files()?)
+    }
+
+    /// Profile the tables from local files. Each file is given with the path of its table,
+    /// relative to the data part of the schema (empty for a single table dataset).
+    /// Several files for the same table are partitions of the table.
+    pub fn profile_files<P, I>(&self, files: I) -> Result<(size::Size, marginals::Marginals)>
+    where
+        P: TryInto<Identifier>,
+        Error: From<P::Error>,
+        I: IntoIterator<Item = (P, DataFile)>,
+    {
+        let tables = self.table_files(files)?;
+        let statistics = profile_type(data_type(&self.schema), &[], &tables, &mut profile_table)?;
+        Ok(self.size_and_marginals(statistics))
+    }
+
+    /// The files of the File or Files spec, holding the single table of the dataset
+    pub(super) fn spec_files(&self) -> Result<Vec<(Identifier, DataFile)>> {
+        let files = match self.dataset.spec.spec.as_ref() {
+            Some(dataset::dataset::spec::Spec::File(file)) => {
+                vec![DataFile::new(file.format(), file.uri())?]
+            }
+            Some(dataset::dataset::spec::Spec::Files(files)) => {
+                matching_paths(files.uri_pattern())?
+                    .into_iter()
+                    .map(|path| DataFile::new(files.format(), &path.to_string_lossy()))
+                    .collect::<Result<_>>()?
+            }
+            _ => {
+                return Err(Error::other(
+                    "The dataset spec does not point at local files",
+                ))
+            }
+        };
+        Ok(files
+            .into_iter()
+            .map(|file| (Identifier::empty(), file))
+            .collect())
+    }
+
+    /// The files of each table, by data path
+    pub(super) fn table_files<P, I>(&self, files: I) -> Result<BTreeMap<Vec<String>, Vec<DataFile>>>
+    where
+        P: TryInto<Identifier>,
+        Error: From<P::Error>,
+        I: IntoIterator<Item = (P, DataFile)>,
+    {
+        let single_table = matches!(
+            self.schema_type_data().type_,
+            Some(type_::type_::Type::Struct(_))
+        );
+        let mut tables: BTreeMap<Vec<String>, Vec<DataFile>> = BTreeMap::new();
+        for (path, file) in files {
+            let path = self.data_path(path.try_into()?);
+            if !(single_table && path.is_empty()) {
+                self.check_table(&path)?;
+            }
+            tables.entry(path).or_default().push(file);
+        }
+        Ok(tables)
+    }
+
+    /// The Size and Marginals messages of the profiled statistics
+    pub(super) fn size_and_marginals(
+        &self,
+        statistics: statistics::Statistics,
+    ) -> (size::Size, marginals::Marginals) {
+        let mut marginals = marginals::Marginals::new();
+        marginals.dataset = self.dataset.uuid.clone();
+        marginals.name = format!("{}_marginals", self.schema.name());
+        marginals.statistics = MessageField::some(statistics.clone());
+        let mut size = size::Size::new();
+        size.dataset = self.dataset.uuid.clone();
+        size.name = format!("{}_sizes", self.schema.name());
+        size.statistics = MessageField::some(without_distributions(statistics));
+        (size, marginals)
+    }
+}
+
+/// The statistics of the tables below `type_`, each table being profiled from its files
+/// by `profile_table`
+pub(super) fn profile_type<F>(
+    type_: &type_::Type,
+    path: &[String],
+    tables: &BTreeMap<Vec<String>, Vec<DataFile>>,
+    profile_table: &mut F,
+) -> Result<statistics::Statistics>
+where
+    F: FnMut(&type_::type_::Struct, &[String], &[DataFile]) -> Result<statistics::Statistics>,
+{
+    match type_.type_.as_ref() {
+        Some(type_::type_::Type::Struct(table)) => {
+            let files = tables.get(path).ok_or_else(|| {
+                Error::other(format!("No data file for table `{}`", path.join(".")))
+            })?;
+            profile_table(table, path, files)
+        }
+        Some(type_::type_::Type::Union(union)) => {
+            let mut result = statistics::statistics::Union::new();
+            result.name = "Union".to_string();
+            for field in union.fields.iter() {
+                let path: Vec<String> = path.iter().cloned().chain([field.name.clone()]).collect();
+                let statistics = profile_type(field.type_(), &path, tables, profile_table)?;
+                result.size += size_of(&statistics);
+                result.fields.push(statistics::statistics::union::Field {
+                    name: field.name.clone(),
+                    statistics: MessageField::some(statistics),
+                    ..Default::default()
+                });
+            }
+            let mut statistics = statistics::Statistics::new();
+            statistics.name = type_.name.clone();
+            statistics.set_union(result);
+            Ok(statistics)
+        }
+        _ => Err(Error::other(format!(
+            "`{}` is neither a table nor a Union of tables",
+            path.join(".")
+        ))),
+    }
+}
+
+/// Profile a table from the rows of its files, without holding them in memory
+fn profile_table(
+    table: &type_::type_::Struct,
+    path: &[String],
+    files: &[DataFile],
+) -> Result<statistics::Statistics> {
+    let columns: Vec<&str> = table.fields.iter().map(|field| field.name()).collect();
+    let mut profiles: Vec<ColumnProfile> = table
+        .fields
+        .iter()
+        .map(|field| {
+            let path: Vec<String> = path.iter().cloned().chain([field.name.clone()]).collect();
+            ColumnProfile::new(field.type_(), path)
+        })
+        .collect();
+    let mut size = 0;
+    for file in files {
+        for row in file.rows(&columns)? {
+            for (profile, cell) in profiles.iter_mut().zip(row?) {
+                profile.push(cell)?;
+            }
+            size += 1;
+        }
+    }
+    let mut result = statistics::statistics::Struct::new();
+    result.name = "Struct".to_string();
+    result.size = size;
+    for (field, profile) in table.fields.iter().zip(profiles) {
+        result.fields.push(statistics::statistics::struct_::Field {
+            name: field.name.clone(),
+            statistics: MessageField::some(profile.statistics()),
+            ..Default::default()
+        });
+    }
+    let mut statistics = statistics::Statistics::new();
+    statistics.name = "Struct".to_string();
+    statistics.set_struct(result);
+    Ok(statistics)
+}
+
+/// The running summary of the values of a column
+struct ColumnProfile<'a> {
+    type_: &'a type_::Type,
+    path: Vec<String>,
+    size: i64,
+    counts: Counts<'a>,
+}
+
+/// The counts of the values of a column, by value
+enum Counts<'a> {
+    /// The profile of the non null values of an Optional column
+    Optional(Box<ColumnProfile<'a>>),
+    Boolean([usize; 2]),
+    /// Integer encoded values of Integer, Duration and temporal columns
+    Integer(BTreeMap<i64, usize>),
+    /// Floats by their bits
+    Float(HashMap<u64, usize>),
+    /// Names, with their values
+    Enum(BTreeMap<&'a str, (f64, usize)>),
+    Text {
+        counts: HashMap<String, usize>,
+        example: Option<String>,
+    },
+    None,
+}
+
+impl<'a> ColumnProfile<'a> {
+    fn new(type_: &'a type_::Type, path: Vec<String>) -> Self {
+        use type_::type_::Type as T;
+        let counts = match type_.type_.as_ref() {
+            Some(T::Optional(optional)) => {
+                Counts::Optional(Box::new(ColumnProfile::new(optional.type_(), path.clone())))
+            }
+            Some(T::Boolean(_)) => Counts::Boolean([0; 2]),
+            Some(T::Integer(_) | T::Duration(_) | T::Date(_) | T::Time(_) | T::Datetime(_)) => {
+                Counts::Integer(BTreeMap::new())
+            }
+            Some(T::Float(_)) => Counts::Float(HashMap::new()),
+            Some(T::Enum(_)) => Counts::Enum(BTreeMap::new()),
+            Some(T::Text(_)) => Counts::Text {
+                counts: HashMap::new(),
+                example: None,
+            },
+            _ => Counts::None,
+        };
+        ColumnProfile {
+            type_,
+            path,
+            size: 0,
+            counts,
+        }
+    }
+
+    /// Count a value of the column
+    fn push(&mut self, cell: Cell) -> Result<()> {
+        self.size += 1;
+        if let Counts::Optional(values) = &mut self.counts {
+            if !cell.is_null() {
+                values.push(cell)?;
+            }
+            return Ok(());
+        }
+        if matches!(cell, Cell::Null) {
+            return Err(Error::other(format!(
+                "`{}` is not Optional but holds nulls",
+                self.path.join(".")
+            )));
+        }
+        let error = || {
+            Error::parsing_error(format!(
+                "`{}` as {} in `{}`",
+                cell,
+                self.type_.name,
+                self.path.join(".")
+            ))
+        };
+        match &mut self.counts {
+            Counts::Boolean(counts) => counts[cell.boolean().ok_or_else(error)? as usize] += 1,
+            Counts::Integer(counts) => {
+                let value = integer_value(self.type_, &cell).ok_or_else(error)?;
+                *counts.entry(value).or_default() += 1;
+                if counts.len() >= 2 * MAX_COUNTS {
+                    *counts = compacted(mem::take(counts).into_iter().collect())
+                        .into_iter()
+                        .collect();
+                }
+            }
+            Counts::Float(counts) => {
+                let value = cell.float().ok_or_else(error)?;
+                *counts.entry(value.to_bits()).or_default() += 1;
+                if counts.len() >= 2 * MAX_COUNTS {
+                    let mut sorted: Vec<(u64, usize)> = mem::take(counts).into_iter().collect();
+                    sorted.sort_by(|(a, _), (b, _)| {
+                        f64::from_bits(*a).total_cmp(&f64::from_bits(*b))
+                    });
+                    *counts = compacted(sorted).into_iter().collect();
+                }
+            }
+            Counts::Enum(counts) => {
+                let Some(type_::type_::Type::Enum(e)) = self.type_.type_.as_ref() else {
+                    return Ok(());
+                };
+                let name_value = e
+                    .name_values
+                    .iter()
+                    .find(|nv| match &cell {
+                        Cell::Integer(i) => nv.value == *i,
+                        _ => nv.name == cell.text(),
+                    })
+                    .ok_or_else(error)?;
+                counts
+                    .entry(&name_value.name)
+                    .or_insert((name_value.value as f64, 0))
+                    .1 += 1;
+            }
+            Counts::Text { counts, example } => {
+                let text = cell.text();
+                example.get_or_insert_with(|| text.clone());
+                *counts.entry(text).or_default() += 1;
+                if counts.len() >= 2 * MAX_COUNTS {
+                    let mut frequent: Vec<(String, usize)> =
+                        mem::take(counts).into_iter().collect();
+                    frequent.sort_by(|(a, m), (b, n)| n.cmp(m).then(a.cmp(b)));
+                    frequent.truncate(MAX_COUNTS);
+                    *counts = frequent.into_iter().collect();
+                }
+            }
+            Counts::Optional(_) | Counts::None => {}
+        }
+        Ok(())
+    }
+
+    /// The statistics of the values of the column
+    fn statistics(self) -> statistics::Statistics {
+        use statistics::statistics::Statistics as S;
+        let size = self.size;
+        let total = size as usize;
+        let mut statistics = with_size(statistics_skeleton(self.type_), size);
+        let distribution = match self.counts {
+            Counts::Optional(values) => {
+                if let Some(S::Optional(o)) = statistics.statistics.as_mut() {
+                    o.statistics = MessageField::some(values.statistics());
+                }
+                None
+            }
+            Counts::Boolean(counts) => Some(boolean_distribution(counts)),
+            Counts::Integer(counts) => Some(integer_distribution(&counts, total)),
+            Counts::Float(counts) => Some(float_distribution(
+                counts
+                    .into_iter()
+                    .map(|(bits, count)| (f64::from_bits(bits), count))
+                    .collect(),
+                total,
+            )),
+            Counts::Enum(counts) => Some(enum_distribution(
+                counts
+                    .into_iter()
+                    .map(|(name, (value, count))| (name.to_string(), value, count)),
+                total,
+            )),
+            Counts::Text { counts, example } => {
+                if let Some(S::Text(s)) = statistics.statistics.as_mut() {
+                    s.example = example.unwrap_or_default();
+                }
+                let mut frequent: Vec<(String, usize)> = counts.into_iter().collect();
+                frequent.sort_by(|(a, m), (b, n)| n.cmp(m).then(a.cmp(b)));
+                frequent.truncate(MAX_POINTS);
+                Some(enum_distribution(
+                    frequent
+                        .into_iter()
+                        .enumerate()
+                        .map(|(rank, (text, count))| (text, rank as f64, count)),
+                    total,
+                ))
+            }
+            Counts::None => None,
+        };
+        if let Some(distribution) = distribution {
+            set_distribution(&mut statistics, distribution);
+        }
+        statistics
+    }
+}
+
+/// The counts of sorted values, with each pair of neighbouring values but the extreme ones
+/// merged into the lowest of the two
+fn compacted<K>(sorted: Vec<(K, usize)>) -> Vec<(K, usize)> {
+    let mut result = Vec::with_capacity(sorted.len() / 2 + 2);
+    let last = sorted.len().saturating_sub(1);
+    let mut values = sorted.into_iter().enumerate();
+    while let Some((index, (value, count))) = values.next() {
+        if index == 0 || index + 1 >= last {
+            result.push((value, count));
+        } else {
+            let (_, (_, next)) = values.next().unwrap();
+            result.push((value, count + next));
+        }
+    }
+    result
+}
+
+/// The integer encoding of a value of an Integer, Duration or temporal column
+pub(super) fn integer_value(type_: &type_::Type, cell: &Cell) -> Option<i64> {
+    match type_.type_.as_ref()? {
+        type_::type_::Type::Integer(_) | type_::type_::Type::Duration(_) => cell.integer(),
+        type_::type_::Type::Date(d) => {
+            let epoch = NaiveDate::from_ymd_opt(1970, 1, 1)?;
+            cell.date(&temporal_format::<NaiveDate>(d.format()))
+                .map(|d| (d - epoch).num_days())
+        }
+        type_::type_::Type::Time(t) => cell
+            .time(&temporal_format::<NaiveTime>(t.format()))
+            .and_then(|t| (t - NaiveTime::MIN).num_microseconds()),
+        type_::type_::Type::Datetime(d) => cell
+            .datetime(&temporal_format::<NaiveDateTime>(d.format()))
+            .map(|d| d.and_utc().timestamp_micros()),
+        _ => None,
+    }
+}
+
+/// Set the distribution of a statistics node
+pub(super) fn set_distribution(
+    statistics: &mut statistics::Statistics,
+    distribution: statistics::Distribution,
+) {
+    use statistics::statistics::Statistics as S;
+    let distribution = MessageField::some(distribution);
+    match statistics.statistics.as_mut() {
+        Some(S::Boolean(s)) => s.distribution = distribution,
+        Some(S::Integer(s)) => s.distribution = distribution,
+        Some(S::Enum(s)) => s.distribution = distribution,
+        Some(S::Float(s)) => s.distribution = distribution,
+        Some(S::Text(s)) => s.distribution = distribution,
+        Some(S::Datetime(s)) => s.distribution = distribution,
+        Some(S::Date(s)) => s.distribution = distribution,
+        Some(S::Time(s)) => s.distribution = distribution,
+        Some(S::Duration(s)) => s.distribution = distribution,
+        _ => {}
+    }
+}
+
+/// Equal width buckets between `min` and `max`
+pub(super) fn bucket_bounds(min: f64, max: f64) -> Vec<f64> {
+    (1..MAX_POINTS)
+        .map(|k| min + (max - min) * k as f64 / MAX_POINTS as f64)
+        .chain([max])
+        .collect()
+}
+
+/// The distribution of integer values with their count, out of `total` values
+fn integer_distribution(counts: &BTreeMap<i64, usize>, total: usize) -> statistics::Distribution {
+    let mut integer = statistics::distribution::Integer::new();
+    integer.min = counts.keys().next().cloned().unwrap_or_default();
+    integer.max = counts.keys().last().cloned().unwrap_or_default();
+    integer.points = counts
+        .iter()
+        .map(|(value, count)| statistics::distribution::integer::Point {
+            value: *value,
+            probability: *count as f64 / total as f64,
+            ..Default::default()
+        })
+        .collect();
+    let (min, max) = (integer.min as f64, integer.max as f64);
+    let mut distribution = statistics::Distribution::new();
+    distribution.set_integer(integer);
+    if counts.len() > MAX_POINTS {
+        // The bounds are increasing, rebucketing cannot fail
+        distribution = distribution.rebucket(&bucket_bounds(min, max)).unwrap();
+    }
+    distribution
+}
+
+/// The distribution of float values with their count, out of `total` values
+fn float_distribution(mut counts: Vec<(f64, usize)>, total: usize) -> statistics::Distribution {
+    counts.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+    let mut double = statistics::distribution::Double::new();
+    double.min = counts.first().map(|(value, _)| *value).unwrap_or_default();
+    double.max = counts.last().map(|(value, _)| *value).unwrap_or_default();
+    double.points = counts
+        .iter()
+        .map(|(value, count)| statistics::distribution::double::Point {
+            value: *value,
+            probability: *count as f64 / total as f64,
+            ..Default::default()
+        })
+        .collect();
+    let (min, max, distinct) = (double.min, double.max, double.points.len());
+    let mut distribution = statistics::Distribution::new();
+    distribution.set_double(double);
+    if distinct > MAX_POINTS {
+        // The bounds are increasing, rebucketing cannot fail
+        distribution = distribution.rebucket(&bucket_bounds(min, max)).unwrap();
+    }
+    distribution
+}
+
+/// The distribution of the counts of `false` and `true`
+fn boolean_distribution(counts: [usize; 2]) -> statistics::Distribution {
+    let total: usize = counts.iter().sum();
+    let mut boolean = statistics::distribution::Boolean::new();
+    for name in [false, true] {
+        let count = counts[name as usize];
+        if count > 0 {
+            boolean
+                .points
+                .push(statistics::distribution::boolean::Point {
+                    name,
+                    value: name as i64,
+                    probability: count as f64 / total as f64,
+                    ..Default::default()
+                });
+        }
+    }
+    let mut distribution = statistics::Distribution::new();
+    distribution.set_boolean(boolean);
+    distribution
+}
+
+/// The distribution of names with their value and count, out of `total` values
+fn enum_distribution(
+    counts: impl Iterator<Item = (String, f64, usize)>,
+    total: usize,
+) -> statistics::Distribution {
+    let mut points: Vec<_> = counts
+        .map(
+            |(name, value, count)| statistics::distribution::enum_::Point {
+                name,
+                value,
+                probability: count as f64 / total as f64,
+                ..Default::default()
+            },
+        )
+        .collect();
+    points.sort_by(|a, b| a.value.total_cmp(&b.value));
+    let mut enum_ = statistics::distribution::Enum::new();
+    enum_.points = points;
+    let mut distribution = statistics::Distribution::new();
+    distribution.set_enum(enum_);
+    distribution
+}
+
+/// The statistics with sizes only
+fn without_distributions(mut statistics: statistics::Statistics) -> statistics::Statistics {
+    use statistics::statistics::Statistics as S;
+    match statistics.statistics.as_mut() {
+        Some(S::Boolean(s)) => s.distribution = MessageField::none(),
+        Some(S::Integer(s)) => s.distribution = MessageField::none(),
+        Some(S::Enum(s)) => s.distribution = MessageField::none(),
+        Some(S::Float(s)) => s.distribution = MessageField::none(),
+        Some(S::Text(s)) => {
+            s.distribution = MessageField::none();
+            s.example.clear();
+        }
+        Some(S::Datetime(s)) => s.distribution = MessageField::none(),
+        Some(S::Date(s)) => s.distribution = MessageField::none(),
+        Some(S::Time(s)) => s.distribution = MessageField::none(),
+        Some(S::Duration(s)) => s.distribution = MessageField::none(),
+        Some(S::Struct(s)) => s.fields.iter_mut().for_each(|f| {
+            f.statistics = MessageField::some(without_distributions(
+                f.statistics.take().unwrap_or_default(),
+            ))
+        }),
+        Some(S::Union(s)) => s.fields.iter_mut().for_each(|f| {
+            f.statistics = MessageField::some(without_distributions(
+                f.statistics.take().unwrap_or_default(),
+            ))
+        }),
+        Some(S::Optional(s)) => {
+            s.statistics = MessageField::some(without_distributions(
+                s.statistics.take().unwrap_or_default(),
+            ))
+        }
+        _ => {}
+    }
+    statistics
+}
+
+/// A value read from a file
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum Cell {
+    Null,
+    Text(String),
+    Boolean(bool),
+    Integer(i64),
+    Float(f64),
+    Date(NaiveDate),
+    Datetime(NaiveDateTime),
+}
+
+impl Cell {
+    /// Nulls and, in text files, empty values
+    pub(super) fn is_null(&self) -> bool {
+        match self {
+            Cell::Null => true,
+            Cell::Text(t) => t.is_empty(),
+            _ => false,
+        }
+    }
+
+    pub(super) fn text(&self) -> String {
+        match self {
+            Cell::Text(t) => t.clone(),
+            _ => self.to_string(),
+        }
+    }
+
+    pub(super) fn boolean(&self) -> Option<bool> {
+        match self {
+            Cell::Boolean(b) => Some(*b),
+            Cell::Integer(0) => Some(false),
+            Cell::Integer(1) => Some(true),
+            Cell::Text(t) => match t.to_lowercase().as_str() {
+                "true" | "1" => Some(true),
+                "false" | "0" => Some(false),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    pub(super) fn integer(&self) -> Option<i64> {
+        match self {
+            Cell::Integer(i) => Some(*i),
+            Cell::Text(t) => t.trim().parse().ok(),
+            _ => None,
+        }
+    }
+
+    pub(super) fn float(&self) -> Option<f64> {
+        match self {
+            Cell::Integer(i) => Some(*i as f64),
+            Cell::Float(f) => Some(*f),
+            Cell::Text(t) => t.trim().parse().ok(),
+            _ => None,
+        }
+    }
+
+    fn date(&self, format: &str) -> Option<NaiveDate> {
+        match self {
+            Cell::Date(d) => Some(*d),
+            Cell::Datetime(d) => Some(d.date()),
+            Cell::Text(t) => NaiveDate::parse(t, format).ok(),
+            _ => None,
+        }
+    }
+
+    fn time(&self, format: &str) -> Option<NaiveTime> {
+        match self {
+            Cell::Datetime(d) => Some(d.time()),
+            Cell::Text(t) => NaiveTime::parse(t, format).ok(),
+            _ => None,
+        }
+    }
+
+    fn datetime(&self, format: &str) -> Option<NaiveDateTime> {
+        match self {
+            Cell::Date(d) => d.and_hms_opt(0, 0, 0),
+            Cell::Datetime(d) => Some(*d),
+            Cell::Text(t) => NaiveDateTime::parse(t, format).ok(),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Cell {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Cell::Null => write!(f, "null"),
+            Cell::Text(t) => write!(f, "{}", t),
+            Cell::Boolean(b) => write!(f, "{}", b),
+            Cell::Integer(i) => write!(f, "{}", i),
+            Cell::Float(x) => write!(f, "{}", x),
+            Cell::Date(d) => write!(f, "{}", d),
+            Cell::Datetime(d) => write!(f, "{}", d),
+        }
+    }
+}
+
+/// The index of each name of `names` in the columns of a file
+fn column_indices<'a>(
+    names: &[&str],
+    columns: impl Iterator<Item = &'a str> + Clone,
+    path: &Path,
+) -> Result<Vec<usize>> {
+    names
+        .iter()
+        .map(|name| {
+            columns
+                .clone()
+                .position(|c| c == *name)
+                .ok_or_else(|| Error::missing_key_error(format!("{} in {}", name, path.display())))
+        })
+        .collect()
+}
+
+fn csv_rows(path: &Path, columns: &[&str]) -> Result<Rows> {
+    let mut reader = csv::Reader::from_path(path)?;
+    let indices = column_indices(columns, reader.headers()?.iter(), path)?;
+    Ok(Box::new(reader.into_records().map(move |record| {
+        let record = record?;
+        Ok(indices
+            .iter()
+            .map(|i| Cell::Text(record.get(*i).unwrap_or_default().to_string()))
+            .collect())
+    })))
+}
+
+fn parquet_reader(path: &Path) -> Result<parquet::file::reader::SerializedFileReader<fs::File>> {
+    Ok(parquet::file::reader::SerializedFileReader::new(
+        fs::File::open(path)?,
+    )?)
+}
+
+fn parquet_columns(path: &Path) -> Result<Vec<String>> {
+    use parquet::file::reader::FileReader;
+    Ok(parquet_reader(path)?
+        .metadata()
+        .file_metadata()
+        .schema()
+        .get_fields()
+        .iter()
+        .map(|f| f.name().to_string())
+        .collect())
+}
+
+fn parquet_rows(path: &Path, columns: &[&str]) -> Result<Rows> {
+    use parquet::record::Field;
+    let file_columns = parquet_columns(path)?;
+    let indices = column_indices(columns, file_columns.iter().map(String::as_str), path)?;
+    Ok(Box::new(parquet_reader(path)?.into_iter().map(
+        move |row| {
+            let row = row?;
+            let fields: Vec<&Field> = row.get_column_iter().map(|(_, field)| field).collect();
+            Ok(indices
+                .iter()
+                .map(|i| match fields[*i] {
+                    Field::Null => Cell::Null,
+                    Field::Bool(b) => Cell::Boolean(*b),
+                    Field::Byte(i) => Cell::Integer(*i as i64),
+                    Field::Short(i) => Cell::Integer(*i as i64),
+                    Field::Int(i) => Cell::Integer(*i as i64),
+                    Field::Long(i) => Cell::Integer(*i),
+                    Field::UByte(i) => Cell::Integer(*i as i64),
+                    Field::UShort(i) => Cell::Integer(*i as i64),
+                    Field::UInt(i) => Cell::Integer(*i as i64),
+                    Field::ULong(i) => i64::try_from(*i)
+                        .map(Cell::Integer)
+                        .unwrap_or(Cell::Float(*i as f64)),
+                    Field::Float16(x) => Cell::Float(f32::from(*x) as f64),
+                    Field::Float(x) => Cell::Float(*x as f64),
+                    Field::Double(x) => Cell::Float(*x),
+                    Field::Str(s) => Cell::Text(s.clone()),
+                    Field::Date(days) => NaiveDate::from_ymd_opt(1970, 1, 1)
+                        .and_then(|epoch| {
+                            epoch.checked_add_signed(chrono::Duration::days(*days as i64))
+                        })
+                        .map_or(Cell::Null, Cell::Date),
+                    Field::TimestampMillis(ms) => DateTime::from_timestamp_millis(*ms)
+                        .map_or(Cell::Null, |d| Cell::Datetime(d.naive_utc())),
+                    Field::TimestampMicros(us) => DateTime::from_timestamp_micros(*us)
+                        .map_or(Cell::Null, |d| Cell::Datetime(d.naive_utc())),
+                    field => Cell::Text(field.to_string()),
+                })
+                .collect())
+        },
+    )))
+}
+
+/// The local path of a `file://` uri or a plain path
+fn local_path(uri: &str) -> Result<PathBuf> {
+    match uri.split_once("://") {
+        None => Ok(PathBuf::from(uri)),
+        Some(("file", path)) => Ok(PathBuf::from(path)),
+        Some((scheme, _)) => Err(Error::other(format!(
+            "Only local files can be profiled, got a `{}` uri",
+            scheme
+        ))),
+    }
+}
+
+/// The paths matching a pattern with `*` and `?` wildcards in its file name
+fn matching_paths(uri_pattern: &str) -> Result<Vec<PathBuf>> {
+    let pattern = local_path(uri_pattern)?;
+    let directory = match pattern.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    let name_pattern = pattern
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let mut paths = vec![];
+    for entry in fs::read_dir(&directory)? {
+        let entry = entry?;
+        if entry.file_type()?.is_file()
+            && wildcard_match(
+                name_pattern.as_bytes(),
+                entry.file_name().to_string_lossy().as_bytes(),
+            )
+        {
+            paths.push(entry.path());
+        }
+    }
+    if paths.is_empty() {
+        return Err(Error::other(format!("No file matches `{}`", uri_pattern)));
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+fn wildcard_match(pattern: &[u8], name: &[u8]) -> bool {
+    match (pattern.split_first(), name.split_first()) {
+        (None, None) => true,
+        (Some((b'*', p)), _) => {
+            wildcard_match(p, name) || (!name.is_empty() && wildcard_match(pattern, &name[1..]))
+        }
+        (Some((b'?', p)), Some((_, n))) => wildcard_match(p, n),
+        (Some((a, p)), Some((b, n))) => a == b && wildcard_match(p, n),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protobuf::parse_from_str;
+    use anyhow::Result;
+    use qrlew::relation::Variant as _;
+    use std::sync::Arc;
+    use tempfile::{tempdir, TempDir};
+
+    const SCHEMA: &str = r#"
+    {
+        "name": "shop",
+        "type": {"name": "Union", "union": {"fields": [
+            {"name": "users", "type": {"name": "Struct", "struct": {"fields": [
+                {"name": "id", "type": {"name": "Integer", "integer": {"min": "0", "max": "1000"}}},
+                {"name": "name", "type": {"name": "Text", "text": {"encoding": "UTF-8"}}},
+                {"name": "score", "type": {"name": "Optional", "optional": {"type": {"name": "Float", "float": {"min": 0, "max": 10}}}}},
+                {"name": "active", "type": {"name": "Boolean", "boolean": {}}},
+                {"name": "joined", "type": {"name": "Date", "date": {"format": "%Y-%m-%d"}}}
+            ]}}},
+            {"name": "orders", "type": {"name": "Struct", "struct": {"fields": [
+                {"name": "user_id", "type": {"name": "Integer", "integer": {"min": "0", "max": "1000"}}},
+                {"name": "sent", "type": {"name": "Datetime", "datetime": {"format": "%Y-%m-%d %H:%M:%S"}}}
+            ]}}}
+        ]}}
+    }
+    "#;
+
+    fn temp_file(dir: &TempDir, name: &str, content: &str) -> PathBuf {
+        let path = dir.path().join(name);
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    fn dataset() -> Dataset {
+        Dataset::new(
+            parse_from_str("{}").unwrap(),
+            parse_from_str(SCHEMA).unwrap(),
+            None,
+        )
+    }
+
+    #[test]
+    fn test_profile_csv() -> Result<()> {
+        let dir = tempdir()?;
+        let users = temp_file(
+            &dir,
+            "users.csv",
+            "name,id,score,active,joined\nAda,1,3.5,true,2020-01-01\nBob,2,,false,2020-01-03\nAda,3,7,true,2020-01-03\n",
+        );
+        let orders = temp_file(
+            &dir,
+            "orders.csv",
+            "user_id,sent\n1,2024-01-01 10:00:00\n1,2024-01-02 10:00:00\n",
+        );
+        let dataset = dataset();
+        let (size, marginals) = dataset.profile_files([
+            (["users"], DataFile::Csv(users.clone())),
+            (["orders"], DataFile::Csv(orders.clone())),
+        ])?;
+        println!("{}", crate::protobuf::print_to_string(&marginals)?);
+        let statistics = marginals.statistics.union();
+        assert_eq!(statistics.size, 5);
+        let users_statistics = statistics.fields[0].statistics.struct_();
+        assert_eq!(users_statistics.size, 3);
+        let id = users_statistics.fields[0].statistics.integer();
+        assert_eq!(id.distribution.mean(), Some(2.));
+        assert_eq!(
+            (id.distribution.min(), id.distribution.max()),
+            (Some(1.), Some(3.))
+        );
+        let name = users_statistics.fields[1].statistics.text();
+        assert_eq!(name.example, "Ada");
+        assert_eq!(name.distribution.cdf(0.), 2. / 3.);
+        let score = users_statistics.fields[2].statistics.optional();
+        assert_eq!(score.size, 3);
+        assert_eq!(score.statistics.float().size, 2);
+        assert_eq!(score.statistics.float().distribution.mean(), Some(5.25));
+        let active = users_statistics.fields[3].statistics.boolean();
+        assert_eq!(active.distribution.mean(), Some(2. / 3.));
+        let joined = users_statistics.fields[4].statistics.date();
+        assert_eq!(joined.distribution.min(), Some(18262.));
+        // The Size has the same sizes but no distribution
+        let size_statistics = size.statistics.union().fields[0].statistics.struct_();
+        assert_eq!(size_statistics.fields[0].statistics.integer().size, 3);
+        assert!(size_statistics.fields[0]
+            .statistics
+            .integer()
+            .distribution
+            .is_none());
+        // The relations are sized by the profile
+        let dataset = Dataset::new(
+            dataset.dataset().clone(),
+            dataset.schema().clone(),
+            Some(size),
+        );
+        let relations = dataset.relations();
+        let users_relation: &Arc<_> = relations.get(&["users".to_string()]).unwrap();
+        assert_eq!(users_relation.size().max(), Some(&3));
+        // A missing table or an invalid value is an error
+        assert!(dataset
+            .profile_files([(["users"], DataFile::Csv(users.clone()))])
+            .is_err());
+        let invalid = temp_file(
+            &dir,
+            "invalid.csv",
+            "name,id,score,active,joined\nAda,one,3.5,true,2020-01-01\n",
+        );
+        assert!(dataset
+            .profile_files([
+                (["users"], DataFile::Csv(invalid)),
+                (["orders"], DataFile::Csv(orders)),
+            ])
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_profile_spec() -> Result<()> {
+        let dir = tempdir()?;
+        for (i, ids) in ["1\n2\n", "3\n"].iter().enumerate() {
+            temp_file(&dir, &format!("part_{}.csv", i), &format!("id\n{}", ids));
+        }
+        let pattern = dir.path().join("part_*.csv");
+        let dataset = Dataset::new(
+            parse_from_str(&format!(
+                r#"{{"name": "parts", "spec": {{"files": {{"format": "csv", "uri_pattern": "file://{}"}}}}}}"#,
+                pattern.display()
+            ))?,
+            parse_from_str(
+                r#"{"name": "parts", "type": {"name": "Struct", "struct": {"fields": [
+                    {"name": "id", "type": {"name": "Integer", "integer": {"min": "0", "max": "1000"}}}
+                ]}}}"#,
+            )?,
+            None,
+        );
+        let (size, marginals) = dataset.profile()?;
+        assert_eq!(size.statistics.struct_().size, 3);
+        assert_eq!(
+            marginals.statistics.struct_().fields[0]
+                .statistics
+                .integer()
+                .distribution
+                .max(),
+            Some(3.)
+        );
+        assert!(DataFile::new("csv", "s3://bucket/file.csv").is_err());
+        assert!(wildcard_match(b"a*b?.csv", b"axyzbc.csv"));
+        assert!(!wildcard_match(b"a*b?.csv", b"axyzb.csv"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_profile_bounded_counts() -> Result<()> {
+        let dir = tempdir()?;
+        let rows = 3 * MAX_COUNTS;
+        let mut content = "id,score,name\n".to_string();
+        for i in 0..rows {
+            let name = if i % 2 == 0 {
+                "Ada".to_string()
+            } else {
+                format!("n{}", i)
+            };
+            content.push_str(&format!("{},{}.5,{}\n", i, i, name));
+        }
+        let file = temp_file(&dir, "values.csv", &content);
+        let dataset = Dataset::new(
+            parse_from_str(&format!(
+                r#"{{"name": "values", "spec": {{"files": {{"format": "csv", "uri_pattern": "file://{}"}}}}}}"#,
+                file.display()
+            ))?,
+            parse_from_str(
+                r#"{"name": "values", "type": {"name": "Struct", "struct": {"fields": [
+                    {"name": "id", "type": {"name": "Integer", "integer": {"min": "0", "max": "100000"}}},
+                    {"name": "score", "type": {"name": "Float", "float": {"min": 0, "max": 100000}}},
+                    {"name": "name", "type": {"name": "Text", "text": {"encoding": "UTF-8"}}}
+                ]}}}"#,
+            )?,
+            None,
+        );
+        let (_, marginals) = dataset.profile()?;
+        let statistics = marginals.statistics.struct_();
+        assert_eq!(statistics.size, rows as i64);
+        // The compacted counts keep the extreme values
+        let id = statistics.fields[0].statistics.integer();
+        assert_eq!(id.size, rows as i64);
+        let id = id.distribution.integer();
+        assert_eq!((id.min, id.max), (0, rows as i64 - 1));
+        let score = statistics.fields[1]
+            .statistics
+            .float()
+            .distribution
+            .double();
+        assert_eq!((score.min, score.max), (0.5, rows as f64 - 0.5));
+        // The most frequent text survives the dropped ones
+        let name = statistics.fields[2].statistics.text();
+        assert_eq!(name.size, rows as i64);
+        let ada = &name.distribution.enum_().points[0];
+        assert_eq!((ada.name.as_str(), ada.probability), ("Ada", 0.5));
+        Ok(())
+    }
+
+    #[test]
+    fn test_profile_parquet() -> Result<()> {
+        use parquet::{
+            data_type::{ByteArray, ByteArrayType, Int64Type},
+            file::{properties::WriterProperties, writer::SerializedFileWriter},
+            schema::parser::parse_message_type,
+        };
+        let dir = tempdir()?;
+        let path = dir.path().join("users.parquet");
+        let schema = Arc::new(parse_message_type(
+            "message users { REQUIRED INT64 id; REQUIRED BYTE_ARRAY name (UTF8); }",
+        )?);
+        let mut writer = SerializedFileWriter::new(
+            fs::File::create(&path)?,
+            schema,
+            Arc::new(WriterProperties::builder().build()),
+        )?;
+        let mut row_group = writer.next_row_group()?;
+        let mut column = row_group.next_column()?.unwrap();
+        column
+            .typed::<Int64Type>()
+            .write_batch(&(0..200).collect::<Vec<_>>(), None, None)?;
+        column.close()?;
+        let mut column = row_group.next_column()?.unwrap();
+        column.typed::<ByteArrayType>().write_batch(
+            &(0..200)
+                .map(|i| ByteArray::from(format!("user {}", i % 2).as_str()))
+                .collect::<Vec<_>>(),
+            None,
+            None,
+        )?;
+        column.close()?;
+        row_group.close()?;
+        writer.close()?;
+        let dataset = Dataset::new(
+            parse_from_str("{}")?,
+            parse_from_str(
+                r#"{"name": "users", "type": {"name": "Struct", "struct": {"fields": [
+                    {"name": "name", "type": {"name": "Text", "text": {"encoding": "UTF-8"}}},
+                    {"name": "id", "type": {"name": "Integer", "integer": {"min": "0", "max": "1000"}}}
+                ]}}}"#,
+            )?,
+            None,
+        );
+        let (size, marginals) =
+            dataset.profile_files([(Identifier::empty(), DataFile::Parquet(path))])?;
+        assert_eq!(size.statistics.struct_().size, 200);
+        let fields = &marginals.statistics.struct_().fields;
+        assert_eq!(
+            fields[0]
+                .statistics
+                .text()
+                .distribution
+                .enum_()
+                .points
+                .len(),
+            2
+        );
+        // Too many distinct values are bucketed
+        let id = &fields[1].statistics.integer().distribution;
+        assert_eq!(id.integer().points.len(), MAX_POINTS);
+        assert!((id.total_probability() - 1.).abs() < 1e-9);
+        assert!((id.cdf(99.) - 0.5).abs() < 1e-9);
+        Ok(())
+    }
+}
@@ -0,0 +1,317 @@
+//! Relations of the source database tables of a dataset.
+//!
+//! A dataset with an `Sql` spec was extracted from the tables of a database. Its relations can
+//! be mapped back to these tables, so that queries compiled from them run directly against
+//! the database, in the dialect given by the scheme of the spec `uri`.
+
+use super::{Dataset, Error, Result};
+use crate::protobuf::transform::transform::SQLDialect;
+use qrlew::{
+    ast,
+    dialect_translation::{
+        bigquery::BigQueryTranslator, hive::HiveTranslator, mssql::MsSqlTranslator,
+        mysql::MySqlTranslator, postgresql::PostgreSqlTranslator,
+        redshiftsql::RedshiftSqlTranslator, sqlite::SQLiteTranslator, RelationWithTranslator,
+    },
+    expr::identifier::Identifier,
+    hierarchy::Hierarchy,
+    relation::{Relation, Table, Variant as _},
+};
+use std::sync::Arc;
+
+impl Dataset {
+    /// The SQL dialect of the source database, from the scheme of the `uri` of the `Sql` spec,
+    /// as `postgresql://`, `mssql+pyodbc://` or `bigquery://`
+    pub fn sql_dialect(&self) -> Option<SQLDialect> {
+        let spec = self.dataset.spec();
+        if !spec.has_sql() {
+            return None;
+        }
+        let (scheme, _) = spec.sql().uri().split_once("://")?;
+        // SQLAlchemy URIs may name a driver after the dialect
+        let dialect = scheme.split('+').next()?;
+        match dialect.to_lowercase().as_str() {
+            "postgresql" | "postgres" => Some(SQLDialect::POSTGRES),
+            "mssql" | "sqlserver" => Some(SQLDialect::SQL_SERVER),
+            "bigquery" => Some(SQLDialect::BIG_QUERY),
+            "mysql" | "mariadb" => Some(SQLDialect::MY_SQL),
+            "sqlite" => Some(SQLDialect::SQLLITE),
+            "redshift" => Some(SQLDialect::REDSHIFT),
+            "hive" => Some(SQLDialect::HIVE),
+            "oracle" => Some(SQLDialect::ORACLE),
+            _ => None,
+        }
+    }
+
+    /// The relations of the tables of the source database, keyed and rendered with the
+    /// `schema.table` paths of the `Sql` spec, as matched by `Dataset::sql_table_matches`.
+    /// Every relation must match a single spec table, under a source path of its own.
+    pub fn source_relations(&self) -> Result<Hierarchy<Arc<Relation>>> {
+        let spec = self.dataset.spec();
+        if !spec.has_sql() {
+            return Err(Error::other(format!(
+                "Dataset `{}` has no Sql spec",
+                self.dataset.name()
+            )));
+        }
+        let matches = self.sql_table_matches();
+        let relations = self
+            .relations()
+            .into_iter()
+            .map(|(path, relation)| {
+                let mut source_paths = matches
+                    .iter()
+                    .filter(|(matched, _)| matched == &path)
+                    .map(|(_, source_path)| source_path);
+                let source_path = match (source_paths.next(), source_paths.next()) {
+                    (Some(source_path), None) => source_path.clone(),
+                    (None, _) => {
+                        return Err(Error::other(format!(
+                            "Relation `{}` matches no table of the Sql spec",
+                            path.join(".")
+                        )))
+                    }
+                    (Some(first), Some(second)) => {
+                        return Err(Error::other(format!(
+                        "Relation `{}` matches several tables of the Sql spec, as `{}` and `{}`",
+                        path.join("."),
+                        first.join("."),
+                        second.join(".")
+                    )))
+                    }
+                };
+                Ok((path, source_path, relation))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        for (i, (path, source_path, _)) in relations.iter().enumerate() {
+            if let Some((other, _, _)) = relations[i + 1..]
+                .iter()
+                .find(|(_, other_source_path, _)| other_source_path == source_path)
+            {
+                return Err(Error::other(format!(
+                    "Relations `{}` and `{}` both match the table `{}` of the Sql spec",
+                    path.join("."),
+                    other.join("."),
+                    source_path.join(".")
+                )));
+            }
+        }
+        relations
+            .into_iter()
+            .map(|(path, source_path, relation)| {
+                let Relation::Table(table) = relation.as_ref() else {
+                    return Err(Error::other(format!("`{}` is not a table", path.join("."))));
+                };
+                let source_table = Table::new(
+                    table.name().to_string(),
+                    Identifier::from(source_path.clone()),
+                    table.schema().clone(),
+                    table.size().clone(),
+                );
+                Ok((source_path, Arc::new(Relation::from(source_table))))
+            })
+            .collect()
+    }
+
+    /// The relations matching the tables of the `Sql` spec, as (full path, source path) pairs.
+    /// A spec table `schema.table` matches the relations whose path ends with `schema.table`,
+    /// or, if there are none, with `table`. A spec table with an empty table name matches the
+    /// tables directly in its schema. The source path of a match is `schema.table`, with the
+    /// last label of the path of the relation as table.
+    pub(super) fn sql_table_matches(&self) -> Vec<(Vec<String>, Vec<String>)> {
+        let spec = self.dataset.spec();
+        if !spec.has_sql() {
+            return vec![];
+        }
+        let paths: Vec<Vec<String>> = self.relations().keys().cloned().collect();
+        // The paths without the schema name ending with `suffix`
+        let ending = |suffix: &[&str]| -> Vec<&Vec<String>> {
+            let suffix: Vec<String> = suffix.iter().map(|label| label.to_string()).collect();
+            paths
+                .iter()
+                .filter(|path| path[1..].ends_with(&suffix))
+                .collect()
+        };
+        let mut matches = vec![];
+        for table in &spec.sql().tables {
+            let (schema, name) = (table.schema(), table.table());
+            let matching = match (schema.is_empty(), name.is_empty()) {
+                (true, true) => vec![],
+                (false, true) => paths
+                    .iter()
+                    .filter(|path| path.len() > 2 && path[path.len() - 2] == schema)
+                    .collect(),
+                (true, false) => ending(&[name]),
+                (false, false) => {
+                    let matching = ending(&[schema, name]);
+                    if matching.is_empty() {
+                        ending(&[name])
+                    } else {
+                        matching
+                    }
+                }
+            };
+            for path in matching {
+                let source_path: Vec<String> = [schema, path[path.len() - 1].as_str()]
+                    .into_iter()
+                    .filter(|label| !label.is_empty())
+                    .map(str::to_string)
+                    .collect();
+                let pair = (path.clone(), source_path);
+                if !matches.contains(&pair) {
+                    matches.push(pair);
+                }
+            }
+        }
+        matches
+    }
+
+    /// Render `relation` as a query in the dialect of the source database,
+    /// failing on dialects without a translator, such as Oracle
+    pub fn source_query(&self, relation: &Relation) -> Result<ast::Query> {
+        let dialect = self.sql_dialect().ok_or_else(|| {
+            Error::other(format!(
+                "Cannot infer the SQL dialect of dataset `{}` from its spec",
+                self.dataset.name()
+            ))
+        })?;
+        Ok(match dialect {
+            SQLDialect::POSTGRES => RelationWithTranslator(relation, PostgreSqlTranslator).into(),
+            SQLDialect::NONE | SQLDialect::ORACLE => {
+                return Err(Error::other(format!(
+                    "Cannot render queries in the {:?} dialect of dataset `{}`",
+                    dialect,
+                    self.dataset.name()
+                )))
+            }
+            SQLDialect::SQL_SERVER => RelationWithTranslator(relation, MsSqlTranslator).into(),
+            SQLDialect::BIG_QUERY => RelationWithTranslator(relation, BigQueryTranslator).into(),
+            SQLDialect::MY_SQL => RelationWithTranslator(relation, MySqlTranslator).into(),
+            SQLDialect::SQLLITE => RelationWithTranslator(relation, SQLiteTranslator).into(),
+            SQLDialect::REDSHIFT => RelationWithTranslator(relation, RedshiftSqlTranslator).into(),
+            SQLDialect::HIVE => RelationWithTranslator(relation, HiveTranslator).into(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protobuf::parse_from_str;
+    use anyhow::Result;
+
+    const SCHEMA: &str = r#"
+    {
+        "name": "shop",
+        "type": {"name": "Union", "union": {"fields": [
+            {"name": "public", "type": {"name": "Union", "union": {"fields": [
+                {"name": "users", "type": {"name": "Struct", "struct": {"fields": [
+                    {"name": "id", "type": {"name": "Integer", "integer": {"min": "0", "max": "10"}}}
+                ]}}},
+                {"name": "orders", "type": {"name": "Struct", "struct": {"fields": [
+                    {"name": "user_id", "type": {"name": "Integer", "integer": {"min": "0", "max": "10"}}}
+                ]}}}
+            ]}}},
+            {"name": "extract", "type": {"name": "Union", "union": {"fields": [
+                {"name": "extract", "type": {"name": "Union", "union": {"fields": [
+                    {"name": "beacon", "type": {"name": "Struct", "struct": {"fields": [
+                        {"name": "at", "type": {"name": "Integer", "integer": {"min": "0", "max": "10"}}}
+                    ]}}}
+                ]}}}
+            ]}}}
+        ]}}
+    }
+    "#;
+
+    fn dataset(uri: &str) -> Dataset {
+        Dataset::new(
+            parse_from_str(&format!(
+                r#"{{"name": "shop", "spec": {{"sql": {{"uri": "{}", "tables": [
+                    {{"schema": "public", "table": ""}},
+                    {{"schema": "analytics", "table": "beacon"}}
+                ]}}}}}}"#,
+                uri
+            ))
+            .unwrap(),
+            parse_from_str(SCHEMA).unwrap(),
+            None,
+        )
+    }
+
+    #[test]
+    fn test_sql_dialect() {
+        assert_eq!(
+            dataset("postgresql://localhost/shop").sql_dialect(),
+            Some(SQLDialect::POSTGRES)
+        );
+        assert_eq!(
+            dataset("mssql+pyodbc://localhost/shop").sql_dialect(),
+            Some(SQLDialect::SQL_SERVER)
+        );
+        assert_eq!(
+            dataset("bigquery://project/shop").sql_dialect(),
+            Some(SQLDialect::BIG_QUERY)
+        );
+        assert_eq!(dataset("s3://bucket/shop").sql_dialect(), None);
+    }
+
+    #[test]
+    fn test_source_relations() -> Result<()> {
+        let oracle = dataset("oracle://localhost/shop");
+        let dataset = dataset("mssql://localhost/shop");
+        let relations = dataset.source_relations()?;
+        let paths: Vec<String> = relations.keys().map(|path| path.join(".")).collect();
+        assert_eq!(
+            paths,
+            vec!["analytics.beacon", "public.orders", "public.users"]
+        );
+        let users = relations
+            .get(&["public".to_string(), "users".to_string()])
+            .unwrap();
+        let query = dataset.source_query(users)?.to_string();
+        println!("{}", query);
+        assert!(query.contains(r#"FROM "public"."users""#));
+        // No fallback to another dialect
+        assert!(oracle.source_query(users).is_err());
+        let dataset = Dataset::new(
+            parse_from_str(
+                r#"{"name": "shop", "spec": {"sql": {"tables": [{"schema": "public"}]}}}"#,
+            )?,
+            parse_from_str(SCHEMA)?,
+            None,
+        );
+        assert!(dataset.source_relations().is_err());
+        assert!(dataset.source_query(users).is_err());
+        // A relation matching two spec tables
+        let dataset = Dataset::new(
+            parse_from_str(
+                r#"{"name": "shop", "spec": {"sql": {"tables": [
+                    {"schema": "public", "table": ""},
+                    {"table": "users"},
+                    {"schema": "analytics", "table": "beacon"}
+                ]}}}"#,
+            )?,
+            parse_from_str(SCHEMA)?,
+            None,
+        );
+        let error = dataset.source_relations().unwrap_err().to_string();
+        println!("{}", error);
+        assert!(error.contains("matches several tables of the Sql spec"));
+        // Two relations matching the same spec table
+        let dataset = Dataset::new(
+            parse_from_str(
+                r#"{"name": "shop", "spec": {"sql": {"tables": [
+                    {"schema": "public", "table": "orders"},
+                    {"table": "beacon"}
+                ]}}}"#,
+            )?,
+            parse_from_str(SCHEMA)?,
+            None,
+        )
+        .rename_table(["public", "users"], "beacon")?;
+        let error = dataset.source_relations().unwrap_err().to_string();
+        println!("{}", error);
+        assert!(error.contains("both match the table `beacon`"));
+        Ok(())
+    }
+}
@@ -0,0 +1,975 @@
+//! Structural edits of a Dataset: tables and columns can be added, dropped or renamed.
+//!
+//! Paths are relative to the data part of the schema (the SARUS_DATA wrapper may be omitted).
+//! The Size statistics, the key properties, the public fields and the protected path
+//! of the schema follow the edits.
+
+use super::{
+    unresolved_path_error, Dataset, Error, Result, SchemaEdit, ALIASES, FOREIGN_KEYS,
+    PUBLIC_FIELDS, SARUS_DATA,
+};
+use crate::protobuf::{path, schema, statistics, type_};
+use protobuf::MessageField;
+use qrlew::{builder::With, expr::identifier::Identifier};
+use std::{collections::HashMap, convert::TryInto, mem};
+
+impl Dataset {
+    /// Add the table `table` at `path`, in the Union of tables holding it.
+    /// A schema with a single table is turned into a Union of tables,
+    /// the existing table being named after the schema.
+    /// The Size statistics of the table are set if `size` is given.
+    pub fn add_table<P>(&self, path: P, table: type_::Type, size: Option<i64>) -> Result<Self>
+    where
+        P: TryInto<Identifier>,
+        Error: From<P::Error>,
+    {
+        let path = self.data_path(path.try_into()?);
+        let Some((name, parent_path)) = path.split_last() else {
+            return Err(Error::other("Cannot add a table at the schema root"));
+        };
+        if !matches!(table.type_, Some(type_::type_::Type::Struct(_))) {
+            return Err(Error::other(format!(
+                "Cannot add `{}`: a table must be a Struct, got {}",
+                name,
+                table.name()
+            )));
+        }
+        let schema_name = self.schema.name().to_string();
+        let mut schema = self.schema.clone();
+        let mut size_ = self.size.clone();
+        let data = data_type_mut(&mut schema);
+        if parent_path.is_empty() && matches!(data.type_, Some(type_::type_::Type::Struct(_))) {
+            if *name == schema_name {
+                return Err(Error::other(format!("Table `{}` already exists", name)));
+            }
+            let existing = mem::take(data);
+            *data = union_type(vec![(schema_name.clone(), existing), (name.clone(), table)]);
+            schema.prefix_paths(&schema_name);
+            if let Some(statistics) = size_.as_mut().map(|s| s.mut_statistics()) {
+                let existing = mem::take(statistics);
+                let mut fields = vec![(schema_name, existing)];
+                if let Some(size) = size {
+                    fields.push((name.clone(), table_statistics(size)));
+                }
+                *statistics = union_statistics(fields);
+            }
+        } else {
+            let visited_path = Identifier::from(vec![SARUS_DATA.to_string()]);
+            match type_at_mut(data, parent_path, &visited_path)?
+                .type_
+                .as_mut()
+            {
+                Some(type_::type_::Type::Union(u)) => {
+                    if u.fields.iter().any(|f| f.name() == name) {
+                        return Err(Error::other(format!("Table `{}` already exists", name)));
+                    }
+                    let mut field = type_::type_::union::Field::new();
+                    field.set_name(name.clone());
+                    field.set_type(table);
+                    u.fields.push(field);
+                }
+                _ => {
+                    return Err(Error::other(format!(
+                        "Cannot add table `{}`: `{}` is not a Union of tables",
+                        name,
+                        parent_path.join(".")
+                    )))
+                }
+            }
+            let statistics = size_
+                .as_mut()
+                .and_then(|s| statistics_at_mut(s.mut_statistics(), parent_path));
+            if let (Some(statistics), Some(size)) = (statistics, size) {
+                if let Some(statistics::statistics::Statistics::Union(u)) =
+                    statistics.statistics.as_mut()
+                {
+                    let mut field = statistics::statistics::union::Field::new();
+                    field.set_name(name.clone());
+                    field.set_statistics(table_statistics(size));
+                    u.fields.push(field);
+                    // The Unions holding the new table, from the innermost, sum their fields
+                    let root = size_.as_mut().unwrap().mut_statistics();
+                    for end in (0..=parent_path.len()).rev() {
+                        if let Some(statistics::statistics::Statistics::Union(u)) =
+                            statistics_at_mut(root, &parent_path[..end])
+                                .and_then(|s| s.statistics.as_mut())
+                        {
+                            u.size = u.fields.iter().map(|f| size_of(f.statistics())).sum();
+                        }
+                    }
+                }
+            }
+        }
+        Ok(Dataset::new(self.dataset.clone(), schema, size_))
+    }
+
+    /// Drop the table at `path`
+    pub fn drop_table<P>(&self, path: P) -> Result<Self>
+    where
+        P: TryInto<Identifier>,
+        Error: From<P::Error>,
+    {
+        let path = self.data_path(path.try_into()?);
+        self.check_table(&path)?;
+        self.apply_edits(&[SchemaEdit::Drop {
+            path: self.schema_path(&path),
+        }])
+    }
+
+    /// Rename the table at `path`
+    pub fn rename_table<P>(&self, path: P, name: &str) -> Result<Self>
+    where
+        P: TryInto<Identifier>,
+        Error: From<P::Error>,
+    {
+        let path = self.data_path(path.try_into()?);
+        self.check_table(&path)?;
+        self.apply_edits(&[SchemaEdit::Rename {
+            path: self.schema_path(&path),
+            name: name.to_string(),
+        }])
+    }
+
+    /// Add the column `column` at `path`, the last segment being the column name.
+    /// If the Size statistics of the table list its columns, a column with the size
+    /// of the table is added.
+    pub fn add_column<P>(&self, path: P, column: type_::Type) -> Result<Self>
+    where
+        P: TryInto<Identifier>,
+        Error: From<P::Error>,
+    {
+        let path = self.data_path(path.try_into()?);
+        let Some((name, table_path)) = path.split_last() else {
+            return Err(Error::other("Cannot add a column at the schema root"));
+        };
+        let mut schema = self.schema.clone();
+        let mut size = self.size.clone();
+        let visited_path = Identifier::from(vec![SARUS_DATA.to_string()]);
+        let table = type_at_mut(data_type_mut(&mut schema), table_path, &visited_path)?;
+        let column_statistics = statistics_skeleton(&column);
+        match table.type_.as_mut() {
+            Some(type_::type_::Type::Struct(s)) => {
+                if s.fields.iter().any(|f| f.name() == name) {
+                    return Err(Error::other(format!(
+                        "Column `{}` already exists",
+                        path.join(".")
+                    )));
+                }
+                let mut field = type_::type_::struct_::Field::new();
+                field.set_name(name.clone());
+                field.set_type(column);
+                s.fields.push(field);
+            }
+            _ => {
+                return Err(Error::other(format!(
+                    "Cannot add column `{}`: `{}` is not a table",
+                    name,
+                    table_path.join(".")
+                )))
+            }
+        }
+        let statistics = size
+            .as_mut()
+            .and_then(|s| statistics_at_mut(s.mut_statistics(), table_path));
+        if let Some(statistics::statistics::Statistics::Struct(s)) =
+            statistics.and_then(|s| s.statistics.as_mut())
+        {
+            if !s.fields.is_empty() {
+                let mut field = statistics::statistics::struct_::Field::new();
+                field.set_name(name.clone());
+                field.set_statistics(with_size(column_statistics, s.size));
+                s.fields.push(field);
+            }
+        }
+        Ok(Dataset::new(self.dataset.clone(), schema, size))
+    }
+
+    /// Drop the column at `path`
+    pub fn drop_column<P>(&self, path: P) -> Result<Self>
+    where
+        P: TryInto<Identifier>,
+        Error: From<P::Error>,
+    {
+        let path = self.data_path(path.try_into()?);
+        self.check_column(&path)?;
+        self.apply_edits(&[SchemaEdit::Drop {
+            path: self.schema_path(&path),
+        }])
+    }
+
+    /// Rename the column at `path`
+    pub fn rename_column<P>(&self, path: P, name: &str) -> Result<Self>
+    where
+        P: TryInto<Identifier>,
+        Error: From<P::Error>,
+    {
+        let path = self.data_path(path.try_into()?);
+        self.check_column(&path)?;
+        self.apply_edits(&[SchemaEdit::Rename {
+            path: self.schema_path(&path),
+            name: name.to_string(),
+        }])
+    }
+
+    /// The path relative to the data part of the schema
+    pub(super) fn data_path(&self, path: Identifier) -> Vec<String> {
+        let mut path = path.to_vec();
+        if self.schema_has_admin_columns() && path.first().is_some_and(|h| h == SARUS_DATA) {
+            path.remove(0);
+        }
+        path
+    }
+
+    /// The path of the relation of the table at `data_path`, prefixed with the schema name
+    pub(super) fn full_path(&self, data_path: &[String]) -> Identifier {
+        [self.schema.name().to_string()]
+            .into_iter()
+            .chain(data_path.iter().cloned())
+            .collect()
+    }
+
+    /// The path relative to the schema type
+    fn schema_path(&self, data_path: &[String]) -> Vec<String> {
+        if self.schema_has_admin_columns() {
+            [SARUS_DATA.to_string()]
+                .into_iter()
+                .chain(data_path.iter().cloned())
+                .collect()
+        } else {
+            data_path.to_vec()
+        }
+    }
+
+    pub(super) fn check_table(&self, path: &[String]) -> Result<()> {
+        let table = type_at(self.schema_type_data(), path, &Identifier::empty())?;
+        if path.is_empty() || !matches!(table.type_, Some(type_::type_::Type::Struct(_))) {
+            return Err(Error::other(format!(
+                "`{}` is not a table in a Union of tables",
+                path.join(".")
+            )));
+        }
+        Ok(())
+    }
+
+    fn check_column(&self, path: &[String]) -> Result<()> {
+        check_column(self.schema_type_data(), path)
+    }
+}
+
+impl schema::Schema {
+    /// Follow the rename (or the drop if `name` is `None`) of the field at `path`,
+    /// relative to the data part of the schema, in the keys and the protected path.
+    pub(super) fn rename_paths(&mut self, path: &[String], name: Option<&str>) {
+        self.map_key_paths(|key| match (key.strip_prefix(path), name) {
+            (None, _) => Some(key.to_vec()),
+            (Some(_), None) => None,
+            (Some(tail), Some(name)) => Some(
+                path[..path.len() - 1]
+                    .iter()
+                    .cloned()
+                    .chain([name.to_string()])
+                    .chain(tail.iter().cloned())
+                    .collect(),
+            ),
+        });
+        rename_path(self.mut_protected(), path, name);
+    }
+
+    /// Set or clear (if `references` is `None`) the foreign key of the column at `path`
+    pub(super) fn set_foreign_key(&mut self, path: &[String], references: Option<&[String]>) {
+        let properties = self.mut_properties();
+        let mut foreign_keys: Vec<(Vec<String>, Vec<String>)> =
+            parse_property(properties, FOREIGN_KEYS).unwrap_or_default();
+        foreign_keys.retain(|(pointing, _)| pointing != path);
+        if let Some(references) = references {
+            foreign_keys.push((path.to_vec(), references.to_vec()));
+        }
+        properties.insert(
+            FOREIGN_KEYS.to_string(),
+            serde_json::to_string(&foreign_keys).unwrap(),
+        );
+    }
+
+    /// Check the foreign keys link columns of tables
+    pub(super) fn check_foreign_keys(&self) -> Result<()> {
+        let foreign_keys: Vec<(Vec<String>, Vec<String>)> =
+            parse_property(self.properties(), FOREIGN_KEYS).unwrap_or_default();
+        for (pointing, pointed) in &foreign_keys {
+            self.check_foreign_key(pointing, pointed)?;
+        }
+        Ok(())
+    }
+
+    /// Check the foreign key from `pointing` to `pointed` links columns of tables
+    pub(super) fn check_foreign_key(&self, pointing: &[String], pointed: &[String]) -> Result<()> {
+        for path in [pointing, pointed] {
+            check_column(data_type(self), path).map_err(|err| {
+                Error::other(format!("Invalid foreign key `{}`: {}", path.join("."), err))
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Prefix the keys and the protected path with `prefix`
+    fn prefix_paths(&mut self, prefix: &str) {
+        self.map_key_paths(|key| {
+            Some(
+                [prefix.to_string()]
+                    .into_iter()
+                    .chain(key.to_vec())
+                    .collect(),
+            )
+        });
+        let protected = self.mut_protected();
+        let children = mem::take(&mut protected.paths);
+        if !children.is_empty() {
+            let mut node = path::Path::new();
+            node.set_label(prefix.to_string());
+            node.paths = children;
+            protected.paths.push(node);
+        }
+    }
+
+    /// Map the paths of the foreign keys and of the aliased tables,
+    /// keys and aliases mapped to `None` are removed
+    fn map_key_paths(&mut self, f: impl Fn(&[String]) -> Option<Vec<String>>) {
+        let properties = self.mut_properties();
+        if let Some(mut foreign_keys) =
+            parse_property::<Vec<(Vec<String>, Vec<String>)>>(properties, FOREIGN_KEYS)
+        {
+            foreign_keys = foreign_keys
+                .iter()
+                .filter_map(|(pointing, pointed)| Some((f(pointing)?, f(pointed)?)))
+                .collect();
+            properties.insert(
+                FOREIGN_KEYS.to_string(),
+                serde_json::to_string(&foreign_keys).unwrap(),
+            );
+        }
+        if let Some(mut aliases) =
+            parse_property::<Vec<(Vec<String>, Vec<String>)>>(properties, ALIASES)
+        {
+            aliases = aliases
+                .iter()
+                .filter_map(|(alias, table)| Some((alias.clone(), f(table)?)))
+                .collect();
+            properties.insert(
+                ALIASES.to_string(),
+                serde_json::to_string(&aliases).unwrap(),
+            );
+        }
+    }
+}
+
+/// Parse a JSON property, an empty or missing property is `None`
+pub(super) fn parse_property<T: serde::de::DeserializeOwned>(
+    properties: &HashMap<String, String>,
+    key: &str,
+) -> Option<T> {
+    properties
+        .get(key)
+        .filter(|value| !value.is_empty())
+        .and_then(|value| serde_json::from_str(value).ok())
+}
+
+/// Follow the renames and drops of the fields of `type_` in its public fields
+pub(super) fn rename_public_fields(type_: &mut type_::Type, changes: &[(String, Option<String>)]) {
+    if changes.is_empty() {
+        return;
+    }
+    let properties = type_.mut_properties();
+    if let Some(public_fields) = parse_property::<Vec<String>>(properties, PUBLIC_FIELDS) {
+        let public_fields: Vec<String> = public_fields
+            .into_iter()
+            .filter_map(
+                |field| match changes.iter().find(|(original, _)| *original == field) {
+                    Some((_, renamed)) => renamed.clone(),
+                    None => Some(field),
+                },
+            )
+            .collect();
+        properties.insert(
+            PUBLIC_FIELDS.to_string(),
+            serde_json::to_string(&public_fields).unwrap(),
+        );
+    }
+}
+
+/// Rename (or remove if `name` is `None`) the node at `path` below the root of a path tree
+fn rename_path(node: &mut path::Path, path: &[String], name: Option<&str>) {
+    match path {
+        [] => {}
+        [head] => match name {
+            Some(name) => node
+                .paths
+                .iter_mut()
+                .filter(|child| child.label() == head)
+                .for_each(|child| child.set_label(name.to_string())),
+            None => node.paths.retain(|child| child.label() != head),
+        },
+        [head, tail @ ..] => node
+            .paths
+            .iter_mut()
+            .filter(|child| child.label() == head)
+            .for_each(|child| rename_path(child, tail, name)),
+    }
+}
+
+/// The data part of a schema type
+pub(super) fn data_type(schema: &schema::Schema) -> &type_::Type {
+    match schema.type_().type_.as_ref() {
+        Some(type_::type_::Type::Struct(s)) => s
+            .fields
+            .iter()
+            .find(|f| f.name() == SARUS_DATA)
+            .map_or(schema.type_(), |f| f.type_()),
+        _ => schema.type_(),
+    }
+}
+
+/// The data part of a schema type
+pub(super) fn data_type_mut(schema: &mut schema::Schema) -> &mut type_::Type {
+    let data_index = match schema.type_().type_.as_ref() {
+        Some(type_::type_::Type::Struct(s)) => s.fields.iter().position(|f| f.name() == SARUS_DATA),
+        _ => None,
+    };
+    match data_index {
+        Some(index) => schema.mut_type().mut_struct().fields[index].mut_type(),
+        None => schema.mut_type(),
+    }
+}
+
+/// Check `path` designates a column of a table below `type_`
+fn check_column(type_: &type_::Type, path: &[String]) -> Result<()> {
+    type_at(type_, path, &Identifier::empty())?;
+    let Some((_, table_path)) = path.split_last() else {
+        return Err(Error::other("The schema root is not a column"));
+    };
+    match type_at(type_, table_path, &Identifier::empty())?.type_ {
+        Some(type_::type_::Type::Struct(_)) => Ok(()),
+        _ => Err(Error::other(format!(
+            "`{}` is not a column of a table",
+            path.join(".")
+        ))),
+    }
+}
+
+/// The type at `path` below `type_`, Optionals are traversed
+pub(super) fn type_at<'a>(
+    type_: &'a type_::Type,
+    path: &[String],
+    visited_path: &Identifier,
+) -> Result<&'a type_::Type> {
+    let Some((head, tail)) = path.split_first() else {
+        return Ok(type_);
+    };
+    let names: Vec<&str> = match type_.type_.as_ref() {
+        Some(type_::type_::Type::Struct(s)) => {
+            if let Some(f) = s.fields.iter().find(|f| f.name() == head) {
+                return type_at(f.type_(), tail, &visited_path.clone().with(head.clone()));
+            }
+            s.fields.iter().map(|f| f.name()).collect()
+        }
+        Some(type_::type_::Type::Union(u)) => {
+            if let Some(f) = u.fields.iter().find(|f| f.name() == head) {
+                return type_at(f.type_(), tail, &visited_path.clone().with(head.clone()));
+            }
+            u.fields.iter().map(|f| f.name()).collect()
+        }
+        Some(type_::type_::Type::Optional(o)) => return type_at(o.type_(), path, visited_path),
+        _ => vec![],
+    };
+    Err(unresolved_path_error(head, visited_path, names.into_iter()))
+}
+
+/// The mutable type at `path` below `type_`, Optionals are traversed
+pub(super) fn type_at_mut<'a>(
+    type_: &'a mut type_::Type,
+    path: &[String],
+    visited_path: &Identifier,
+) -> Result<&'a mut type_::Type> {
+    let Some((head, tail)) = path.split_first() else {
+        return Ok(type_);
+    };
+    let names: Vec<String> = match type_.type_.as_mut() {
+        Some(type_::type_::Type::Struct(s)) => {
+            let names = s.fields.iter().map(|f| f.name().to_string()).collect();
+            if let Some(f) = s.fields.iter_mut().find(|f| f.name() == head) {
+                return type_at_mut(f.mut_type(), tail, &visited_path.clone().with(head.clone()));
+            }
+            names
+        }
+        Some(type_::type_::Type::Union(u)) => {
+            let names = u.fields.iter().map(|f| f.name().to_string()).collect();
+            if let Some(f) = u.fields.iter_mut().find(|f| f.name() == head) {
+                return type_at_mut(f.mut_type(), tail, &visited_path.clone().with(head.clone()));
+            }
+            names
+        }
+        Some(type_::type_::Type::Optional(o)) => {
+            return type_at_mut(o.mut_type(), path, visited_path)
+        }
+        _ => vec![],
+    };
+    Err(unresolved_path_error(
+        head,
+        visited_path,
+        names.iter().map(|n| n.as_str()),
+    ))
+}
+
+/// The mutable statistics at `path` below `statistics`, if any
+pub(super) fn statistics_at_mut<'a>(
+    statistics: &'a mut statistics::Statistics,
+    path: &[String],
+) -> Option<&'a mut statistics::Statistics> {
+    let Some((head, tail)) = path.split_first() else {
+        return Some(statistics);
+    };
+    match statistics.statistics.as_mut()? {
+        statistics::statistics::Statistics::Struct(s) => s
+            .fields
+            .iter_mut()
+            .find(|f| f.name() == head)
+            .and_then(|f| statistics_at_mut(f.mut_statistics(), tail)),
+        statistics::statistics::Statistics::Union(u) => u
+            .fields
+            .iter_mut()
+            .find(|f| f.name() == head)
+            .and_then(|f| statistics_at_mut(f.mut_statistics(), tail)),
+        statistics::statistics::Statistics::Optional(o) => {
+            statistics_at_mut(o.mut_statistics(), path)
+        }
+        _ => None,
+    }
+}
+
+fn union_type(fields: Vec<(String, type_::Type)>) -> type_::Type {
+    let mut union = type_::type_::Union::new();
+    for (name, type_) in fields {
+        let mut field = type_::type_::union::Field::new();
+        field.set_name(name);
+        field.set_type(type_);
+        union.fields.push(field);
+    }
+    let mut type_ = type_::Type::new();
+    type_.set_name("Union".to_string());
+    type_.set_union(union);
+    type_
+        .mut_properties()
+        .insert(PUBLIC_FIELDS.to_string(), "[]".to_string());
+    type_
+}
+
+pub(super) fn union_statistics(
+    fields: Vec<(String, statistics::Statistics)>,
+) -> statistics::Statistics {
+    let mut union = statistics::statistics::Union::new();
+    union.set_name("Union".to_string());
+    for (name, statistics) in fields {
+        union.size += size_of(&statistics);
+        let mut field = statistics::statistics::union::Field::new();
+        field.set_name(name);
+        field.set_statistics(statistics);
+        union.fields.push(field);
+    }
+    let mut statistics = statistics::Statistics::new();
+    statistics.set_union(union);
+    statistics
+}
+
+pub(super) fn table_statistics(size: i64) -> statistics::Statistics {
+    let mut table = statistics::statistics::Struct::new();
+    table.set_name("Struct".to_string());
+    table.set_size(size);
+    let mut statistics = statistics::Statistics::new();
+    statistics.set_struct(table);
+    statistics
+}
+
+/// Statistics matching `type_`, with no distribution and a null size
+pub(super) fn statistics_skeleton(type_: &type_::Type) -> statistics::Statistics {
+    use statistics::statistics::Statistics as S;
+    use type_::type_::Type as T;
+    let statistics = match type_.type_.as_ref() {
+        Some(T::Null(_)) => Some(S::Null(Default::default())),
+        Some(T::Unit(_)) => Some(S::Unit(Default::default())),
+        Some(T::Boolean(_)) => Some(S::Boolean(Default::default())),
+        Some(T::Integer(_)) => Some(S::Integer(Default::default())),
+        Some(T::Enum(_)) => Some(S::Enum(Default::default())),
+        Some(T::Float(_)) => Some(S::Float(Default::default())),
+        Some(T::Text(_)) => Some(S::Text(Default::default())),
+        Some(T::Bytes(_)) => Some(S::Bytes(Default::default())),
+        Some(T::Id(_)) => Some(S::Id(Default::default())),
+        Some(T::Datetime(_)) => Some(S::Datetime(Default::default())),
+        Some(T::Date(_)) => Some(S::Date(Default::default())),
+        Some(T::Time(_)) => Some(S::Time(Default::default())),
+        Some(T::Duration(_)) => Some(S::Duration(Default::default())),
+        Some(T::Struct(s)) => Some(S::Struct(statistics::statistics::Struct {
+            name: "Struct".to_string(),
+            fields: s
+                .fields
+                .iter()
+                .map(|f| statistics::statistics::struct_::Field {
+                    name: f.name().to_string(),
+                    statistics: MessageField::some(statistics_skeleton(f.type_())),
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        })),
+        Some(T::Union(u)) => Some(S::Union(statistics::statistics::Union {
+            name: "Union".to_string(),
+            fields: u
+                .fields
+                .iter()
+                .map(|f| statistics::statistics::union::Field {
+                    name: f.name().to_string(),
+                    statistics: MessageField::some(statistics_skeleton(f.type_())),
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        })),
+        Some(T::Optional(o)) => Some(S::Optional(statistics::statistics::Optional {
+            name: "Optional".to_string(),
+            statistics: MessageField::some(statistics_skeleton(o.type_())),
+            ..Default::default()
+        })),
+        Some(T::List(l)) => Some(S::List(statistics::statistics::List {
+            statistics: MessageField::some(statistics_skeleton(l.type_())),
+            ..Default::default()
+        })),
+        Some(T::Array(a)) => Some(S::Array(statistics::statistics::Array {
+            statistics: MessageField::some(statistics_skeleton(a.type_())),
+            ..Default::default()
+        })),
+        Some(T::Constrained(c)) => Some(S::Constrained(statistics::statistics::Constrained {
+            statistics: MessageField::some(statistics_skeleton(c.type_())),
+            ..Default::default()
+        })),
+        _ => None,
+    };
+    statistics::Statistics {
+        name: type_.name().to_string(),
+        statistics,
+        ..Default::default()
+    }
+}
+
+/// Set the size of a statistics node
+pub(super) fn with_size(
+    mut statistics: statistics::Statistics,
+    size: i64,
+) -> statistics::Statistics {
+    use statistics::statistics::Statistics as S;
+    macro_rules! set_size {
+        ($($variant:ident),*) => {
+            match statistics.statistics.as_mut() {
+                $(Some(S::$variant(s)) => s.size = size,)*
+                None => {}
+            }
+        };
+    }
+    set_size!(
+        Null,
+        Unit,
+        Boolean,
+        Integer,
+        Enum,
+        Float,
+        Text,
+        Bytes,
+        Struct,
+        Union,
+        Optional,
+        List,
+        Array,
+        Datetime,
+        Id,
+        Constrained,
+        Date,
+        Time,
+        Duration
+    );
+    statistics
+}
+
+/// The size of a statistics node
+pub(super) fn size_of(statistics: &statistics::Statistics) -> i64 {
+    use statistics::statistics::Statistics as S;
+    macro_rules! size {
+        ($($variant:ident),*) => {
+            match statistics.statistics.as_ref() {
+                $(Some(S::$variant(s)) => s.size,)*
+                None => 0,
+            }
+        };
+    }
+    size!(
+        Null,
+        Unit,
+        Boolean,
+        Integer,
+        Enum,
+        Float,
+        Text,
+        Bytes,
+        Struct,
+        Union,
+        Optional,
+        List,
+        Array,
+        Datetime,
+        Id,
+        Constrained,
+        Date,
+        Time,
+        Duration
+    )
+}
+
+/// The multiplicity of a statistics node
+pub(super) fn multiplicity_of(statistics: &statistics::Statistics) -> f64 {
+    use statistics::statistics::Statistics as S;
+    macro_rules! multiplicity {
+        ($($variant:ident),*) => {
+            match statistics.statistics.as_ref() {
+                $(Some(S::$variant(s)) => s.multiplicity,)*
+                None => 0.,
+            }
+        };
+    }
+    multiplicity!(
+        Null,
+        Unit,
+        Boolean,
+        Integer,
+        Enum,
+        Float,
+        Text,
+        Bytes,
+        Struct,
+        Union,
+        Optional,
+        List,
+        Array,
+        Datetime,
+        Id,
+        Constrained,
+        Date,
+        Time,
+        Duration
+    )
+}
+
+/// The name of the variant of a statistics node
+pub(super) fn variant_name(statistics: &statistics::Statistics) -> &'static str {
+    use statistics::statistics::Statistics as S;
+    match statistics.statistics.as_ref() {
+        None => "None",
+        Some(S::Null(_)) => "Null",
+        Some(S::Unit(_)) => "Unit",
+        Some(S::Boolean(_)) => "Boolean",
+        Some(S::Integer(_)) => "Integer",
+        Some(S::Enum(_)) => "Enum",
+        Some(S::Float(_)) => "Float",
+        Some(S::Text(_)) => "Text",
+        Some(S::Bytes(_)) => "Bytes",
+        Some(S::Struct(_)) => "Struct",
+        Some(S::Union(_)) => "Union",
+        Some(S::Optional(_)) => "Optional",
+        Some(S::List(_)) => "List",
+        Some(S::Array(_)) => "Array",
+        Some(S::Datetime(_)) => "Datetime",
+        Some(S::Id(_)) => "Id",
+        Some(S::Constrained(_)) => "Constrained",
+        Some(S::Date(_)) => "Date",
+        Some(S::Time(_)) => "Time",
+        Some(S::Duration(_)) => "Duration",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protobuf::parse_from_str;
+    use anyhow::Result;
+    use qrlew::relation::{Constraint, Variant as _};
+
+    const SCHEMA: &str = r#"
+    {
+        "name": "a",
+        "type": {
+            "name": "Struct",
+            "struct": {
+                "fields": [
+                    {"name": "sarus_data", "type": {"name": "Union", "union": {"fields": [
+                        {"name": "users", "type": {"name": "Struct", "struct": {"fields": [
+                            {"name": "id", "type": {"name": "Integer", "properties": {"_CONSTRAINT_": "_PRIMARY_KEY_"},
+                                "integer": {"min": "0", "max": "1000"}}},
+                            {"name": "name", "type": {"name": "Text", "text": {"encoding": "UTF-8"}}}
+                        ]}}},
+                        {"name": "orders", "type": {"name": "Struct", "struct": {"fields": [
+                            {"name": "user_id", "type": {"name": "Integer", "integer": {"min": "0", "max": "1000"}}}
+                        ]}}}
+                    ]}, "properties": {"public_fields": "[\"orders\"]"}}},
+                    {"name": "sarus_is_public", "type": {"name": "Boolean", "boolean": {}}},
+                    {"name": "sarus_privacy_unit", "type": {"name": "Optional", "optional": {"type": {"name": "Id", "id": {"base": "STRING"}}}}},
+                    {"name": "sarus_weights", "type": {"name": "Float", "float": {"min": 0, "max": 1}}}
+                ]
+            }
+        },
+        "protected": {"label": "data", "paths": [{"label": "users", "paths": [{"label": "id"}]}]},
+        "properties": {
+            "foreign_keys": "[[[\"orders\",\"user_id\"],[\"users\",\"id\"]]]"
+        }
+    }
+    "#;
+
+    const SIZE: &str = r#"
+    {
+        "statistics": {"name": "Union", "union": {"fields": [
+            {"name": "users", "statistics": {"name": "Struct", "struct": {"size": "100", "fields": [
+                {"name": "id", "statistics": {"name": "Integer", "integer": {"size": "100"}}},
+                {"name": "name", "statistics": {"name": "Text", "text": {"size": "100"}}}
+            ]}}},
+            {"name": "orders", "statistics": {"name": "Struct", "struct": {"size": "300", "fields": []}}}
+        ]}}
+    }
+    "#;
+
+    const TABLE: &str = r#"
+    {"name": "Struct", "struct": {"fields": [
+        {"name": "sku", "type": {"name": "Text", "text": {"encoding": "UTF-8"}}}
+    ]}}
+    "#;
+
+    fn dataset() -> Dataset {
+        Dataset::new(
+            parse_from_str("{}").unwrap(),
+            parse_from_str(SCHEMA).unwrap(),
+            parse_from_str(SIZE).ok(),
+        )
+    }
+
+    fn property<'a>(dataset: &'a Dataset, key: &str) -> &'a str {
+        dataset.schema().properties().get(key).unwrap()
+    }
+
+    #[test]
+    fn test_tables() -> Result<()> {
+        let dataset = dataset()
+            .add_table(["products"], parse_from_str(TABLE)?, Some(20))?
+            .rename_table(["users"], "customers")?
+            .drop_table(["sarus_data", "orders"])?;
+        let relations = dataset.relations();
+        println!("{}", dataset);
+        assert_eq!(size_of(dataset.size_statistics().unwrap()), 120);
+        assert_eq!(relations[["a", "products"]].size().max(), Some(&20));
+        assert_eq!(relations[["a", "customers"]].size().max(), Some(&100));
+        assert!(relations
+            .get(&["a".to_string(), "orders".to_string()])
+            .is_none());
+        assert_eq!(
+            relations[["a", "customers"]]
+                .schema()
+                .field("id")?
+                .constraint(),
+            Some(Constraint::PrimaryKey)
+        );
+        assert_eq!(property(&dataset, FOREIGN_KEYS), "[]");
+        assert_eq!(
+            dataset
+                .schema_type_data()
+                .properties()
+                .get(PUBLIC_FIELDS)
+                .unwrap(),
+            "[]"
+        );
+        assert_eq!(dataset.schema().protected().paths[0].label(), "customers");
+        // Errors
+        assert!(dataset
+            .add_table(["products"], parse_from_str(TABLE)?, None)
+            .is_err());
+        assert!(dataset.drop_table(["customers", "id"]).is_err());
+        assert!(dataset.rename_table(["custommers"], "users").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_add_table_to_single_table() -> Result<()> {
+        let dataset = Dataset::new(
+            parse_from_str("{}")?,
+            parse_from_str(&format!(r#"{{"name": "a", "type": {}}}"#, TABLE))?,
+            parse_from_str(r#"{"statistics": {"name": "Struct", "struct": {"size": "10"}}}"#).ok(),
+        );
+        assert_eq!(dataset.relations().len(), 1);
+        let dataset = dataset.add_table(["b"], parse_from_str(TABLE)?, Some(5))?;
+        assert_eq!(size_of(dataset.size_statistics().unwrap()), 15);
+        let relations = dataset.relations();
+        assert_eq!(relations[["a", "a"]].size().max(), Some(&10));
+        assert_eq!(relations[["a", "b"]].size().max(), Some(&5));
+        assert!(dataset
+            .add_table(["a"], parse_from_str(TABLE)?, None)
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_columns() -> Result<()> {
+        let column: type_::Type =
+            parse_from_str(r#"{"name": "Float", "float": {"min": 0, "max": 1}}"#)?;
+        let dataset = dataset()
+            .add_column(["users", "score"], column.clone())?
+            .add_column(["orders", "score"], column.clone())?
+            .rename_column(["users", "id"], "user_id")?
+            .drop_column(["users", "name"])?;
+        let users = &dataset.relations()[["a", "users"]];
+        assert_eq!(
+            users.schema().iter().map(|f| f.name()).collect::<Vec<_>>(),
+            vec![
+                "user_id",
+                "score",
+                "sarus_is_public",
+                "sarus_privacy_unit",
+                "sarus_weights"
+            ]
+        );
+        let Some(statistics::statistics::Statistics::Union(u)) =
+            dataset.size_statistics().unwrap().statistics.as_ref()
+        else {
+            panic!()
+        };
+        let users_statistics = u.fields[0].statistics().struct_();
+        assert_eq!(
+            users_statistics
+                .fields
+                .iter()
+                .map(|f| (f.name(), size_of(f.statistics())))
+                .collect::<Vec<_>>(),
+            vec![("user_id", 100), ("score", 100)]
+        );
+        assert!(u.fields[1].statistics().struct_().fields.is_empty());
+        assert_eq!(
+            users.schema().field("user_id")?.constraint(),
+            Some(Constraint::PrimaryKey)
+        );
+        assert_eq!(
+            property(&dataset, FOREIGN_KEYS),
+            r#"[[["orders","user_id"],["users","user_id"]]]"#
+        );
+        assert_eq!(
+            dataset.schema().protected().paths[0].paths[0].label(),
+            "user_id"
+        );
+        // Errors
+        assert!(dataset
+            .add_column(["users", "score"], column.clone())
+            .is_err());
+        assert!(dataset.add_column(["users", "score", "x"], column).is_err());
+        assert!(dataset.drop_column(["users"]).is_err());
+        assert!(dataset.rename_column(["users", "nme"], "x").is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,421 @@
+//! Validation of statistics trees (Size, Marginals or Bounds) against the schema type.
+//!
+//! Table statistics without field nodes only give the size of the table,
+//! their columns are not reported as missing.
+
+use super::{
+    profile::{integer_value, Cell},
+    structure::{data_type, size_of, variant_name},
+    Dataset, Error, Result, SIZE_MAX, SIZE_MIN,
+};
+use crate::protobuf::{statistics, type_};
+use std::fmt;
+
+/// A mismatch between a statistics tree and the schema type,
+/// at a path relative to the data part of the schema
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mismatch {
+    pub path: Vec<String>,
+    pub description: String,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "`{}`: {}", self.path.join("."), self.description)
+    }
+}
+
+impl Dataset {
+    /// Check the Size statistics, if any, against the schema type
+    pub fn validate(&self) -> Result<()> {
+        match self.size_statistics() {
+            Some(statistics) => self.validate_statistics(statistics),
+            None => Ok(()),
+        }
+    }
+
+    /// Check statistics (e.g. from a Size, Marginals or Bounds message) against the schema type
+    pub fn validate_statistics(&self, statistics: &statistics::Statistics) -> Result<()> {
+        let mismatches = self.mismatches(statistics);
+        if mismatches.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::other(format!(
+                "The statistics do not match the schema:\n{}",
+                mismatches
+                    .iter()
+                    .map(|m| m.to_string())
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            )))
+        }
+    }
+
+    /// All the mismatches between statistics and the schema type
+    pub fn mismatches(&self, statistics: &statistics::Statistics) -> Vec<Mismatch> {
+        let mut mismatches = vec![];
+        validate(
+            data_type(&self.schema),
+            statistics,
+            &mut vec![],
+            &mut mismatches,
+        );
+        mismatches
+    }
+}
+
+fn validate(
+    type_: &type_::Type,
+    statistics: &statistics::Statistics,
+    path: &mut Vec<String>,
+    mismatches: &mut Vec<Mismatch>,
+) {
+    use statistics::statistics::Statistics as S;
+    use type_::type_::Type as T;
+    let type_name = type_variant_name(type_);
+    let statistics_name = variant_name(statistics);
+    if type_name != statistics_name {
+        // Hypotheses have no statistics counterpart
+        if type_name != "Hypothesis" {
+            report(
+                mismatches,
+                path,
+                format!("{} statistics for type {}", statistics_name, type_name),
+            );
+        }
+        return;
+    }
+    let size = size_of(statistics);
+    if size < 0 {
+        report(mismatches, path, format!("negative size {}", size));
+    }
+    let bound = |key: &str| {
+        statistics
+            .properties
+            .get(key)
+            .and_then(|b| b.parse::<i64>().ok())
+    };
+    if let (Some(min), Some(max)) = (bound(SIZE_MIN), bound(SIZE_MAX)) {
+        if min > max {
+            report(
+                mismatches,
+                path,
+                format!("size range [{}, {}] is empty", min, max),
+            );
+        }
+    }
+    match (type_.type_.as_ref(), statistics.statistics.as_ref()) {
+        (Some(T::Union(t)), Some(S::Union(s))) => {
+            let fields: Vec<(&str, &statistics::Statistics)> = s
+                .fields
+                .iter()
+                .map(|f| (f.name(), f.statistics()))
+                .collect();
+            validate_fields(
+                t.fields.iter().map(|f| (f.name(), f.type_())),
+                &fields,
+                true,
+                path,
+                mismatches,
+            );
+        }
+        (Some(T::Struct(t)), Some(S::Struct(s))) => {
+            let fields: Vec<(&str, &statistics::Statistics)> = s
+                .fields
+                .iter()
+                .map(|f| (f.name(), f.statistics()))
+                .collect();
+            validate_fields(
+                t.fields.iter().map(|f| (f.name(), f.type_())),
+                &fields,
+                !fields.is_empty(),
+                path,
+                mismatches,
+            );
+        }
+        (Some(T::Optional(t)), Some(S::Optional(s))) => {
+            let inner_size = size_of(s.statistics());
+            if inner_size > s.size {
+                report(
+                    mismatches,
+                    path,
+                    format!("{} values out of {} rows", inner_size, s.size),
+                );
+            }
+            validate(t.type_(), s.statistics(), path, mismatches);
+        }
+        (Some(T::List(t)), Some(S::List(s))) => {
+            validate(t.type_(), s.statistics(), path, mismatches)
+        }
+        (Some(T::Array(t)), Some(S::Array(s))) => {
+            validate(t.type_(), s.statistics(), path, mismatches)
+        }
+        (Some(T::Constrained(t)), Some(S::Constrained(s))) => {
+            validate(t.type_(), s.statistics(), path, mismatches)
+        }
+        (_, Some(s)) => {
+            if let Some(distribution) = distribution(s) {
+                validate_distribution(type_, distribution, &mut |description| {
+                    report(mismatches, path, description)
+                });
+            }
+        }
+        _ => {}
+    }
+}
+
+fn report(mismatches: &mut Vec<Mismatch>, path: &[String], description: String) {
+    mismatches.push(Mismatch {
+        path: path.to_vec(),
+        description,
+    })
+}
+
+fn validate_fields<'a>(
+    types: impl Iterator<Item = (&'a str, &'a type_::Type)>,
+    statistics: &[(&str, &statistics::Statistics)],
+    report_missing: bool,
+    path: &mut Vec<String>,
+    mismatches: &mut Vec<Mismatch>,
+) {
+    let types: Vec<(&str, &type_::Type)> = types.collect();
+    for (name, type_) in types.iter() {
+        path.push(name.to_string());
+        match statistics.iter().find(|(n, _)| n == name) {
+            Some((_, s)) => validate(type_, s, path, mismatches),
+            None if report_missing => report(mismatches, path, "missing statistics".to_string()),
+            None => {}
+        }
+        path.pop();
+    }
+    for (name, _) in statistics.iter() {
+        if !types.iter().any(|(n, _)| n == name) {
+            path.push(name.to_string());
+            report(
+                mismatches,
+                path,
+                "statistics of a field absent from the type".to_string(),
+            );
+            path.pop();
+        }
+    }
+}
+
+pub(super) fn distribution(
+    statistics: &statistics::statistics::Statistics,
+) -> Option<&statistics::Distribution> {
+    use statistics::statistics::Statistics as S;
+    match statistics {
+        S::Boolean(s) => s.distribution.as_ref(),
+        S::Integer(s) => s.distribution.as_ref(),
+        S::Enum(s) => s.distribution.as_ref(),
+        S::Float(s) => s.distribution.as_ref(),
+        S::Text(s) => s.distribution.as_ref(),
+        S::Datetime(s) => s.distribution.as_ref(),
+        S::Date(s) => s.distribution.as_ref(),
+        S::Time(s) => s.distribution.as_ref(),
+        S::Duration(s) => s.distribution.as_ref(),
+        _ => None,
+    }
+}
+
+/// Check the values of a distribution are in the type.
+/// Temporal values are encoded as by the profiler.
+fn validate_distribution(
+    type_: &type_::Type,
+    distribution: &statistics::Distribution,
+    report: &mut impl FnMut(String),
+) {
+    use statistics::distribution::Distribution as D;
+    use type_::type_::Type as T;
+    let temporal_bounds = |min: &str, max: &str| {
+        Some((
+            integer_value(type_, &Cell::Text(min.to_string()))? as f64,
+            integer_value(type_, &Cell::Text(max.to_string()))? as f64,
+        ))
+    };
+    let (expected, bounds) = match type_.type_.as_ref() {
+        Some(T::Boolean(_)) => ("Boolean", None),
+        Some(T::Integer(t)) => ("Integer", Some((t.min as f64, t.max as f64))),
+        Some(T::Duration(t)) => ("Integer", Some((t.min as f64, t.max as f64))),
+        Some(T::Date(t)) => ("Integer", temporal_bounds(&t.min, &t.max)),
+        Some(T::Time(t)) => ("Integer", temporal_bounds(&t.min, &t.max)),
+        Some(T::Datetime(t)) => ("Integer", temporal_bounds(&t.min, &t.max)),
+        Some(T::Float(t)) => ("Double", Some((t.min, t.max))),
+        Some(T::Enum(_)) | Some(T::Text(_)) => ("Enum", None),
+        _ => return,
+    };
+    let actual = match distribution.distribution.as_ref() {
+        None => return,
+        Some(D::Double(_)) => "Double",
+        Some(D::Integer(_)) => "Integer",
+        Some(D::Boolean(_)) => "Boolean",
+        Some(D::Enum(_)) => "Enum",
+    };
+    if actual != expected {
+        report(format!(
+            "{} distribution for type {}",
+            actual,
+            type_variant_name(type_)
+        ));
+        return;
+    }
+    if let Some((min, max)) = bounds {
+        // Bounds of a distribution without points are only checked when set
+        let declared = match distribution.distribution.as_ref() {
+            Some(D::Double(d)) if d.points.is_empty() && (d.min, d.max) == (0., 0.) => None,
+            Some(D::Integer(d)) if d.points.is_empty() && (d.min, d.max) == (0, 0) => None,
+            _ => distribution.min().zip(distribution.max()),
+        };
+        if let Some((low, high)) = declared {
+            if low < min || high > max {
+                report(format!(
+                    "distribution values in [{}, {}] outside the type bounds [{}, {}]",
+                    low, high, min, max
+                ));
+            }
+        }
+    }
+    let possible_names: Option<Vec<&str>> = match type_.type_.as_ref() {
+        Some(T::Enum(t)) => Some(t.name_values.iter().map(|nv| nv.name()).collect()),
+        Some(T::Text(t)) if !t.possible_values.is_empty() => {
+            Some(t.possible_values.iter().map(|v| v.as_str()).collect())
+        }
+        _ => None,
+    };
+    if let (Some(possible_names), Some(D::Enum(d))) =
+        (possible_names, distribution.distribution.as_ref())
+    {
+        let impossible: Vec<&str> = d
+            .points
+            .iter()
+            .filter(|p| p.probability > 0. && !possible_names.contains(&p.name()))
+            .map(|p| p.name())
+            .collect();
+        if !impossible.is_empty() {
+            report(format!(
+                "distribution values {} outside the possible values",
+                impossible.join(", ")
+            ));
+        }
+    }
+}
+
+/// The name of the variant of a type
+fn type_variant_name(type_: &type_::Type) -> &'static str {
+    use type_::type_::Type as T;
+    match type_.type_.as_ref() {
+        None => "None",
+        Some(T::Null(_)) => "Null",
+        Some(T::Unit(_)) => "Unit",
+        Some(T::Boolean(_)) => "Boolean",
+        Some(T::Integer(_)) => "Integer",
+        Some(T::Enum(_)) => "Enum",
+        Some(T::Float(_)) => "Float",
+        Some(T::Text(_)) => "Text",
+        Some(T::Bytes(_)) => "Bytes",
+        Some(T::Struct(_)) => "Struct",
+        Some(T::Union(_)) => "Union",
+        Some(T::Optional(_)) => "Optional",
+        Some(T::List(_)) => "List",
+        Some(T::Array(_)) => "Array",
+        Some(T::Datetime(_)) => "Datetime",
+        Some(T::Constrained(_)) => "Constrained",
+        Some(T::Hypothesis(_)) => "Hypothesis",
+        Some(T::Id(_)) => "Id",
+        Some(T::Date(_)) => "Date",
+        Some(T::Time(_)) => "Time",
+        Some(T::Duration(_)) => "Duration",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{data_spec::DataFile, protobuf::parse_from_str};
+    use anyhow::Result;
+
+    const SCHEMA: &str = r#"
+    {
+        "name": "shop",
+        "type": {"name": "Union", "union": {"fields": [
+            {"name": "users", "type": {"name": "Struct", "struct": {"fields": [
+                {"name": "age", "type": {"name": "Integer", "integer": {"min": "0", "max": "120"}}},
+                {"name": "city", "type": {"name": "Text", "text": {"encoding": "UTF-8", "possible_values": ["Paris", "Lyon"]}}},
+                {"name": "score", "type": {"name": "Optional", "optional": {"type": {"name": "Float", "float": {"min": 0, "max": 10}}}}}
+            ]}}},
+            {"name": "orders", "type": {"name": "Struct", "struct": {"fields": [
+                {"name": "user_id", "type": {"name": "Integer", "integer": {"min": "0", "max": "1000"}}}
+            ]}}}
+        ]}}
+    }
+    "#;
+
+    #[test]
+    fn test_validate() -> Result<()> {
+        let dataset = Dataset::new(
+            parse_from_str("{}")?,
+            parse_from_str(SCHEMA)?,
+            parse_from_str(
+                r#"{"statistics": {"name": "Union", "union": {"fields": [
+                    {"name": "users", "statistics": {"name": "Struct", "struct": {"size": "10"}}},
+                    {"name": "orders", "statistics": {"name": "Struct", "struct": {"size": "30"}}}
+                ]}}}"#,
+            )
+            .ok(),
+        );
+        dataset.validate()?;
+        let statistics: statistics::Statistics = parse_from_str(
+            r#"{"name": "Union", "union": {"fields": [
+                {"name": "users", "statistics": {"name": "Struct", "struct": {"size": "-1", "fields": [
+                    {"name": "age", "statistics": {"name": "Integer", "integer": {"size": "10",
+                        "distribution": {"integer": {"min": "-5", "max": "40", "points": [
+                            {"value": "-5", "probability": 0.5}, {"value": "40", "probability": 0.5}
+                        ]}}}}},
+                    {"name": "city", "statistics": {"name": "Text", "text": {"size": "10",
+                        "distribution": {"enum": {"points": [
+                            {"name": "Paris", "value": 0, "probability": 0.5},
+                            {"name": "Nantes", "value": 1, "probability": 0.5}
+                        ]}}}}},
+                    {"name": "score", "statistics": {"name": "Float", "float": {"size": "10"}}}
+                ]}}},
+                {"name": "customers", "statistics": {"name": "Struct", "struct": {"size": "3"}}}
+            ]}}"#,
+        )?;
+        let mismatches: Vec<String> = dataset
+            .mismatches(&statistics)
+            .iter()
+            .map(|m| m.to_string())
+            .collect();
+        assert_eq!(
+            mismatches,
+            vec![
+                "`users`: negative size -1",
+                "`users.age`: distribution values in [-5, 40] outside the type bounds [0, 120]",
+                "`users.city`: distribution values Nantes outside the possible values",
+                "`users.score`: Float statistics for type Optional",
+                "`orders`: missing statistics",
+                "`customers`: statistics of a field absent from the type",
+            ]
+        );
+        assert!(dataset.validate_statistics(&statistics).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_profile() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("users.csv");
+        std::fs::write(&path, "age,city,score\n30,Paris,1.5\n40,Lyon,\n")?;
+        let dataset = Dataset::new(parse_from_str("{}")?, parse_from_str(SCHEMA)?, None);
+        let orders = dir.path().join("orders.csv");
+        std::fs::write(&orders, "user_id\n1\n")?;
+        let (size, marginals) = dataset.profile_files([
+            (["users"], DataFile::Csv(path)),
+            (["orders"], DataFile::Csv(orders)),
+        ])?;
+        dataset.validate_statistics(size.statistics())?;
+        dataset.validate_statistics(marginals.statistics())?;
+        Ok(())
+    }
+}
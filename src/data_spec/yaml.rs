@@ -0,0 +1,1452 @@
+//! A concise YAML format to author datasets by hand.
+//!
+//! Tables are keyed by their dotted path in the data part of the schema and columns are written
+//! as `<type> [<constraint>] [references <table>.<column>]`, types using the compact notation of
+//! `type_::Type::to_compact_string`:
+//!
+//! ```yaml
+//! name: shop
+//! doc: The orders of an online shop
+//! spec:
+//!   sql:
+//!     uri: postgresql://localhost/shop
+//!     tables: [{schema: public, table: users}]
+//! protected: [users]
+//! max_max_multiplicity: 3
+//! tables:
+//!   users:
+//!     size: 100
+//!     columns:
+//!       id: int[0, 1000] primary key
+//!       city: optional<text{"Paris","Lyon"}>
+//!   orders:
+//!     size: [1000, 2000]
+//!     columns:
+//!       user_id: int[0, 1000] references users.id
+//!       at: datetime[2020-01-01 00:00:00, 2030-01-01 00:00:00]
+//! aliases:
+//!   customers: users
+//! admin_columns:
+//!   sarus_is_public: bool
+//!   sarus_privacy_unit: optional<id>
+//!   sarus_weights: float[0, 1]
+//! ```
+//!
+//! Writing a dataset fails on what this format cannot express, such as Id references, Text
+//! encodings other than UTF-8, non-default temporal formats or type properties.
+
+use super::{
+    ddl::{float_type, insert_table, integer_type},
+    edit::{float_base_max, integer_base_range, Temporal},
+    structure::{parse_property, table_statistics, union_statistics},
+    table_structs, Dataset, Error, Result, ALIASES, CONSTRAINT, CONSTRAINT_NOT_NULL,
+    CONSTRAINT_PRIMARY_KEY, CONSTRAINT_UNIQUE, FOREIGN_KEYS, MAX_MAX_MULTIPLICITY, POSSIBLE_VALUES,
+    PUBLIC_FIELDS, SARUS_DATA,
+};
+use crate::protobuf::{
+    dataset, parse_from_str, path, print_to_string, schema, size, statistics, type_,
+};
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use qrlew::expr::identifier::Identifier;
+use serde::{de, ser::SerializeMap, Deserialize, Deserializer, Serialize, Serializer};
+use std::{fmt, marker::PhantomData, str::FromStr};
+
+/// The label of the root of the protected paths written from YAML
+const PROTECTED_ROOT: &str = "data";
+
+impl Dataset {
+    /// Parse a dataset from its YAML authoring format.
+    /// Parsing errors report the line and column of the faulty value.
+    pub fn from_yaml(yaml: &str) -> Result<Self> {
+        let document: Document = serde_yaml::from_str(yaml)?;
+        document.into_dataset(yaml)
+    }
+
+    /// The YAML authoring format of the dataset
+    pub fn to_yaml(&self) -> Result<String> {
+        Ok(serde_yaml::to_string(&Document::try_from(self)?)?)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Document {
+    name: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    doc: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    spec: Option<Spec>,
+    /// The name of the schema, if it differs from the name of the dataset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    schema: Option<String>,
+    /// The dotted paths of the tables or columns designating the protected entity
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    protected: Option<Vec<String>>,
+    /// The bound of the multiplicity of the privacy units
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_max_multiplicity: Option<f64>,
+    tables: Ordered<Table>,
+    /// The dotted paths of the tables, by dotted alias
+    #[serde(default, skip_serializing_if = "Ordered::is_empty")]
+    aliases: Ordered<String>,
+    #[serde(default, skip_serializing_if = "Ordered::is_empty")]
+    admin_columns: Ordered<Column>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Table {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    size: Option<Size>,
+    #[serde(default, skip_serializing_if = "is_false")]
+    public: bool,
+    columns: Ordered<Column>,
+}
+
+fn is_false(value: &bool) -> bool {
+    !value
+}
+
+/// The size of a table or the range of its size
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(untagged)]
+enum Size {
+    Exact(i64),
+    Range([i64; 2]),
+}
+
+/// A column as `<type> [<constraint>] [references <path>]`
+#[derive(Debug)]
+struct Column {
+    type_: type_::Type,
+    constraint: Option<&'static str>,
+    references: Option<Vec<String>>,
+}
+
+/// The spec of a dataset, in the JSON mapping of its protobuf message
+#[derive(Debug)]
+struct Spec(dataset::dataset::Spec);
+
+/// A mapping keeping the order of its entries
+#[derive(Debug)]
+struct Ordered<T>(Vec<(String, T)>);
+
+impl<T> Ordered<T> {
+    fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl<T> Default for Ordered<T> {
+    fn default() -> Self {
+        Ordered(vec![])
+    }
+}
+
+impl<T: Serialize> Serialize for Ordered<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.0.len()))?;
+        for (key, value) in &self.0 {
+            map.serialize_entry(key, value)?;
+        }
+        map.end()
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Ordered<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        struct Visitor<T>(PhantomData<T>);
+        impl<'de, T: Deserialize<'de>> de::Visitor<'de> for Visitor<T> {
+            type Value = Ordered<T>;
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a mapping")
+            }
+            fn visit_map<A: de::MapAccess<'de>>(
+                self,
+                mut map: A,
+            ) -> std::result::Result<Self::Value, A::Error> {
+                let mut entries: Vec<(String, T)> = vec![];
+                while let Some(key) = map.next_key::<String>()? {
+                    if entries.iter().any(|(k, _)| k == &key) {
+                        return Err(de::Error::custom(format!("duplicate key `{}`", key)));
+                    }
+                    entries.push((key, map.next_value()?));
+                }
+                Ok(Ordered(entries))
+            }
+        }
+        deserializer.deserialize_map(Visitor(PhantomData))
+    }
+}
+
+impl Serialize for Spec {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        use serde::ser::Error as _;
+        let json = print_to_string(&self.0).map_err(S::Error::custom)?;
+        serde_json::from_str::<serde_json::Value>(&json)
+            .map_err(S::Error::custom)?
+            .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Spec {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let value = serde_json::Value::deserialize(deserializer)?;
+        parse_from_str(&value.to_string())
+            .map(Spec)
+            .map_err(|err| de::Error::custom(format!("invalid dataset spec: {}", err)))
+    }
+}
+
+impl fmt::Display for Column {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.type_.to_compact_string())?;
+        if let Some(constraint) = self.constraint {
+            write!(f, " {}", constraint_keyword(constraint))?;
+        }
+        if let Some(references) = &self.references {
+            write!(f, " references {}", references.join("."))?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Column {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<Self> {
+        let mut parser = Parser::new(text);
+        let type_ = parser.type_()?;
+        let mut rest = parser.rest().trim();
+        let constraint = [
+            CONSTRAINT_PRIMARY_KEY,
+            CONSTRAINT_UNIQUE,
+            CONSTRAINT_NOT_NULL,
+        ]
+        .into_iter()
+        .find_map(|constraint| {
+            let tail = rest.strip_prefix(constraint_keyword(constraint))?;
+            rest = tail.trim_start();
+            Some(constraint)
+        });
+        let references = match rest.strip_prefix("references ") {
+            Some(path) => {
+                rest = "";
+                Some(path.trim().split('.').map(str::to_string).collect())
+            }
+            None => None,
+        };
+        if !rest.is_empty() {
+            return Err(Error::parsing_error(format!(
+                "column `{}`: unexpected `{}`, expected a constraint or `references <path>`",
+                text, rest
+            )));
+        }
+        Ok(Column {
+            type_,
+            constraint,
+            references,
+        })
+    }
+}
+
+impl Serialize for Column {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Column {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(de::Error::custom)
+    }
+}
+
+fn constraint_keyword(constraint: &str) -> &'static str {
+    match constraint {
+        CONSTRAINT_PRIMARY_KEY => "primary key",
+        CONSTRAINT_UNIQUE => "unique",
+        _ => "not null",
+    }
+}
+
+fn dotted_path(path: &str) -> Vec<String> {
+    path.split('.').map(str::to_string).collect()
+}
+
+/// The type of a column read from YAML: primary keys and not-null columns are required,
+/// primary keys and unique columns are marked in the type properties
+fn column_type(column: &Column) -> type_::Type {
+    let mut type_ = column.type_.clone();
+    if let Some(constraint) = column.constraint {
+        if constraint != CONSTRAINT_UNIQUE {
+            if let Some(type_::type_::Type::Optional(o)) = type_.type_.as_mut() {
+                let mut inner = o.take_type_();
+                inner.mut_properties().extend(type_.properties.drain());
+                type_ = inner;
+            }
+        }
+        if constraint != CONSTRAINT_NOT_NULL {
+            type_
+                .mut_properties()
+                .insert(CONSTRAINT.to_string(), constraint.to_string());
+        }
+    }
+    type_
+}
+
+impl Document {
+    /// The dataset of the document, errors being located in the `yaml` it was parsed from
+    fn into_dataset(self, yaml: &str) -> Result<Dataset> {
+        let schema_name = self.schema.unwrap_or_else(|| self.name.clone());
+        let mut data_type = type_::Type::new();
+        data_type.set_name("Union".to_string());
+        data_type.set_union(type_::type_::Union::new());
+        // The foreign keys, with the table and the column defining them
+        let mut foreign_keys: Vec<(&str, &str, Vec<String>, Vec<String>)> = vec![];
+        for (name, table) in &self.tables.0 {
+            let path = dotted_path(name);
+            let mut struct_ = type_::type_::Struct::new();
+            for (column_name, column) in &table.columns.0 {
+                if let Some(references) = &column.references {
+                    let column_path = path.iter().cloned().chain([column_name.clone()]).collect();
+                    foreign_keys.push((name, column_name, column_path, references.clone()));
+                }
+                let mut field = type_::type_::struct_::Field::new();
+                field.set_name(column_name.clone());
+                field.set_type(column_type(column));
+                struct_.fields.push(field);
+            }
+            let mut type_ = type_::Type::new();
+            type_.set_name("Struct".to_string());
+            type_.set_struct(struct_);
+            insert_table(&mut data_type, &path, type_);
+        }
+        if let Some((name, _)) = self
+            .tables
+            .0
+            .iter()
+            .find(|(_, table)| table.size.is_none())
+            .filter(|_| self.tables.0.iter().any(|(_, table)| table.size.is_some()))
+        {
+            return Err(located(
+                Error::other("Sizes must be given for all the tables or for none"),
+                yaml,
+                &["tables", name],
+            ));
+        }
+        let size = table_sizes(&self.tables).map(|sizes| {
+            let mut size = size::Size::new();
+            size.set_statistics(size_statistics(&data_type, &[], &sizes));
+            size
+        });
+        let mut schema = schema::Schema::new();
+        schema.set_name(schema_name);
+        if self.admin_columns.is_empty() {
+            schema.set_type(data_type);
+        } else {
+            let mut struct_ = type_::type_::Struct::new();
+            let columns = [(SARUS_DATA.to_string(), data_type)].into_iter().chain(
+                self.admin_columns
+                    .0
+                    .into_iter()
+                    .map(|(name, column)| (name, column.type_)),
+            );
+            for (name, type_) in columns {
+                let mut field = type_::type_::struct_::Field::new();
+                field.set_name(name);
+                field.set_type(type_);
+                struct_.fields.push(field);
+            }
+            let mut type_ = type_::Type::new();
+            type_.set_name("Struct".to_string());
+            type_.set_struct(struct_);
+            schema.set_type(type_);
+        }
+        for (table, column, pointing, pointed) in &foreign_keys {
+            schema
+                .check_foreign_key(pointing, pointed)
+                .map_err(|err| located(err, yaml, &["tables", table, "columns", column]))?;
+        }
+        schema.properties.insert(
+            FOREIGN_KEYS.to_string(),
+            serde_json::to_string(
+                &foreign_keys
+                    .into_iter()
+                    .map(|(_, _, pointing, pointed)| (pointing, pointed))
+                    .collect::<Vec<_>>(),
+            )
+            .unwrap(),
+        );
+        if let Some(multiplicity) = self.max_max_multiplicity {
+            schema
+                .properties
+                .insert(MAX_MAX_MULTIPLICITY.to_string(), multiplicity.to_string());
+        }
+        let mut dataset_ = dataset::Dataset::new();
+        dataset_.set_name(self.name);
+        dataset_.set_doc(self.doc);
+        if let Some(Spec(spec)) = self.spec {
+            dataset_.set_spec(spec);
+        }
+        let mut dataset = Dataset::new(dataset_, schema, size);
+        if let Some(protected) = self.protected {
+            let mut root = path::Path::new();
+            root.set_label(PROTECTED_ROOT.to_string());
+            for protected in &protected {
+                let mut entry = root.clone();
+                insert_path(&mut entry, &dotted_path(protected));
+                dataset
+                    .with_protected_path(entry)
+                    .map_err(|err| located(err, yaml, &["protected", protected]))?;
+                insert_path(&mut root, &dotted_path(protected));
+            }
+            dataset = dataset.with_protected_path(root)?;
+        }
+        for (name, table) in &self.tables.0 {
+            let at_table = |err| located(err, yaml, &["tables", name]);
+            if table.public {
+                dataset = dataset
+                    .with_public(Identifier::from(dotted_path(name)), true)
+                    .map_err(at_table)?;
+            }
+            if let Some(Size::Range([min, max])) = table.size {
+                dataset = dataset
+                    .with_size_range(Identifier::from(dotted_path(name)), min, max)
+                    .map_err(at_table)?;
+            }
+        }
+        for (alias, table) in &self.aliases.0 {
+            dataset = dataset
+                .with_alias(
+                    Identifier::from(dotted_path(alias)),
+                    Identifier::from(dotted_path(table)),
+                )
+                .map_err(|err| located(err, yaml, &["aliases", alias]))?;
+        }
+        Ok(dataset)
+    }
+}
+
+/// `err` located at the value of the `keys` of the `yaml` document, as serde_yaml errors are
+fn located(err: Error, yaml: &str, keys: &[&str]) -> Error {
+    let Some((line, column)) = location(yaml, keys) else {
+        return err;
+    };
+    let at = |message: String| format!("{} at line {} column {}", message.trim_end(), line, column);
+    match err {
+        Error::ParsingError(message) => Error::ParsingError(at(message)),
+        Error::MissingKeyError(message) => Error::MissingKeyError(at(message)),
+        Error::Other(message) => Error::Other(at(message)),
+    }
+}
+
+/// The line and column of the value at the `keys` of a block mapping, each key being searched
+/// in the block of the previous one. A last key missing from the keys of its block is searched
+/// in the values of the block, as the items of a sequence.
+fn location(yaml: &str, keys: &[&str]) -> Option<(usize, usize)> {
+    let lines: Vec<&str> = yaml.lines().collect();
+    let indent = |line: &str| line.len() - line.trim_start().len();
+    let is_content = |line: &str| !line.trim().is_empty() && !line.trim_start().starts_with('#');
+    // The block of the current key, as a range of lines, and its indentation
+    let mut block = 0..lines.len();
+    let mut parent_indent: Option<usize> = None;
+    let mut position = None;
+    for key in keys {
+        let key_line = block.clone().find(|&i| {
+            let line = lines[i];
+            let content = line.trim_start();
+            is_content(line)
+                && parent_indent.map_or(indent(line) == 0, |parent| indent(line) > parent)
+                && [
+                    format!("{}:", key),
+                    format!("\"{}\":", key),
+                    format!("'{}':", key),
+                ]
+                .iter()
+                .any(|prefix| content.starts_with(prefix.as_str()))
+        });
+        match key_line {
+            Some(i) => {
+                let key_indent = indent(lines[i]);
+                // The items of a block sequence may be at the indentation of their key
+                let end = (i + 1..block.end)
+                    .find(|&j| {
+                        let line = lines[j];
+                        is_content(line)
+                            && (indent(line) < key_indent
+                                || indent(line) == key_indent
+                                    && !line.trim_start().starts_with('-'))
+                    })
+                    .unwrap_or(block.end);
+                position = Some((i + 1, key_indent + 1));
+                block = i..end;
+                parent_indent = Some(key_indent);
+            }
+            None => {
+                return block.clone().find_map(|i| {
+                    let line = lines[i];
+                    let column = line.match_indices(key).find_map(|(start, _)| {
+                        let before = line[..start].chars().next_back();
+                        let after = line[start + key.len()..].chars().next();
+                        let boundary = |c: Option<char>| {
+                            c.is_none_or(|c| c.is_whitespace() || "[]{},:-\"'".contains(c))
+                        };
+                        (boundary(before) && boundary(after)).then_some(start)
+                    })?;
+                    Some((i + 1, line[..column].chars().count() + 1))
+                });
+            }
+        }
+    }
+    position
+}
+
+/// The sizes of the tables, by table path
+type TableSizes = Vec<(Vec<String>, i64)>;
+
+/// The size of each table, if the tables have sizes.
+/// A size range gives its upper bound, the range being set afterwards.
+fn table_sizes(tables: &Ordered<Table>) -> Option<TableSizes> {
+    let sizes: TableSizes = tables
+        .0
+        .iter()
+        .filter_map(|(name, table)| {
+            let size = match table.size? {
+                Size::Exact(size) => size,
+                Size::Range([_, max]) => max,
+            };
+            Some((dotted_path(name), size))
+        })
+        .collect();
+    (!sizes.is_empty()).then_some(sizes)
+}
+
+/// The Size statistics of the tables below `type_`, at `path`
+fn size_statistics(
+    type_: &type_::Type,
+    path: &[String],
+    sizes: &[(Vec<String>, i64)],
+) -> statistics::Statistics {
+    match type_.type_.as_ref() {
+        Some(type_::type_::Type::Union(u)) => union_statistics(
+            u.fields
+                .iter()
+                .map(|f| {
+                    let path: Vec<String> =
+                        path.iter().cloned().chain([f.name().to_string()]).collect();
+                    (
+                        f.name().to_string(),
+                        size_statistics(f.type_(), &path, sizes),
+                    )
+                })
+                .collect(),
+        ),
+        _ => table_statistics(
+            sizes
+                .iter()
+                .find_map(|(table, size)| (table == path).then_some(*size))
+                .unwrap_or_default(),
+        ),
+    }
+}
+
+/// Insert the labels of `path` below `root`
+fn insert_path(root: &mut path::Path, labels: &[String]) {
+    let Some((label, tail)) = labels.split_first() else {
+        return;
+    };
+    let position = match root.paths.iter().position(|p| p.label() == label) {
+        Some(position) => position,
+        None => {
+            let mut child = path::Path::new();
+            child.set_label(label.clone());
+            root.paths.push(child);
+            root.paths.len() - 1
+        }
+    };
+    insert_path(&mut root.paths[position], tail);
+}
+
+/// The dotted paths of the leaves below `path`
+fn leaves(path: &path::Path, prefix: &[String], leaves_: &mut Vec<String>) {
+    for child in &path.paths {
+        let mut prefix = prefix.to_vec();
+        if child.label() != SARUS_DATA {
+            prefix.push(child.label().to_string());
+        }
+        if child.paths.is_empty() {
+            leaves_.push(prefix.join("."));
+        } else {
+            leaves(child, &prefix, leaves_);
+        }
+    }
+}
+
+impl TryFrom<&Dataset> for Document {
+    type Error = Error;
+
+    /// The document of a dataset, failing on what the YAML format cannot express.
+    /// Only the sizes of the tables are kept from the size statistics.
+    fn try_from(dataset: &Dataset) -> Result<Self> {
+        check_properties(dataset)?;
+        let schema_name = dataset.schema().name().to_string();
+        let foreign_keys: Vec<(Vec<String>, Vec<String>)> =
+            parse_property(dataset.schema().properties(), FOREIGN_KEYS).unwrap_or_default();
+        let public_tables = dataset.public_tables();
+        let max_max_multiplicity = match dataset.schema().properties().get(MAX_MAX_MULTIPLICITY) {
+            Some(property) => match property.parse::<f64>() {
+                Ok(multiplicity) if multiplicity.to_string() == *property => Some(multiplicity),
+                _ => {
+                    return Err(Error::other(format!(
+                        "The `{}` property of the schema cannot be written in YAML as `{}`",
+                        MAX_MAX_MULTIPLICITY, property
+                    )))
+                }
+            },
+            None => None,
+        };
+        // The column of `type_` at `path`, admin columns having an empty path
+        let column = |name: &str, path: &[String], type_: &type_::Type| -> Result<Column> {
+            let column = Column {
+                type_: type_.clone(),
+                constraint: type_.properties.get(CONSTRAINT).and_then(
+                    |constraint| match constraint.as_str() {
+                        CONSTRAINT_PRIMARY_KEY => Some(CONSTRAINT_PRIMARY_KEY),
+                        CONSTRAINT_UNIQUE => Some(CONSTRAINT_UNIQUE),
+                        _ => None,
+                    },
+                ),
+                references: foreign_keys
+                    .iter()
+                    .find_map(|(pointing, pointed)| (pointing == path).then(|| pointed.clone())),
+            };
+            let read = column.to_string().parse::<Column>().ok().map(|read| {
+                if path.is_empty() {
+                    read.type_
+                } else {
+                    column_type(&read)
+                }
+            });
+            if read.as_ref() != Some(type_) {
+                return Err(Error::other(format!(
+                    "Column `{}` cannot be written in YAML as `{}`, which loses its Id base or \
+                     reference, Text encoding, temporal format or base, Enum values or properties",
+                    name, column
+                )));
+            }
+            Ok(column)
+        };
+        // The dotted names of the tables, by table path
+        let mut table_names: Vec<(Vec<String>, String)> = vec![];
+        let tables = table_structs(dataset.schema_type_data(), dataset.size_statistics())
+            .into_iter()
+            .map(|(identifier, struct_, statistics)| {
+                let relation = dataset.full_path(&identifier);
+                let path: Vec<String> = if identifier.is_empty() {
+                    relation.to_vec()
+                } else {
+                    identifier.to_vec()
+                };
+                let size = statistics.map(|statistics| match super::size_range(statistics) {
+                    (min, max) if min == max => Size::Exact(min),
+                    (min, max) => Size::Range([min, max]),
+                });
+                let columns = struct_
+                    .fields
+                    .iter()
+                    .map(|f| {
+                        let column_path: Vec<String> = identifier
+                            .iter()
+                            .cloned()
+                            .chain([f.name().to_string()])
+                            .collect();
+                        let name = format!("{}.{}", path.join("."), f.name());
+                        Ok((
+                            f.name().to_string(),
+                            column(&name, &column_path, f.type_())?,
+                        ))
+                    })
+                    .collect::<Result<_>>()?;
+                table_names.push((identifier.to_vec(), path.join(".")));
+                Ok((
+                    path.join("."),
+                    Table {
+                        size,
+                        public: public_tables.contains(&relation),
+                        columns: Ordered(columns),
+                    },
+                ))
+            })
+            .collect::<Result<_>>()?;
+        let aliases = dataset
+            .aliases()
+            .into_iter()
+            .map(|(alias, table)| {
+                let name = table_names
+                    .iter()
+                    .find_map(|(path, name)| (path.as_slice() == table.as_slice()).then_some(name))
+                    .filter(|_| alias.iter().all(|label| !label.contains('.')))
+                    .ok_or_else(|| {
+                        Error::other(format!(
+                            "The alias `{}` of `{}` cannot be written in YAML",
+                            alias, table
+                        ))
+                    })?;
+                Ok((alias.to_vec().join("."), name.clone()))
+            })
+            .collect::<Result<_>>()?;
+        let admin_columns = dataset
+            .admin_names_and_types()
+            .into_iter()
+            .map(|(name, type_)| Ok((name.to_string(), column(name, &[], type_)?)))
+            .collect::<Result<_>>()?;
+        let protected = dataset.schema().protected.as_ref().map(|protected| {
+            let mut paths = vec![];
+            leaves(protected, &[], &mut paths);
+            paths
+        });
+        Ok(Document {
+            name: dataset.dataset().name().to_string(),
+            doc: dataset.dataset().doc().to_string(),
+            spec: dataset.dataset().spec.as_ref().cloned().map(Spec),
+            schema: (schema_name != dataset.dataset().name()).then_some(schema_name),
+            protected,
+            max_max_multiplicity,
+            tables: Ordered(tables),
+            aliases: Ordered(aliases),
+            admin_columns: Ordered(admin_columns),
+        })
+    }
+}
+
+/// Check the YAML format can express the properties of the schema and of its types:
+/// the foreign keys, aliases and maximum multiplicity of the schema and the public fields
+/// of the Unions of tables, the labels of the tables having no dots
+fn check_properties(dataset: &Dataset) -> Result<()> {
+    fn check_type(type_: &type_::Type, path: &[&str]) -> Result<()> {
+        let (allowed, fields): (&[&str], Vec<(&str, &type_::Type)>) = match type_.type_.as_ref() {
+            Some(type_::type_::Type::Union(u)) => (
+                &[PUBLIC_FIELDS],
+                u.fields.iter().map(|f| (f.name(), f.type_())).collect(),
+            ),
+            _ => (&[], vec![]),
+        };
+        if let Some(key) = type_
+            .properties
+            .keys()
+            .find(|key| !allowed.contains(&key.as_str()))
+        {
+            return Err(Error::other(format!(
+                "The `{}` property of `{}` cannot be written in YAML",
+                key,
+                path.join(".")
+            )));
+        }
+        for (name, type_) in fields {
+            if name.contains('.') {
+                return Err(Error::other(format!(
+                    "The dotted name of `{}` cannot be written in YAML",
+                    name
+                )));
+            }
+            let path: Vec<&str> = path.iter().copied().chain([name]).collect();
+            check_type(type_, &path)?;
+        }
+        Ok(())
+    }
+    let schema = dataset.schema();
+    if let Some(key) = schema
+        .properties
+        .keys()
+        .find(|key| ![FOREIGN_KEYS, ALIASES, MAX_MAX_MULTIPLICITY].contains(&key.as_str()))
+    {
+        return Err(Error::other(format!(
+            "The `{}` property of the schema cannot be written in YAML",
+            key
+        )));
+    }
+    if dataset.schema_has_admin_columns() && !dataset.schema_type().properties.is_empty() {
+        return Err(Error::other(
+            "The properties of the type of the schema cannot be written in YAML",
+        ));
+    }
+    check_type(dataset.schema_type_data(), &[schema.name()])
+}
+
+/// The `[min, max]` bounds of a scalar type
+type Bounds = Option<(String, String)>;
+
+/// A parser of the compact notation of types
+struct Parser<'a> {
+    text: &'a str,
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(text: &'a str) -> Self {
+        Parser { text, position: 0 }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.text[self.position..]
+    }
+
+    fn error(&self, expected: &str) -> Error {
+        Error::parsing_error(format!(
+            "type `{}`: expected {} at character {}",
+            self.text,
+            expected,
+            self.position + 1
+        ))
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.position += rest.len() - rest.trim_start().len();
+    }
+
+    /// Consume `token` if it comes next
+    fn eat(&mut self, token: &str) -> bool {
+        self.skip_whitespace();
+        if self.rest().starts_with(token) {
+            self.position += token.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, token: &str) -> Result<()> {
+        if self.eat(token) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("`{}`", token)))
+        }
+    }
+
+    fn word(&mut self) -> Result<&'a str> {
+        self.skip_whitespace();
+        let rest = self.rest();
+        let length = rest
+            .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .unwrap_or(rest.len());
+        if length == 0 {
+            return Err(self.error("a name"));
+        }
+        self.position += length;
+        Ok(&rest[..length])
+    }
+
+    fn number<T: FromStr>(&mut self) -> Result<T> {
+        let start = self.position;
+        let word = self.word()?;
+        word.parse().map_err(|_| {
+            self.position = start;
+            self.error("a number")
+        })
+    }
+
+    /// A value ending before one of `ends`, quoted values may contain any character
+    fn value(&mut self, ends: &[char]) -> Result<String> {
+        self.skip_whitespace();
+        if !self.rest().starts_with('"') {
+            let rest = self.rest();
+            let length = rest.find(ends).unwrap_or(rest.len());
+            self.position += length;
+            return Ok(rest[..length].trim().to_string());
+        }
+        let mut value = String::new();
+        let mut chars = self.rest().char_indices().skip(1);
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.position += i + 1;
+                    return Ok(value);
+                }
+                '\\' => match chars.next().map(|(_, c)| c) {
+                    Some('n') => value.push('\n'),
+                    Some('r') => value.push('\r'),
+                    Some('t') => value.push('\t'),
+                    Some('0') => value.push('\0'),
+                    Some('u') => {
+                        let code: String = chars
+                            .by_ref()
+                            .map(|(_, c)| c)
+                            .skip_while(|c| *c == '{')
+                            .take_while(|c| *c != '}')
+                            .collect();
+                        match u32::from_str_radix(&code, 16).ok().and_then(char::from_u32) {
+                            Some(c) => value.push(c),
+                            None => return Err(self.error("a unicode escape")),
+                        }
+                    }
+                    Some(c) => value.push(c),
+                    None => break,
+                },
+                c => value.push(c),
+            }
+        }
+        Err(self.error("a closing quote"))
+    }
+
+    /// The bounds `[min, max]` or the possible values `{a,b}` following a scalar type
+    fn domain(&mut self) -> Result<(Bounds, Vec<String>)> {
+        if self.eat("[") {
+            let min = self.value(&[','])?;
+            self.expect(",")?;
+            let max = self.value(&[']'])?;
+            self.expect("]")?;
+            Ok((Some((min, max)), vec![]))
+        } else if self.eat("{") {
+            let mut values = vec![];
+            if !self.eat("}") {
+                loop {
+                    values.push(self.value(&[',', '}'])?);
+                    if self.eat("}") {
+                        break;
+                    }
+                    self.expect(",")?;
+                }
+            }
+            Ok((None, values))
+        } else {
+            Ok((None, vec![]))
+        }
+    }
+
+    fn fields(&mut self) -> Result<Vec<(String, type_::Type)>> {
+        let mut fields = vec![];
+        self.expect("{")?;
+        if self.eat("}") {
+            return Ok(fields);
+        }
+        loop {
+            let name = self.value(&[':'])?;
+            self.expect(":")?;
+            fields.push((name, self.type_()?));
+            if self.eat("}") {
+                return Ok(fields);
+            }
+            self.expect(",")?;
+        }
+    }
+
+    fn type_(&mut self) -> Result<type_::Type> {
+        use type_::type_::{float, integer};
+        let start = self.position;
+        let name = self.word()?;
+        let mut type_ = type_::Type::new();
+        match name {
+            "optional" => {
+                self.expect("<")?;
+                let mut optional = type_::type_::Optional::new();
+                optional.set_type(self.type_()?);
+                self.expect(">")?;
+                type_.set_name("Optional".to_string());
+                type_.set_optional(optional);
+            }
+            "list" => {
+                self.expect("<")?;
+                let mut list = type_::type_::List::new();
+                list.set_type(self.type_()?);
+                self.expect(",")?;
+                list.set_max_size(self.number()?);
+                self.expect(">")?;
+                type_.set_name("List".to_string());
+                type_.set_list(list);
+            }
+            "array" => {
+                self.expect("<")?;
+                let mut array = type_::type_::Array::new();
+                array.set_type(self.type_()?);
+                self.expect(",")?;
+                let shape = self.word()?;
+                array.shape = shape
+                    .split('x')
+                    .map(|size| size.parse())
+                    .collect::<std::result::Result<_, _>>()
+                    .map_err(|_| self.error("a shape as `2x3`"))?;
+                self.expect(">")?;
+                type_.set_name("Array".to_string());
+                type_.set_array(array);
+            }
+            "struct" => {
+                let mut struct_ = type_::type_::Struct::new();
+                for (name, field_type) in self.fields()? {
+                    let mut field = type_::type_::struct_::Field::new();
+                    field.set_name(name);
+                    field.set_type(field_type);
+                    struct_.fields.push(field);
+                }
+                type_.set_name("Struct".to_string());
+                type_.set_struct(struct_);
+            }
+            "union" => {
+                let mut union = type_::type_::Union::new();
+                for (name, field_type) in self.fields()? {
+                    let mut field = type_::type_::union::Field::new();
+                    field.set_name(name);
+                    field.set_type(field_type);
+                    union.fields.push(field);
+                }
+                type_.set_name("Union".to_string());
+                type_.set_union(union);
+            }
+            "null" => {
+                type_.set_name("Null".to_string());
+                type_.set_null(type_::type_::Null::new());
+            }
+            "unit" => {
+                type_.set_name("Unit".to_string());
+                type_.set_unit(type_::type_::Unit::new());
+            }
+            "bytes" => {
+                type_.set_name("Bytes".to_string());
+                type_.set_bytes(type_::type_::Bytes::new());
+            }
+            "id" => {
+                let mut id = type_::type_::Id::new();
+                id.set_base(type_::type_::id::Base::STRING);
+                id.unique = self.eat("(unique)");
+                type_.set_name("Id".to_string());
+                type_.set_id(id);
+            }
+            "duration" => {
+                self.expect("<")?;
+                let unit = self.word()?.to_string();
+                self.expect(">")?;
+                let (bounds, values) = self.domain()?;
+                let (min, max, possible_values) =
+                    self.range(bounds, values, (i64::MIN, i64::MAX))?;
+                let mut duration = type_::type_::Duration::new();
+                duration.set_unit(unit);
+                duration.set_min(min);
+                duration.set_max(max);
+                duration.set_possible_values(possible_values);
+                type_.set_name("Duration".to_string());
+                type_.set_duration(duration);
+            }
+            "bool" => {
+                let (bounds, values) = self.domain()?;
+                if bounds.is_some() {
+                    return Err(self.error("possible values"));
+                }
+                type_.set_name("Boolean".to_string());
+                type_.set_boolean(type_::type_::Boolean::new());
+                if !values.is_empty() {
+                    let values = values
+                        .iter()
+                        .map(|value| value.parse())
+                        .collect::<std::result::Result<Vec<bool>, _>>()
+                        .map_err(|_| self.error("`true` or `false`"))?;
+                    type_.mut_properties().insert(
+                        POSSIBLE_VALUES.to_string(),
+                        serde_json::to_string(&values).unwrap(),
+                    );
+                }
+            }
+            "int" | "int32" | "int16" | "int8" | "uint64" | "uint32" | "uint16" | "uint8" => {
+                let base = match name {
+                    "int" => integer::Base::INT64,
+                    "int32" => integer::Base::INT32,
+                    "int16" => integer::Base::INT16,
+                    "int8" => integer::Base::INT8,
+                    "uint64" => integer::Base::UINT64,
+                    "uint32" => integer::Base::UINT32,
+                    "uint16" => integer::Base::UINT16,
+                    _ => integer::Base::UINT8,
+                };
+                let (bounds, values) = self.domain()?;
+                let (min, max, possible_values) =
+                    self.range(bounds, values, integer_base_range(base))?;
+                type_ = integer_type(base, min, max);
+                type_.mut_integer().set_possible_values(possible_values);
+            }
+            "float" | "float32" | "float16" => {
+                let base = match name {
+                    "float" => float::Base::FLOAT64,
+                    "float32" => float::Base::FLOAT32,
+                    _ => float::Base::FLOAT16,
+                };
+                let (bounds, values) = self.domain()?;
+                let max = float_base_max(base);
+                let (min, max, possible_values) = self.range(bounds, values, (-max, max))?;
+                type_ = float_type(base, min, max);
+                type_.mut_float().set_possible_values(possible_values);
+            }
+            "text" => {
+                let (bounds, values) = self.domain()?;
+                if bounds.is_some() {
+                    return Err(self.error("possible values"));
+                }
+                let mut text = type_::type_::Text::new();
+                text.set_encoding("UTF-8".to_string());
+                text.set_possible_values(values);
+                type_.set_name("Text".to_string());
+                type_.set_text(text);
+            }
+            "enum" => {
+                let (bounds, values) = self.domain()?;
+                if bounds.is_some() || values.is_empty() {
+                    return Err(self.error("the names of the values"));
+                }
+                let mut enum_ = type_::type_::Enum::new();
+                enum_.name_values = values
+                    .into_iter()
+                    .enumerate()
+                    .map(|(value, name)| {
+                        let mut name_value = type_::type_::enum_::NameValue::new();
+                        name_value.set_name(name);
+                        name_value.set_value(value as i64);
+                        name_value
+                    })
+                    .collect();
+                type_.set_name("Enum".to_string());
+                type_.set_enum(enum_);
+            }
+            "date" => {
+                let (min, max, possible_values, format) = self.temporal::<NaiveDate>()?;
+                let mut date = type_::type_::Date::new();
+                date.set_format(format);
+                date.set_min(min);
+                date.set_max(max);
+                date.set_possible_values(possible_values);
+                type_.set_name("Date".to_string());
+                type_.set_date(date);
+            }
+            "time" => {
+                let (min, max, possible_values, format) = self.temporal::<NaiveTime>()?;
+                let mut time = type_::type_::Time::new();
+                time.set_format(format);
+                time.set_min(min);
+                time.set_max(max);
+                time.set_possible_values(possible_values);
+                type_.set_name("Time".to_string());
+                type_.set_time(time);
+            }
+            "datetime" => {
+                let (min, max, possible_values, format) = self.temporal::<NaiveDateTime>()?;
+                let mut datetime = type_::type_::Datetime::new();
+                datetime.set_format(format);
+                datetime.set_min(min);
+                datetime.set_max(max);
+                datetime.set_possible_values(possible_values);
+                type_.set_name("Datetime".to_string());
+                type_.set_datetime(datetime);
+            }
+            _ => {
+                self.position = start;
+                return Err(self.error("a type"));
+            }
+        }
+        Ok(type_)
+    }
+
+    /// The range and the possible values of a numeric type, the range of the possible values
+    /// if they are given, the range of the base if nothing is
+    fn range<T: FromStr + PartialOrd + Copy>(
+        &self,
+        bounds: Bounds,
+        values: Vec<String>,
+        (base_min, base_max): (T, T),
+    ) -> Result<(T, T, Vec<T>)> {
+        let parse = |value: &str| -> Result<T> {
+            value
+                .parse()
+                .ok()
+                .filter(|value| base_min <= *value && *value <= base_max)
+                .ok_or_else(|| {
+                    self.error(&format!(
+                        "a number in the range of the type, got `{}`",
+                        value
+                    ))
+                })
+        };
+        let values = values
+            .iter()
+            .map(|value| parse(value))
+            .collect::<Result<Vec<T>>>()?;
+        let (min, max) = match bounds {
+            Some((min, max)) => (parse(&min)?, parse(&max)?),
+            None => values
+                .iter()
+                .fold((base_max, base_min), |(min, max), value| {
+                    (
+                        if *value < min { *value } else { min },
+                        if *value > max { *value } else { max },
+                    )
+                }),
+        };
+        if values.is_empty() && min > max {
+            Ok((base_min, base_max, values))
+        } else if min > max {
+            Err(self.error("a min lower than the max"))
+        } else {
+            Ok((min, max, values))
+        }
+    }
+
+    /// The bounds, possible values and format of a temporal type,
+    /// values being checked against the default format
+    fn temporal<T: Temporal>(&mut self) -> Result<(String, String, Vec<String>, String)> {
+        let (bounds, values) = self.domain()?;
+        let format = T::DEFAULT_FORMAT.to_string();
+        for value in bounds
+            .iter()
+            .flat_map(|(min, max)| [min, max])
+            .chain(&values)
+        {
+            T::parse(value, &format).map_err(|_| {
+                self.error(&format!(
+                    "a value with format `{}`, got `{}`",
+                    format, value
+                ))
+            })?;
+        }
+        let (min, max) = match bounds {
+            Some(bounds) => bounds,
+            None => match (values.first(), values.last()) {
+                (Some(min), Some(max)) => (min.clone(), max.clone()),
+                _ => (String::new(), String::new()),
+            },
+        };
+        Ok((min, max, values, format))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    const YAML: &str = r#"name: shop
+doc: The orders of an online shop
+spec:
+  sql:
+    tables:
+    - schema: public
+      table: users
+    uri: postgresql://localhost/shop
+protected:
+- users
+tables:
+  users:
+    size: 100
+    columns:
+      id: int[0, 1000] primary key
+      city: optional<text{"Paris","Lyon"}>
+      status: enum{"active","on hold"}
+  orders:
+    size:
+    - 1000
+    - 2000
+    public: true
+    columns:
+      user_id: int[0, 1000] references users.id
+      at: datetime[2020-01-01 00:00:00, 2030-01-01 00:00:00]
+      amount: float[0, 1.5]
+      tags: list<text, 10>
+      length: duration<ms>[0, 3600000]
+      paid: bool{true}
+admin_columns:
+  sarus_is_public: bool
+  sarus_privacy_unit: optional<id>
+  sarus_weights: float[0, 1]
+"#;
+
+    #[test]
+    fn test_yaml() -> Result<()> {
+        let dataset = Dataset::from_yaml(YAML)?;
+        println!("{}", dataset);
+        assert_eq!(dataset.dataset().doc(), "The orders of an online shop");
+        assert_eq!(dataset.dataset().spec().sql().tables[0].table(), "users");
+        assert_eq!(dataset.relations().len(), 2);
+        assert_eq!(
+            dataset.foreign_keys()[0].referred_relation,
+            Identifier::from(["shop", "users"])
+        );
+        assert_eq!(
+            dataset.public_tables(),
+            vec![Identifier::from(["shop", "orders"])]
+        );
+        assert_eq!(dataset.protected_path().paths[0].label(), "users");
+        assert_eq!(dataset.to_yaml()?, YAML);
+        Ok(())
+    }
+
+    #[test]
+    fn test_yaml_errors() {
+        let error =
+            Dataset::from_yaml("name: shop\ntables:\n  users:\n    columns:\n      id: int[0,\n")
+                .unwrap_err()
+                .to_string();
+        println!("{}", error);
+        assert!(error.contains("line 5 column"));
+        assert!(error.contains("expected `]`"));
+        let error =
+            Dataset::from_yaml("name: shop\ntables:\n  users:\n    columns:\n      id: integer\n")
+                .unwrap_err()
+                .to_string();
+        assert!(error.contains("expected a type at character 1"));
+        for (yaml, location) in [
+            (
+                "name: shop\ntables:\n  users:\n    size: 10\n    columns: {}\n  orders:\n    columns: {}\n",
+                "line 6 column 3",
+            ),
+            (
+                "name: shop\ntables:\n  orders:\n    columns:\n      user_id: int references users.id\n",
+                "line 5 column 7",
+            ),
+            (
+                "name: shop\nprotected:\n- orders\n- users\ntables:\n  orders:\n    columns: {}\n",
+                "line 4 column 3",
+            ),
+            (
+                "name: shop\ntables:\n  users:\n    columns: {}\naliases:\n  people: orders\n",
+                "line 6 column 3",
+            ),
+        ] {
+            let error = Dataset::from_yaml(yaml).unwrap_err().to_string();
+            println!("{}", error);
+            assert!(error.contains(location));
+        }
+    }
+
+    const TABLES: &str = r#"
+        {"name": "users", "type": {"name": "Struct", "struct": {"fields": [
+            {"name": "id", "type": {"name": "Integer", "properties": {"_CONSTRAINT_": "_PRIMARY_KEY_"},
+                "integer": {"base": "INT32", "min": "0", "max": "1000"}}},
+            {"name": "city", "type": {"name": "Optional", "optional": {"type": {"name": "Text",
+                "text": {"encoding": "UTF-8", "possible_values": ["Paris", "Lyon"]}}}}},
+            {"name": "status", "type": {"name": "Enum", "enum": {"name_values": [
+                {"name": "active", "value": "0"}, {"name": "on hold", "value": "1"}]}}},
+            {"name": "joined", "type": {"name": "Date", "date": {"format": "%Y-%m-%d", "min": "2020-01-01", "max": "2030-01-01"}}},
+            {"name": "token", "type": {"name": "Id", "properties": {"_CONSTRAINT_": "_UNIQUE_"},
+                "id": {"base": "STRING", "unique": true}}}
+        ]}}},
+        {"name": "archive", "type": {"name": "Union", "union": {"fields": [
+            {"name": "orders", "type": {"name": "Struct", "struct": {"fields": [
+                {"name": "user_id", "type": {"name": "Integer", "integer": {"base": "INT64", "min": "0", "max": "1000"}}},
+                {"name": "at", "type": {"name": "Datetime", "datetime": {"format": "%Y-%m-%d %H:%M:%S%.f",
+                    "min": "2020-01-01 00:00:00", "max": "2030-01-01 00:00:00"}}},
+                {"name": "amount", "type": {"name": "Float", "float": {"base": "FLOAT32", "min": 0, "max": 1.5}}},
+                {"name": "tags", "type": {"name": "List", "list": {"type": {"name": "Text", "text": {"encoding": "UTF-8"}}, "max_size": "10"}}},
+                {"name": "length", "type": {"name": "Duration", "duration": {"unit": "ms", "min": "0", "max": "3600000"}}}
+            ]}}}
+        ]}}}
+    "#;
+
+    const SIZE: &str = r#"
+    {
+        "statistics": {"union": {"name": "Union", "size": "2100", "fields": [
+            {"name": "users", "statistics": {"struct": {"name": "Struct", "size": "100"}}},
+            {"name": "archive", "statistics": {"union": {"name": "Union", "size": "2000", "fields": [
+                {"name": "orders", "statistics": {"struct": {"name": "Struct", "size": "2000"}}}
+            ]}}}
+        ]}}
+    }
+    "#;
+
+    fn dataset() -> Result<Dataset> {
+        let schema = format!(
+            r#"{{
+                "name": "shop",
+                "protected": {{"label": "data", "paths": [{{"label": "users"}}]}},
+                "properties": {{
+                    "foreign_keys": "[[[\"archive\",\"orders\",\"user_id\"],[\"users\",\"id\"]]]",
+                    "max_max_multiplicity": "3"
+                }},
+                "type": {{"name": "Struct", "struct": {{"fields": [
+                    {{"name": "sarus_data", "type": {{"name": "Union", "union": {{"fields": [{}]}}}}}},
+                    {{"name": "sarus_is_public", "type": {{"name": "Boolean", "boolean": {{}}}}}},
+                    {{"name": "sarus_privacy_unit", "type": {{"name": "Optional", "optional": {{"type": {{"name": "Id", "id": {{"base": "STRING"}}}}}}}}}},
+                    {{"name": "sarus_weights", "type": {{"name": "Float", "float": {{"min": 0, "max": 1}}}}}}
+                ]}}}}
+            }}"#,
+            TABLES
+        );
+        Ok(Dataset::new(
+            parse_from_str(r#"{"name": "shop", "doc": "The orders of an online shop"}"#)?,
+            parse_from_str(&schema)?,
+            Some(parse_from_str(SIZE)?),
+        )
+        .with_public(["archive", "orders"], true)?
+        .with_size_range(["archive", "orders"], 1000, 2000)?
+        .with_alias(["orders"], ["archive", "orders"])?)
+    }
+
+    #[test]
+    fn test_yaml_round_trip() -> Result<()> {
+        let dataset = dataset()?;
+        let yaml = dataset.to_yaml()?;
+        println!("{}", yaml);
+        let read = Dataset::from_yaml(&yaml)?;
+        assert_eq!(read.dataset(), dataset.dataset());
+        assert_eq!(read.schema(), dataset.schema());
+        assert_eq!(read.size(), dataset.size());
+        assert_eq!(read.to_yaml()?, yaml);
+        // A public Union of tables is written as public tables
+        let dataset = dataset
+            .with_public(["archive", "orders"], false)?
+            .with_public(["archive"], true)?;
+        let read = Dataset::from_yaml(&dataset.to_yaml()?)?;
+        assert_eq!(read.public_tables(), dataset.public_tables());
+        Ok(())
+    }
+
+    #[test]
+    fn test_yaml_lossy() -> Result<()> {
+        let dataset = dataset()?;
+        for (lossless, lossy) in [
+            (
+                r#""base": "STRING", "unique""#,
+                r#""base": "INT64", "unique""#,
+            ),
+            (
+                r#""encoding": "UTF-8", "possible_values""#,
+                r#""encoding": "ASCII", "possible_values""#,
+            ),
+            (r#""value": "1"}"#, r#""value": "2"}"#),
+            (
+                r#""format": "%Y-%m-%d %H:%M:%S%.f""#,
+                r#""format": "%Y-%m-%d %H:%M:%S""#,
+            ),
+            (
+                r#"{"name": "Date", "date""#,
+                r#"{"name": "Date", "properties": {"doc": "Joining day"}, "date""#,
+            ),
+        ] {
+            let schema = print_to_string(dataset.schema())?;
+            let lossy_schema = TABLES.replace(lossless, lossy);
+            assert_ne!(lossy_schema, TABLES);
+            let schema = schema.replace(
+                &print_to_string(dataset.schema_type_data())?,
+                &print_to_string(&parse_from_str::<type_::Type>(&format!(
+                    r#"{{"name": "Union", "union": {{"fields": [{}]}}}}"#,
+                    lossy_schema
+                ))?)?,
+            );
+            let lossy = Dataset::new(
+                dataset.dataset().clone(),
+                parse_from_str(&schema)?,
+                dataset.size().cloned(),
+            );
+            let error = lossy.to_yaml().unwrap_err().to_string();
+            println!("{}", error);
+            assert!(error.contains("cannot be written in YAML"));
+        }
+        // The maximum multiplicity is written as a number and aliases as dotted paths
+        let mut schema = dataset.schema().clone();
+        schema
+            .properties
+            .insert("max_max_multiplicity".to_string(), "1.50".to_string());
+        let lossy = Dataset::new(dataset.dataset().clone(), schema, None);
+        assert!(lossy.to_yaml().is_err());
+        let lossy = dataset.with_alias(["old.orders"], ["users"])?;
+        assert!(lossy.to_yaml().is_err());
+        Ok(())
+    }
+}
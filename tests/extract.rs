@@ -3,7 +3,12 @@
 //! Tests on the `extract` dataset
 
 use itertools::Itertools;
-use qrlew::{ast::Query, display::Dot as _};
+use qrlew::{
+    ast::Query,
+    data_type::{DataType, DataTyped as _},
+    display::Dot as _,
+    relation::Variant as _,
+};
 use qrlew_sarus::data_spec::Dataset;
 
 const DATASET: &str = r#"{"@type": "sarus_data_spec/sarus_data_spec.Dataset", "uuid": "e9cb9391ca184e89897f49bd75387a46", "name": "Transformed", "spec": {"transformed": {"transform": "98f18c2b0beb406088193dab26e24552", "arguments": [], "named_arguments": {}}}, "properties": {}, "doc": "This ia a demo dataset for testing purpose"}"#;
@@ -20,3 +25,48 @@ fn test_dataset() {
         println!("{}", Query::from(relation.as_ref()));
     }
 }
+
+#[test]
+fn test_dataset_with_range() {
+    let dataset = Dataset::parse_from_dataset_schema_size(DATASET, SCHEMA, SIZE).expect("dataset");
+    let dataset = dataset
+        .with_range(["extract", "beacon", "RSSI"], -120., 0.)
+        .expect("with_range");
+    let relations = dataset.relations();
+    let beacon = relations
+        .get(&["extract", "extract", "beacon"].map(String::from))
+        .expect("beacon");
+    assert_eq!(
+        beacon.schema().field("RSSI").unwrap().data_type(),
+        DataType::integer_interval(-120, 0)
+    );
+    assert!(dataset
+        .with_range(["extract", "beacons", "RSSI"], -120., 0.)
+        .is_err());
+    // Bound the timestamps with the format of the column
+    let dataset = dataset
+        .with_range(
+            ["extract", "beacon", "検知日時"],
+            "2023-01-01 00:00:00",
+            "2023-12-31 23:59:59",
+        )
+        .expect("with_range");
+    let relations = dataset.relations();
+    let beacon = relations
+        .get(&["extract", "extract", "beacon"].map(String::from))
+        .expect("beacon");
+    println!("{}", beacon.schema());
+    assert!(dataset
+        .with_range(
+            ["extract", "beacon", "検知日時"],
+            "2023-01-01",
+            "2023-12-31"
+        )
+        .is_err());
+}
+
+#[test]
+fn test_dataset_validate() {
+    let dataset = Dataset::parse_from_dataset_schema_size(DATASET, SCHEMA, SIZE).expect("dataset");
+    dataset.validate().expect("valid size");
+}